@@ -5,7 +5,7 @@ extern crate tauri;
 
 use crate::constants;
 use crate::error::Result;
-use reqwest::Client as Reqwest;
+use crate::settings::SettingsStore;
 use serde::{Deserialize, Serialize};
 use tauri::{Invoke, Runtime};
 
@@ -57,9 +57,9 @@ struct LatestVersion {
 }
 
 #[tauri::command]
-async fn get_latest_version() -> Result<LatestVersion> {
-    Ok(Reqwest::builder()
-        .build()?
+async fn get_latest_version(settings: tauri::State<'_, SettingsStore>) -> Result<LatestVersion> {
+    Ok(settings
+        .create_reqwest()?
         .get("https://raw.githubusercontent.com/WxWatch/gacha-tracker/main/manifest.json")
         .send()
         .await?
@@ -68,12 +68,16 @@ async fn get_latest_version() -> Result<LatestVersion> {
 }
 
 #[tauri::command]
-async fn update_app(latest_version: LatestVersion) -> Result<()> {
+async fn update_app(
+    settings: tauri::State<'_, SettingsStore>,
+    latest_version: LatestVersion,
+) -> Result<()> {
     use futures::stream::TryStreamExt;
     use std::env::current_exe;
     use std::fs::{remove_file, rename, File};
     use std::io::Write;
     use std::path::PathBuf;
+    use std::time::Duration;
 
     let current_exe = dbg!(current_exe()?);
     let current_dir = current_exe.parent().unwrap();
@@ -82,10 +86,14 @@ async fn update_app(latest_version: LatestVersion) -> Result<()> {
     let out_file = PathBuf::from(current_dir).join(latest_version.asset.name);
     let mut out_file = File::create(out_file)?;
 
-    let response = Reqwest::builder()
-        .build()?
+    // The release asset can take much longer than a regular response
+    const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+    let response = settings
+        .create_reqwest()?
         .get("https://hoyo-gacha.lgou2w.com/release/download")
         .query(&[("id", latest_version.id.to_string())])
+        .timeout(DOWNLOAD_TIMEOUT)
         .send()
         .await?
         .error_for_status()?;
@@ -5,6 +5,10 @@ extern crate shadow_rs;
 pub const ID: &str = "com.wxwatch.gacha.tracker";
 pub const NAME: &str = "Gacha Tracker";
 pub const DATABASE: &str = "com.wxwatch.gacha.tracker.db";
+/// Marker file next to the executable, that keeps the database there
+pub const PORTABLE: &str = "com.wxwatch.gacha.tracker.portable";
+pub const SETTINGS: &str = "com.wxwatch.gacha.tracker.settings.json";
+pub const RESPONSE_ARCHIVE: &str = "com.wxwatch.gacha.tracker.responses";
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const AUTHOR: &str = env!("CARGO_PKG_AUTHORS");
@@ -114,7 +114,7 @@ pub trait ReadCacheAddrExt: Read {
     ) -> Result<[CacheAddr; LENGTH]> {
         let mut addrs = [0u32; LENGTH];
         self.read_u32_into::<T>(&mut addrs)?;
-        Ok(addrs.map(|addr| CacheAddr(addr)))
+        Ok(addrs.map(CacheAddr))
     }
 }
 
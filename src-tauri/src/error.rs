@@ -57,6 +57,15 @@ pub enum Error {
     message: String
   },
 
+  #[error("Gacha record response mismatch at {path:?}: {message}")]
+  GachaRecordResponse {
+    path: String,
+    message: String
+  },
+
+  #[error("Response archive not found: {0:?}")]
+  ResponseArchiveNotFound(String),
+
   #[allow(unused)]
   #[error("Gacha record fetcher channel send error")]
   GachaRecordFetcherChannelSend,
@@ -80,6 +89,29 @@ pub enum Error {
 
   #[error("Account not found")]
   AccountNotFound,
+
+  // Storage
+
+  #[error("Database schema version {current} is newer than the supported {latest}")]
+  DatabaseSchemaTooNew { current: u32, latest: u32 },
+
+  #[error("Database migration {version} ({name}) failed: {message}")]
+  DatabaseMigration {
+    version: u32,
+    name: String,
+    message: String
+  },
+
+  #[error("Illegal gacha record cursor: {0:?}")]
+  IllegalGachaRecordCursor(String),
+
+  // Task
+
+  #[error("Task cancelled")]
+  TaskCancelled,
+
+  #[error("Task already running")]
+  TaskAlreadyRunning,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -106,12 +138,19 @@ impl_error_identifiers! {
   VacantGachaUrl                => VACANT_GACHA_URL,
   TimeoutdGachaUrl              => TIMEOUTD_GACHA_URL,
   GachaRecordRetcode            => GACHA_RECORD_RETCODE,
+  GachaRecordResponse           => GACHA_RECORD_RESPONSE,
+  ResponseArchiveNotFound       => RESPONSE_ARCHIVE_NOT_FOUND,
   GachaRecordFetcherChannelSend => GACHA_RECORD_FETCHER_CHANNEL_SEND,
   GachaRecordFetcherChannelJoin => GACHA_RECORD_FETCHER_CHANNEL_JOIN,
   UIGFOrSRGFMismatchedUID       => UIGF_OR_SRGF_MISMATCHED_UID,
   UIGFOrSRGFInvalidField        => UIGF_OR_SRGF_INVALID_FIELD,
   AccountAlreadyExists          => ACCOUNT_ALREADY_EXISTS,
-  AccountNotFound               => ACCOUNT_NOT_FOUND
+  AccountNotFound               => ACCOUNT_NOT_FOUND,
+  DatabaseSchemaTooNew          => DATABASE_SCHEMA_TOO_NEW,
+  DatabaseMigration             => DATABASE_MIGRATION,
+  IllegalGachaRecordCursor      => ILLEGAL_GACHA_RECORD_CURSOR,
+  TaskCancelled                 => TASK_CANCELLED,
+  TaskAlreadyRunning            => TASK_ALREADY_RUNNING
 }
 
 impl serde::Serialize for Error {
@@ -0,0 +1,314 @@
+extern crate serde_json;
+extern crate time;
+extern crate tracing;
+extern crate url;
+
+use crate::error::{Error, Result};
+use crate::storage::entity_account::AccountFacet;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::{debug, warn};
+use url::Url;
+
+/// Query keys and body fields that authorize the request, never archived
+const REDACTED_KEYS: &[&str] = &["authkey", "recordId", "record_id"];
+const REDACTED: &str = "<redacted>";
+
+const PULL_FILE: &str = "pull.json";
+
+/// Response Archive Settings
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ResponseArchiveSettings {
+    /// Keep the raw page responses of every pull, for troubleshooting
+    pub enabled: bool,
+    /// Number of pulls kept, the oldest are removed first
+    pub max_pulls: usize,
+}
+
+impl Default for ResponseArchiveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_pulls: 20,
+        }
+    }
+}
+
+/// Archived Pull
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedPull {
+    pub pull_id: String,
+    pub facet: AccountFacet,
+    pub uid: String,
+    /// Rfc3339
+    pub start_time: String,
+}
+
+/// Response Archive
+///   Raw page responses of the recent pulls, one directory per pull.
+#[derive(Debug, Clone)]
+pub struct ResponseArchive {
+    directory: PathBuf,
+    settings: ResponseArchiveSettings,
+}
+
+impl ResponseArchive {
+    pub fn new<P: AsRef<Path>>(directory: P, settings: ResponseArchiveSettings) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            settings,
+        }
+    }
+
+    /// Start archiving a pull, none when disabled.
+    ///   The oldest pulls beyond the limit are removed.
+    pub fn start_pull(&self, facet: &AccountFacet, uid: &str) -> Result<Option<PullArchive>> {
+        if !self.settings.enabled {
+            return Ok(None);
+        }
+
+        let start_time = OffsetDateTime::now_utc();
+        let pull_id = format!(
+            "{}-{facet}-{uid}",
+            start_time.unix_timestamp_nanos() / 1_000_000
+        );
+        self.rotate(self.settings.max_pulls.saturating_sub(1))?;
+
+        let directory = self.directory.join(&pull_id);
+        fs::create_dir_all(&directory)?;
+        let pull = ArchivedPull {
+            pull_id,
+            facet: facet.clone(),
+            uid: uid.to_owned(),
+            start_time: start_time.format(&Rfc3339).map_err(time::Error::from)?,
+        };
+        serde_json::to_writer_pretty(File::create(directory.join(PULL_FILE))?, &pull)?;
+
+        debug!("Archive pull responses to: {}", directory.display());
+        Ok(Some(PullArchive {
+            directory,
+            pages: Arc::new(AtomicU32::new(0)),
+        }))
+    }
+
+    /// Archived pulls, oldest first
+    pub fn find_pulls(&self) -> Result<Vec<ArchivedPull>> {
+        let mut pulls = Vec::new();
+        for directory in self.pull_directories()? {
+            let file = File::open(directory.join(PULL_FILE))?;
+            pulls.push(serde_json::from_reader(file)?);
+        }
+        Ok(pulls)
+    }
+
+    /// Write the pull and all of its pages as a single json document
+    pub fn export_pull(&self, pull_id: &str, writer: impl Write) -> Result<()> {
+        // The pull id must name a directory of the archive, nothing else
+        let directory = self
+            .pull_directories()?
+            .into_iter()
+            .find(|directory| directory.file_name().and_then(|name| name.to_str()) == Some(pull_id))
+            .ok_or_else(|| Error::ResponseArchiveNotFound(pull_id.to_owned()))?;
+
+        let pull: serde_json::Value =
+            serde_json::from_reader(File::open(directory.join(PULL_FILE))?)?;
+
+        let mut page_files = fs::read_dir(&directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.file_name().and_then(|name| name.to_str()) != Some(PULL_FILE))
+            .collect::<Vec<_>>();
+        page_files.sort();
+
+        let mut pages = Vec::with_capacity(page_files.len());
+        for page_file in page_files {
+            let page: serde_json::Value = serde_json::from_reader(File::open(page_file)?)?;
+            pages.push(page);
+        }
+
+        let document = serde_json::json!({ "pull": pull, "pages": pages });
+        Ok(serde_json::to_writer_pretty(writer, &document)?)
+    }
+
+    /// Pull directories, oldest first
+    fn pull_directories(&self) -> Result<Vec<PathBuf>> {
+        if !self.directory.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        // Named after the start time in millis, so that the names sort by age
+        let mut directories = fs::read_dir(&self.directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.join(PULL_FILE).is_file())
+            .collect::<Vec<_>>();
+        directories.sort();
+        Ok(directories)
+    }
+
+    /// Remove the oldest pulls until at most `keep` remain
+    fn rotate(&self, keep: usize) -> Result<()> {
+        let directories = self.pull_directories()?;
+        let excess = directories.len().saturating_sub(keep);
+        for directory in directories.into_iter().take(excess) {
+            debug!("Remove archived pull: {}", directory.display());
+            fs::remove_dir_all(directory)?;
+        }
+        Ok(())
+    }
+}
+
+/// Pull Archive
+///   Where the pages of a single pull are archived.
+#[derive(Debug, Clone)]
+pub struct PullArchive {
+    directory: PathBuf,
+    pages: Arc<AtomicU32>,
+}
+
+impl PartialEq for PullArchive {
+    fn eq(&self, other: &Self) -> bool {
+        self.directory == other.directory
+    }
+}
+
+impl Eq for PullArchive {}
+
+impl PullArchive {
+    /// Archive a raw page response with its request, the authorization redacted.
+    ///   Never fails the pull, a failure is only logged.
+    pub fn save_page(
+        &self,
+        method: &str,
+        url: &Url,
+        body: Option<serde_json::Value>,
+        status: u16,
+        response: &str,
+    ) {
+        let page = self.pages.fetch_add(1, Ordering::Relaxed) + 1;
+        let file = self.directory.join(format!("page-{page:05}.json"));
+
+        let result = (|| -> Result<()> {
+            let time = OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .map_err(time::Error::from)?;
+            let document = serde_json::json!({
+                "time": time,
+                "method": method,
+                "url": redact_url(url).as_str(),
+                "body": body.map(redact_body),
+                "status": status,
+                "response": response,
+            });
+            serde_json::to_writer_pretty(File::create(&file)?, &document)?;
+            Ok(())
+        })();
+
+        if let Err(error) = result {
+            warn!("Unable to archive response to {}: {error}", file.display());
+        }
+    }
+}
+
+fn redact_url(url: &Url) -> Url {
+    let mut url = url.clone();
+    let queries = url
+        .query_pairs()
+        .map(|(key, value)| {
+            let value = if REDACTED_KEYS.contains(&key.as_ref()) {
+                REDACTED.into()
+            } else {
+                value
+            };
+            (key.into_owned(), value.into_owned())
+        })
+        .collect::<Vec<_>>();
+
+    if !queries.is_empty() {
+        url.query_pairs_mut().clear().extend_pairs(queries);
+    }
+    url
+}
+
+fn redact_body(mut body: serde_json::Value) -> serde_json::Value {
+    if let Some(object) = body.as_object_mut() {
+        for key in REDACTED_KEYS {
+            if let Some(value) = object.get_mut(*key) {
+                *value = REDACTED.into();
+            }
+        }
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ResponseArchive, ResponseArchiveSettings};
+    use crate::error::Error;
+    use crate::storage::entity_account::AccountFacet;
+    use serde_json::json;
+
+    #[test]
+    fn redacts_and_rotates_pulls() {
+        let directory =
+            std::env::temp_dir().join(format!("response-archive-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let archive = ResponseArchive::new(
+            &directory,
+            ResponseArchiveSettings {
+                enabled: true,
+                max_pulls: 2,
+            },
+        );
+
+        let mut pull_ids = Vec::new();
+        for _ in 0..3 {
+            let pull = archive
+                .start_pull(&AccountFacet::Genshin, "100000001")
+                .unwrap()
+                .unwrap();
+            let url = url::Url::parse("https://public-operation-hk4e-sg.hoyoverse.com/gacha_info/api/getGachaLog?authkey=secret&gacha_type=301").unwrap();
+            pull.save_page(
+                "GET",
+                &url,
+                Some(json!({ "recordId": "secret" })),
+                200,
+                "{}",
+            );
+            pull_ids.push(
+                archive
+                    .find_pulls()
+                    .unwrap()
+                    .last()
+                    .unwrap()
+                    .pull_id
+                    .clone(),
+            );
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let pulls = archive.find_pulls().unwrap();
+        assert_eq!(
+            pulls.iter().map(|pull| &pull.pull_id).collect::<Vec<_>>(),
+            pull_ids[1..].iter().collect::<Vec<_>>()
+        );
+        assert!(matches!(
+            archive.export_pull(&pull_ids[0], Vec::new()),
+            Err(Error::ResponseArchiveNotFound(_))
+        ));
+
+        let mut exported = Vec::new();
+        archive.export_pull(&pull_ids[2], &mut exported).unwrap();
+        let exported = String::from_utf8(exported).unwrap();
+        assert!(!exported.contains("secret"));
+        assert!(exported.contains("gacha_type=301"));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -138,6 +138,10 @@ embedded!(
   wuthering_waves {
     WutheringWaves,
     EN_US = "en-us" -> "./en-us.json"
+  },
+  zenless_zone_zero {
+    ZenlessZoneZero,
+    EN_US = "en-us" -> "./zenlesszonezero-en-us.json"
   }
 );
 
@@ -156,3 +160,14 @@ pub fn id(
 ) -> Option<&'static GachaDictionaryEntry<'static>> {
     dictionary(facet, lang).and_then(|dictionary| dictionary.id(item_id))
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn embedded_dictionaries_are_readable() {
+        super::genshin_impact::validation_lazy_read();
+        super::star_rail::validation_lazy_read();
+        super::wuthering_waves::validation_lazy_read();
+        super::zenless_zone_zero::validation_lazy_read();
+    }
+}
@@ -11,6 +11,8 @@ pub mod embedded;
 pub enum Category {
     Character,
     Weapon,
+    /// Zenless Zone Zero only
+    Bangboo,
 }
 
 #[derive(Debug)]
@@ -0,0 +1,261 @@
+extern crate serde_json;
+
+use super::GachaRecord;
+use crate::error::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Keyed Gacha Record
+///   A fetched or stored record, serialized with its key.
+#[derive(Debug, Clone)]
+pub struct KeyedGachaRecord {
+    pub key: String,
+    pub record: serde_json::Value,
+}
+
+impl KeyedGachaRecord {
+    pub fn new<T: GachaRecord + Serialize>(record: &T) -> Result<Self> {
+        Ok(Self {
+            key: record.key(),
+            record: serde_json::to_value(record)?,
+        })
+    }
+}
+
+/// Gacha Record Diff
+///   What saving the fetched records of a gacha type would change.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordDiff {
+    pub gacha_type: String,
+    /// Fetched and not stored yet
+    pub new: Vec<serde_json::Value>,
+    /// Fetched and already stored
+    pub stored: Vec<serde_json::Value>,
+    /// Stored and missing from the server window
+    pub missing: Vec<serde_json::Value>,
+}
+
+/// Gacha Record Pull Diff
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordPullDiff {
+    pub gacha_types: Vec<GachaRecordDiff>,
+}
+
+/// Diff the fetched records of a gacha type against the stored ones.
+///   Records sharing a key are matched by count, so that identical pulls are not collapsed.
+pub fn diff_gacha_records(
+    gacha_type: &str,
+    fetched: Vec<KeyedGachaRecord>,
+    stored: Vec<KeyedGachaRecord>,
+) -> GachaRecordDiff {
+    let mut stored_by_key: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
+    for record in stored {
+        stored_by_key
+            .entry(record.key)
+            .or_default()
+            .push(record.record);
+    }
+
+    let mut diff = GachaRecordDiff {
+        gacha_type: gacha_type.to_owned(),
+        ..Default::default()
+    };
+
+    let mut matched: HashMap<String, usize> = HashMap::new();
+    for record in fetched {
+        let count = matched.entry(record.key.clone()).or_default();
+        let stored_count = stored_by_key.get(&record.key).map_or(0, Vec::len);
+        if *count < stored_count {
+            *count += 1;
+            diff.stored.push(record.record);
+        } else {
+            diff.new.push(record.record);
+        }
+    }
+
+    for (key, records) in stored_by_key {
+        let count = matched.get(&key).copied().unwrap_or_default();
+        diff.missing.extend(records.into_iter().skip(count));
+    }
+
+    diff
+}
+
+/// Stored Gacha Record Keys
+///   Keys of the stored records of a gacha type, matched by count.
+#[derive(Debug, Clone, Default)]
+pub struct StoredGachaRecordKeys(HashMap<String, usize>);
+
+impl StoredGachaRecordKeys {
+    pub fn new(records: &[KeyedGachaRecord]) -> Self {
+        let mut keys: HashMap<String, usize> = HashMap::new();
+        for record in records {
+            *keys.entry(record.key.clone()).or_default() += 1;
+        }
+        Self(keys)
+    }
+
+    /// Match the key against a stored record not matched yet, false when there is none
+    pub fn take(&mut self, key: &str) -> bool {
+        match self.0.get_mut(key) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Gacha Record Gap
+///   A run of records the server has and storage lacks, older than a stored one.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordGap {
+    pub newest: serde_json::Value,
+    pub oldest: serde_json::Value,
+    pub count: u64,
+}
+
+/// Gacha Record Repair
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordRepair {
+    pub gacha_type: String,
+    /// Records newer than every stored one
+    pub new: u64,
+    /// Holes left by earlier pulls, newest first
+    pub gaps: Vec<GachaRecordGap>,
+}
+
+/// Gacha Record Pull Repair
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordPullRepair {
+    pub gacha_types: Vec<GachaRecordRepair>,
+}
+
+/// Find the runs of fetched records (newest first) missing from the stored ones.
+///   The leading run is new, every later one is a gap.
+pub fn find_gacha_record_gaps(
+    gacha_type: &str,
+    fetched: Vec<KeyedGachaRecord>,
+    stored: &[KeyedGachaRecord],
+) -> GachaRecordRepair {
+    let mut stored_keys = StoredGachaRecordKeys::new(stored);
+    let mut repair = GachaRecordRepair {
+        gacha_type: gacha_type.to_owned(),
+        ..Default::default()
+    };
+
+    let mut reached_stored = false;
+    let mut run = Vec::new();
+    for record in fetched {
+        if stored_keys.take(&record.key) {
+            reached_stored = true;
+            close_gap(&mut run, &mut repair.gaps);
+        } else if reached_stored {
+            run.push(record.record);
+        } else {
+            repair.new += 1;
+        }
+    }
+    close_gap(&mut run, &mut repair.gaps);
+
+    repair
+}
+
+fn close_gap(run: &mut Vec<serde_json::Value>, gaps: &mut Vec<GachaRecordGap>) {
+    if let (Some(newest), Some(oldest)) = (run.first(), run.last()) {
+        gaps.push(GachaRecordGap {
+            newest: newest.clone(),
+            oldest: oldest.clone(),
+            count: run.len() as u64,
+        });
+    }
+    run.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_gacha_records, find_gacha_record_gaps, KeyedGachaRecord};
+    use crate::gacha::hoyoverse::genshin::GenshinGachaRecord;
+    use crate::gacha::kuro::wutheringwaves::WutheringWavesGachaRecord;
+    use serde_json::json;
+
+    #[test]
+    fn diff_matches_pulls_by_natural_key() {
+        let records = |records: &[(&str, u32)]| {
+            records
+                .iter()
+                .map(|(time, ordinal)| {
+                    let record: WutheringWavesGachaRecord = serde_json::from_value(json!({
+                        "cardPoolType": "Featured Resonator Convene",
+                        "resourceId": 21050043,
+                        "qualityLevel": 3,
+                        "resourceType": "Weapons",
+                        "name": "Sword of Voyager",
+                        "count": 1,
+                        "time": time,
+                        "ordinal": ordinal,
+                    }))
+                    .unwrap();
+                    KeyedGachaRecord::new(&record).unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Two identical pulls of a second fetched, the older one stored, plus one stored
+        // out of the window
+        let fetched = records(&[("2024-05-25 10:00:00", 1), ("2024-05-25 10:00:00", 0)]);
+        let stored = records(&[("2024-05-25 10:00:00", 0), ("2023-01-01 10:00:00", 0)]);
+
+        let diff = diff_gacha_records("1", fetched, stored);
+        assert_eq!(diff.new.len(), 1);
+        assert_eq!(diff.new[0]["ordinal"], 1);
+        assert_eq!(diff.stored.len(), 1);
+        assert_eq!(diff.stored[0]["ordinal"], 0);
+        assert_eq!(diff.missing.len(), 1);
+        assert_eq!(diff.missing[0]["time"], "2023-01-01 10:00:00");
+    }
+
+    #[test]
+    fn repair_finds_gaps_below_the_stored_records() {
+        // Newest first
+        let ids: Vec<String> = (0..10).rev().map(|n| (1000 + n).to_string()).collect();
+        let keyed = |ids: &[String]| {
+            ids.iter()
+                .map(|id| {
+                    let record: GenshinGachaRecord = serde_json::from_value(json!({
+                        "id": id,
+                        "uid": "100000001",
+                        "gacha_type": "301",
+                        "item_id": "",
+                        "count": "1",
+                        "time": "2023-05-01 12:00:00",
+                        "name": "Cool Steel",
+                        "lang": "en-us",
+                        "item_type": "Weapon",
+                        "rank_type": "3",
+                    }))
+                    .unwrap();
+                    KeyedGachaRecord::new(&record).unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // The two newest are new, 4..6 and the oldest are holes
+        let stored = [&ids[2..4], &ids[6..9]].concat();
+        let repair = find_gacha_record_gaps("301", keyed(&ids), &keyed(&stored));
+
+        assert_eq!(repair.new, 2);
+        assert_eq!(repair.gaps.len(), 2);
+        assert_eq!(repair.gaps[0].count, 2);
+        assert_eq!(repair.gaps[0].newest["id"], ids[4].as_str());
+        assert_eq!(repair.gaps[0].oldest["id"], ids[5].as_str());
+        assert_eq!(repair.gaps[1].count, 1);
+        assert_eq!(repair.gaps[1].oldest["id"], ids[9].as_str());
+    }
+}
@@ -16,6 +16,11 @@ pub trait GameDataDirectoryFinder {
 pub trait GachaRecord: Any {
     fn id(&self) -> String;
     fn as_any(&self) -> &dyn Any;
+
+    /// Identifies the record both fetched and stored, the id unless the server has none
+    fn key(&self) -> String {
+        self.id()
+    }
 }
 
 impl dyn GachaRecord {
@@ -25,6 +30,107 @@ impl dyn GachaRecord {
     }
 }
 
+/// Gacha Record Fetcher Channel Fragment
+///   Shared by every fetcher channel. Every fragment of a gacha type carries it,
+///   the counts are cumulative within that gacha type.
+#[allow(unused)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GachaRecordFetcherChannelFragment<T: GachaRecord + Sized + Serialize + Send + Sync> {
+    #[serde(rename_all = "camelCase")]
+    Sleeping {
+        gacha_type: String,
+        delay_millis: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Ready {
+        gacha_type: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Pagination {
+        gacha_type: String,
+        page: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    Data {
+        gacha_type: String,
+        records: Vec<T>,
+        /// Records fetched so far, this batch included
+        total: u64,
+    },
+    /// Sent by the pull receiver after saving a batch, only when saving to storage
+    #[serde(rename_all = "camelCase")]
+    Saved {
+        gacha_type: String,
+        inserted: u64,
+        duplicates: u64,
+        total_inserted: u64,
+        total_duplicates: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Retrying {
+        gacha_type: String,
+        attempt: u32,
+        delay_millis: u64,
+    },
+    Checkpoint(GachaRecordCheckpoint),
+    /// UTC offset in hours of the server the records are from, sent before them
+    TimeZone(i8),
+    #[serde(rename_all = "camelCase")]
+    Finished {
+        gacha_type: String,
+        total: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Cancelled {
+        gacha_type: String,
+    },
+    /// The last fragment of a completed pull
+    Summary(GachaRecordPullSummary),
+}
+
+/// Gacha Record Pull Summary
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordPullSummary {
+    pub gacha_types: Vec<GachaRecordPullCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordPullCount {
+    pub gacha_type: String,
+    pub fetched: u64,
+    /// Filled in by the pull receiver, none when not saving to storage
+    pub inserted: Option<u64>,
+    pub duplicates: Option<u64>,
+}
+
+impl GachaRecordPullSummary {
+    pub fn push(&mut self, gacha_type: &str, fetched: u64) {
+        self.gacha_types.push(GachaRecordPullCount {
+            gacha_type: gacha_type.to_owned(),
+            fetched,
+            inserted: None,
+            duplicates: None,
+        });
+    }
+}
+
+/// Gacha Record Checkpoint
+///   Where the backwards pagination of a gacha type stopped,
+///   so that an interrupted pull can be resumed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordCheckpoint {
+    pub gacha_type: String,
+    /// The `end_id` cursor of the next page
+    pub end_id: String,
+    /// The walk stops at this id, none to walk until the history end
+    pub floor_end_id: Option<String>,
+    pub reached_end: bool,
+}
+
 /// Gacha Url
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GachaUrl {
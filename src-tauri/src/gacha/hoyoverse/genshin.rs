@@ -1,24 +1,35 @@
 extern crate async_trait;
 extern crate reqwest;
 extern crate serde;
+extern crate serde_json;
 
 use std::any::Any;
 use std::cmp::Ordering;
+use std::fs::File;
 use std::path::{Path, PathBuf};
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::gacha::diff::KeyedGachaRecord;
 use crate::gacha::hoyoverse::utilities::lookup_mihoyo_dir;
+use crate::gacha::policy::FetchPolicy;
+use crate::gacha::provider::{ExportFormat, GachaProvider, PullReceiver, PullRequest};
 use crate::gacha::utilities::{
     lookup_gacha_urls_from_endpoint, lookup_path_line_from_keyword, lookup_valid_cache_data_dir,
 };
 use crate::gacha::{dict, GachaRecord, GachaUrl, GachaUrlFinder, GameDataDirectoryFinder};
+use crate::gacha::{uigf, uigf4};
 use crate::storage::entity_account::AccountFacet;
+use crate::storage::Storage;
 use async_trait::async_trait;
 use reqwest::Client as Reqwest;
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
-use super::hoyoverse::{GachaRecordFetcherChannel, HoyoverseGachaRecordFetcher};
-use super::utilities::fetch_gacha_records;
+use super::hoyoverse::{
+    create_fetcher_channel, GachaRecordFetcherChannel, HoyoverseGachaRecordFetcher,
+    HoyoverseGachaRecordPage,
+};
+use super::utilities::{fetch_gacha_records, find_hoyoverse_gacha_url_and_validate_consistency};
 
 #[derive(Default, Deserialize)]
 pub struct GenshinGacha;
@@ -36,7 +47,7 @@ impl GameDataDirectoryFinder for GenshinGacha {
 
         let output_log = mihoyo_dir.join(INTERNATIONAL_OUTPUT_LOG);
         if let Some(directory) =
-            lookup_path_line_from_keyword(&output_log, INTERNATIONAL_DIR_KEYWORD)?
+            lookup_path_line_from_keyword(output_log, INTERNATIONAL_DIR_KEYWORD)?
         {
             directories.push(directory);
         }
@@ -45,10 +56,21 @@ impl GameDataDirectoryFinder for GenshinGacha {
     }
 }
 
+/// Gacha Types
+///   Pulled gacha type -> stored gacha types. 301 also returns 400 records.
+pub const GACHA_TYPE_MAPPINGS: &[(&str, &[&str])] = &[
+    ("100", &["100"]),
+    ("200", &["200"]),
+    ("301", &["301", "400"]),
+    ("302", &["302"]),
+    ("500", &["500"]),
+];
+
 /// Gacha Url
 
 const ENDPOINT: &str = "/api/getGachaLog?";
 // const ENDPOINT: &str = "e20190909gacha-v2";
+const GACHA_TYPE_KEY: &str = "gacha_type";
 
 impl GachaUrlFinder for GenshinGacha {
     fn find_gacha_urls<P: AsRef<Path>>(&self, game_data_dir: P) -> Result<Vec<GachaUrl>> {
@@ -102,6 +124,16 @@ pub(crate) struct GenshinGachaRecordPagination {
     region: String,
 }
 
+/// UTC offset in hours of the server region, e.g. `os_usa`. The Genshin Impact
+/// pagination has no `region_time_zone` unlike the Star Rail one.
+fn region_time_zone(region: &str) -> i8 {
+    match region {
+        "os_usa" => -5,
+        "os_euro" => 1,
+        _ => 8,
+    }
+}
+
 #[async_trait]
 impl HoyoverseGachaRecordFetcher for GenshinGacha {
     type Target = GenshinGachaRecord;
@@ -112,26 +144,35 @@ impl HoyoverseGachaRecordFetcher for GenshinGacha {
         gacha_url: &str,
         gacha_type: Option<&str>,
         end_id: Option<&str>,
-    ) -> Result<Option<Vec<Self::Target>>> {
+        policy: &FetchPolicy,
+    ) -> Result<Option<HoyoverseGachaRecordPage<Self::Target>>> {
         let response = fetch_gacha_records::<GenshinGachaRecordPagination>(
-            reqwest, ENDPOINT, gacha_url, gacha_type, end_id,
+            reqwest,
+            ENDPOINT,
+            GACHA_TYPE_KEY,
+            gacha_url,
+            gacha_type,
+            end_id,
+            policy,
         )
         .await?;
 
-        Ok(response.data.map(|pagination| {
-            pagination
+        Ok(response.data.map(|pagination| HoyoverseGachaRecordPage {
+            time_zone: region_time_zone(&pagination.region),
+            records: pagination
                 .list
                 .into_iter()
                 .map(|mut record| {
+                    // Look up in the language the records were actually fetched in
                     if let Some(entry) =
-                        dict::embedded::name(&AccountFacet::Genshin, "en-us", &record.name)
+                        dict::embedded::name(&AccountFacet::Genshin, &record.lang, &record.name)
                     {
                         record.item_id = entry.item_id.to_string();
                     }
 
                     record
                 })
-                .collect()
+                .collect(),
         }))
     }
 
@@ -139,11 +180,12 @@ impl HoyoverseGachaRecordFetcher for GenshinGacha {
         &self,
         reqwest: &Reqwest,
         gacha_url: &str,
+        policy: &FetchPolicy,
     ) -> Result<Option<String>> {
         let result = self
-            .fetch_gacha_records(reqwest, gacha_url, None, None)
+            .fetch_gacha_records(reqwest, gacha_url, None, None, policy)
             .await?;
-        Ok(result.and_then(|gacha_records| gacha_records.first().map(|record| record.uid.clone())))
+        Ok(result.and_then(|page| page.records.first().map(|record| record.uid.clone())))
     }
 }
 
@@ -151,3 +193,154 @@ impl HoyoverseGachaRecordFetcher for GenshinGacha {
 impl GachaRecordFetcherChannel<GenshinGachaRecord> for GenshinGacha {
     type Fetcher = Self;
 }
+
+/// Gacha Provider
+
+#[async_trait]
+impl GachaProvider for GenshinGacha {
+    fn facet(&self) -> AccountFacet {
+        AccountFacet::Genshin
+    }
+
+    fn gacha_type_mappings(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        GACHA_TYPE_MAPPINGS
+    }
+
+    fn find_game_data_directories(&self) -> Result<Vec<PathBuf>> {
+        GameDataDirectoryFinder::find_game_data_directories(self)
+    }
+
+    fn find_gacha_urls(&self, game_data_dir: &Path) -> Result<Vec<GachaUrl>> {
+        GachaUrlFinder::find_gacha_urls(self, game_data_dir)
+    }
+
+    async fn find_valid_gacha_url(
+        &self,
+        reqwest: &Reqwest,
+        uid: &str,
+        gacha_urls: &[GachaUrl],
+        policy: &FetchPolicy,
+    ) -> Result<GachaUrl> {
+        find_hoyoverse_gacha_url_and_validate_consistency(
+            reqwest,
+            self,
+            &AccountFacet::Genshin,
+            uid,
+            gacha_urls,
+            policy,
+        )
+        .await
+    }
+
+    async fn find_last_query(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        gacha_types: &[&str],
+    ) -> Result<Option<String>> {
+        Ok(storage
+            .find_genshin_gacha_records_latest(uid, gacha_types)
+            .await?
+            .map(|record| record.id))
+    }
+
+    async fn find_stored_records(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        gacha_types: &[&str],
+    ) -> Result<Vec<KeyedGachaRecord>> {
+        let mut records = Vec::new();
+        for gacha_type in gacha_types {
+            for record in storage
+                .find_genshin_gacha_records(uid, Some(gacha_type), None)
+                .await?
+            {
+                records.push(KeyedGachaRecord::new(&record)?);
+            }
+        }
+        Ok(records)
+    }
+
+    async fn pull_gacha_records(
+        &self,
+        request: PullRequest,
+        receiver: &PullReceiver<'_>,
+    ) -> Result<()> {
+        create_fetcher_channel(
+            GenshinGacha,
+            GenshinGacha,
+            request,
+            receiver.find_checkpoints().await?,
+            |fragment| {
+                receiver.receive(fragment, |data| async move {
+                    receiver.storage.save_genshin_gacha_records(&data).await
+                })
+            },
+        )
+        .await
+    }
+
+    fn export_format(&self, format: ExportFormat) -> Option<(&'static str, &'static str)> {
+        Some(match format {
+            ExportFormat::Classic => ("Unified Standardized GenshinData Format", "UIGF"),
+            ExportFormat::Uigf4 => ("Uniform Interchangeable GachaLog Format", "UIGFv4"),
+        })
+    }
+
+    async fn import_gacha_records(&self, storage: &Storage, uid: &str, file: File) -> Result<u64> {
+        let value: serde_json::Value = serde_json::from_reader(file)?;
+
+        // UIGF v4 groups the accounts by game, earlier versions carry a single account
+        let gacha_records = if uigf4::UIGF4::is_uigf4(&value) {
+            let uigf = uigf4::UIGF4::from_value(value)?;
+            uigf4::convert_hk4e_to_offical(&uigf, uid)?
+        } else {
+            let mut uigf = uigf::UIGF::from_value(value)?;
+            if uigf.info.uid != uid {
+                return Err(Error::UIGFOrSRGFMismatchedUID {
+                    expected: uid.to_owned(),
+                    actual: uigf.info.uid,
+                });
+            }
+
+            uigf::convert_uigf_to_offical(&mut uigf)?
+        };
+
+        storage.save_genshin_gacha_records(&gacha_records).await
+    }
+
+    async fn export_gacha_records(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        format: ExportFormat,
+        now: &OffsetDateTime,
+        writer: File,
+    ) -> Result<()> {
+        let gacha_records = storage.find_genshin_gacha_records(uid, None, None).await?;
+        let lang = gacha_records
+            .first()
+            .map(|v| v.lang.clone())
+            .unwrap_or("en-us".to_owned());
+
+        match format {
+            ExportFormat::Classic => {
+                // convert to uigf and write
+                let uigf_list = uigf::convert_offical_to_uigf(&gacha_records)?;
+                let uigf = uigf::UIGF::new(uid.to_owned(), lang, now, uigf_list)?;
+                uigf.to_writer(writer, false)
+            }
+            ExportFormat::Uigf4 => {
+                let time_zone = storage
+                    .find_account_time_zone(&AccountFacet::Genshin, uid)
+                    .await?;
+
+                // convert to uigf v4 and write
+                let hk4e = uigf4::convert_offical_to_hk4e(uid, lang, time_zone, &gacha_records)?;
+                let uigf = uigf4::UIGF4::new(now).with_hk4e(vec![hk4e]);
+                uigf.to_writer(writer, false)
+            }
+        }
+    }
+}
@@ -4,16 +4,32 @@ extern crate reqwest;
 extern crate serde;
 extern crate time;
 extern crate tokio;
+extern crate tokio_util;
 extern crate url;
 
 use crate::error::{Error, Result};
-use crate::gacha::GachaRecord;
+use crate::gacha::policy::FetchPolicy;
+use crate::gacha::provider::{PullContext, PullRequest};
+use crate::gacha::retry::retry_with_backoff;
+use crate::gacha::task::{cancellable, cancellable_sleep};
+use crate::gacha::{
+    GachaRecord, GachaRecordCheckpoint, GachaRecordFetcherChannelFragment, GachaRecordPullSummary,
+};
 use async_trait::async_trait;
 use reqwest::Client as Reqwest;
 use serde::Serialize;
 use std::collections::BTreeMap;
 use std::future::Future;
 
+use super::utilities::compare_gacha_record_ids;
+
+/// Gacha Record Page
+pub struct HoyoverseGachaRecordPage<T> {
+    pub records: Vec<T>,
+    /// UTC offset in hours of the server, the record times are in it
+    pub time_zone: i8,
+}
+
 /// Gacha Record Fetcher
 #[async_trait]
 pub trait HoyoverseGachaRecordFetcher {
@@ -25,32 +41,22 @@ pub trait HoyoverseGachaRecordFetcher {
         gacha_url: &str,
         gacha_type: Option<&str>,
         end_id: Option<&str>,
-    ) -> Result<Option<Vec<Self::Target>>>;
+        policy: &FetchPolicy,
+    ) -> Result<Option<HoyoverseGachaRecordPage<Self::Target>>>;
 
     async fn fetch_gacha_records_any_uid(
         &self,
         reqwest: &Reqwest,
         gacha_url: &str,
+        policy: &FetchPolicy,
     ) -> Result<Option<String>>;
 }
 
-#[allow(unused)]
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub enum GachaRecordFetcherChannelFragment<T: GachaRecord + Sized + Serialize + Send + Sync> {
-    Sleeping,
-    Ready(String),
-    Pagination(u32),
-    Data(Vec<T>),
-    Finished,
-}
-
 pub async fn create_fetcher_channel<Record, FetcherChannel, F, Fut>(
     fetcher_channel: FetcherChannel,
-    reqwest: Reqwest,
     fetcher: FetcherChannel::Fetcher,
-    gacha_url: String,
-    gacha_type_and_last_end_id_mappings: BTreeMap<String, Option<String>>,
+    request: PullRequest,
+    checkpoints: BTreeMap<String, GachaRecordCheckpoint>,
     receiver_fn: F,
 ) -> Result<()>
 where
@@ -64,13 +70,12 @@ where
 
     let (sender, mut receiver) = channel(1);
     let task = spawn(async move {
+        let pull = PullContext::new(&request, &fetcher, &sender);
         fetcher_channel
             .pull_all_gacha_records(
-                &reqwest,
-                &fetcher,
-                &sender,
-                &gacha_url,
-                &gacha_type_and_last_end_id_mappings,
+                &pull,
+                &request.gacha_type_and_last_query_mappings,
+                &checkpoints,
             )
             .await
     });
@@ -89,106 +94,492 @@ pub trait GachaRecordFetcherChannel<T: GachaRecord + Sized + Serialize + Send +
 
     async fn pull_gacha_records(
         &self,
-        reqwest: &Reqwest,
-        fetcher: &Self::Fetcher,
-        sender: &tokio::sync::mpsc::Sender<GachaRecordFetcherChannelFragment<T>>,
-        gacha_url: &str,
+        pull: &PullContext<'_, Self::Fetcher, T>,
         gacha_type: &str,
         last_end_id: Option<&str>,
-    ) -> Result<()> {
-        const SLEEPING: u64 = 3;
-
-        sender
-            .send(GachaRecordFetcherChannelFragment::Ready(
-                gacha_type.to_owned(),
-            ))
+        checkpoint: Option<&GachaRecordCheckpoint>,
+    ) -> Result<u64> {
+        pull.sender
+            .send(GachaRecordFetcherChannelFragment::Ready {
+                gacha_type: gacha_type.to_owned(),
+            })
             .await
             .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
-        tokio::time::sleep(tokio::time::Duration::from_secs(SLEEPING)).await;
+        cancellable_sleep(pull.cancellation, pull.policy.page_delay()).await?;
 
-        let mut end_id = String::from("0");
         let mut pagination: u32 = 1;
+        let mut total: u64 = 0;
+
+        // Finish an interrupted walk first, so at most one gap is ever pending
+        if let Some(checkpoint) = checkpoint.filter(|checkpoint| !checkpoint.reached_end) {
+            self.walk_gacha_records(
+                pull,
+                gacha_type,
+                &checkpoint.end_id,
+                checkpoint.floor_end_id.as_deref(),
+                &mut pagination,
+                &mut total,
+            )
+            .await?;
+        }
+
+        self.walk_gacha_records(
+            pull,
+            gacha_type,
+            "0",
+            last_end_id,
+            &mut pagination,
+            &mut total,
+        )
+        .await?;
+
+        pull.sender
+            .send(GachaRecordFetcherChannelFragment::Finished {
+                gacha_type: gacha_type.to_owned(),
+                total,
+            })
+            .await
+            .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
+        Ok(total)
+    }
+
+    /// Walk backwards from `start_end_id` until `floor_end_id` or the history end,
+    /// sending a checkpoint after every page.
+    async fn walk_gacha_records(
+        &self,
+        pull: &PullContext<'_, Self::Fetcher, T>,
+        gacha_type: &str,
+        start_end_id: &str,
+        floor_end_id: Option<&str>,
+        pagination: &mut u32,
+        total: &mut u64,
+    ) -> Result<()> {
+        let retry_policy = pull.policy.retry_policy();
+        let mut end_id = start_end_id.to_owned();
 
         loop {
-            if pagination % 5 == 0 {
-                sender
-                    .send(GachaRecordFetcherChannelFragment::Sleeping)
+            // Stop between pages, the records already sent are kept
+            if pull.cancellation.is_cancelled() {
+                return Err(Error::TaskCancelled);
+            }
+
+            if pull.policy.is_burst_pause(*pagination) {
+                let delay = pull.policy.burst_pause();
+                pull.sender
+                    .send(GachaRecordFetcherChannelFragment::Sleeping {
+                        gacha_type: gacha_type.to_owned(),
+                        delay_millis: delay.as_millis() as u64,
+                    })
                     .await
                     .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
-                tokio::time::sleep(tokio::time::Duration::from_secs(SLEEPING)).await;
+                cancellable_sleep(pull.cancellation, delay).await?;
             }
 
-            sender
-                .send(GachaRecordFetcherChannelFragment::Pagination(pagination))
+            pull.sender
+                .send(GachaRecordFetcherChannelFragment::Pagination {
+                    gacha_type: gacha_type.to_owned(),
+                    page: *pagination,
+                })
                 .await
                 .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
-            let gacha_records = fetcher
-                .fetch_gacha_records(reqwest, gacha_url, Some(gacha_type), Some(&end_id))
-                .await?;
-            pagination += 1;
-
-            if let Some(gacha_records) = gacha_records {
-                if !gacha_records.is_empty() {
-                    end_id = gacha_records.last().unwrap().id().to_owned();
-
-                    let mut should_break = false;
-                    let data = if let Some(last) = last_end_id {
-                        let mut tmp = Vec::with_capacity(gacha_records.len());
-                        for record in gacha_records {
-                            if last.cmp(record.id().as_str()).is_lt() {
-                                tmp.push(record);
-                            } else {
-                                should_break = true;
-                            }
+            let page = cancellable(
+                pull.cancellation,
+                retry_with_backoff(
+                    &retry_policy,
+                    || {
+                        pull.fetcher.fetch_gacha_records(
+                            pull.reqwest,
+                            pull.gacha_url,
+                            Some(gacha_type),
+                            Some(&end_id),
+                            pull.policy,
+                        )
+                    },
+                    |attempt, delay| async move {
+                        pull.sender
+                            .send(GachaRecordFetcherChannelFragment::Retrying {
+                                gacha_type: gacha_type.to_owned(),
+                                attempt,
+                                delay_millis: delay.as_millis() as u64,
+                            })
+                            .await
+                            .map_err(|_| Error::GachaRecordFetcherChannelSend)
+                    },
+                ),
+            )
+            .await?;
+            *pagination += 1;
+
+            let mut reached_end = true;
+            if let Some(page) = page.filter(|page| !page.records.is_empty()) {
+                pull.sender
+                    .send(GachaRecordFetcherChannelFragment::TimeZone(page.time_zone))
+                    .await
+                    .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
+
+                let gacha_records = page.records;
+                end_id = gacha_records.last().unwrap().id().to_owned();
+
+                let mut should_break = false;
+                let data = if let Some(last) = floor_end_id {
+                    let mut tmp = Vec::with_capacity(gacha_records.len());
+                    for record in gacha_records {
+                        if compare_gacha_record_ids(last, &record.id()).is_lt() {
+                            tmp.push(record);
+                        } else {
+                            should_break = true;
                         }
-                        tmp
-                    } else {
-                        gacha_records
-                    };
-
-                    sender
-                        .send(GachaRecordFetcherChannelFragment::Data(data))
-                        .await
-                        .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
-
-                    if should_break {
-                        break;
-                    } else {
-                        tokio::time::sleep(tokio::time::Duration::from_secs(SLEEPING)).await;
-                        continue;
                     }
-                }
+                    tmp
+                } else {
+                    gacha_records
+                };
+
+                *total += data.len() as u64;
+                pull.sender
+                    .send(GachaRecordFetcherChannelFragment::Data {
+                        gacha_type: gacha_type.to_owned(),
+                        records: data,
+                        total: *total,
+                    })
+                    .await
+                    .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
+                reached_end = should_break;
+            }
+
+            pull.sender
+                .send(GachaRecordFetcherChannelFragment::Checkpoint(
+                    GachaRecordCheckpoint {
+                        gacha_type: gacha_type.to_owned(),
+                        end_id: end_id.clone(),
+                        floor_end_id: floor_end_id.map(str::to_owned),
+                        reached_end,
+                    },
+                ))
+                .await
+                .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
+
+            if reached_end {
+                break;
             }
 
-            break;
+            cancellable_sleep(pull.cancellation, pull.policy.page_delay()).await?;
         }
 
-        sender
-            .send(GachaRecordFetcherChannelFragment::Finished)
-            .await
-            .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
         Ok(())
     }
 
     async fn pull_all_gacha_records(
         &self,
-        reqwest: &Reqwest,
-        fetcher: &Self::Fetcher,
-        sender: &tokio::sync::mpsc::Sender<GachaRecordFetcherChannelFragment<T>>,
-        gacha_url: &str,
+        pull: &PullContext<'_, Self::Fetcher, T>,
         gacha_type_and_last_end_id_mappings: &BTreeMap<String, Option<String>>,
+        checkpoints: &BTreeMap<String, GachaRecordCheckpoint>,
     ) -> Result<()> {
+        let mut summary = GachaRecordPullSummary::default();
         for (gacha_type, last_end_id) in gacha_type_and_last_end_id_mappings {
-            self.pull_gacha_records(
-                reqwest,
-                fetcher,
-                sender,
-                gacha_url,
-                gacha_type,
-                last_end_id.as_deref(),
-            )
-            .await?;
+            let result = self
+                .pull_gacha_records(
+                    pull,
+                    gacha_type,
+                    last_end_id.as_deref(),
+                    checkpoints.get(gacha_type),
+                )
+                .await;
+
+            if let Err(Error::TaskCancelled) = result {
+                pull.sender
+                    .send(GachaRecordFetcherChannelFragment::Cancelled {
+                        gacha_type: gacha_type.to_owned(),
+                    })
+                    .await
+                    .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
+            }
+
+            summary.push(gacha_type, result?);
         }
+
+        pull.sender
+            .send(GachaRecordFetcherChannelFragment::Summary(summary))
+            .await
+            .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::create_fetcher_channel;
+    use crate::error::{Error, Result};
+    use crate::gacha::hoyoverse::genshin::{GenshinGacha, GenshinGachaRecord};
+    use crate::gacha::mock::{MockGachaServer, EXPIRED_AUTHKEY};
+    use crate::gacha::policy::FetchPolicy;
+    use crate::gacha::provider::PullRequest;
+    use crate::gacha::{GachaRecordCheckpoint, GachaRecordFetcherChannelFragment};
+    use crate::http::{create_reqwest, HttpClientConfig};
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+    use tokio_util::sync::CancellationToken;
+
+    type Fragments = Vec<GachaRecordFetcherChannelFragment<GenshinGachaRecord>>;
+
+    /// Ids of the given count of records, newest first
+    fn ids(count: u64) -> Vec<String> {
+        (0..count)
+            .rev()
+            .map(|n| (1_700_000_000_000_000_000 + n).to_string())
+            .collect()
+    }
+
+    fn records(ids: &[String]) -> Vec<Value> {
+        ids.iter()
+            .map(|id| {
+                json!({
+                    "id": id,
+                    "uid": "100000001",
+                    "gacha_type": "301",
+                    "item_id": "",
+                    "count": "1",
+                    "time": "2023-05-01 12:00:00",
+                    "name": "Cool Steel",
+                    "lang": "en-us",
+                    "item_type": "Weapon",
+                    "rank_type": "3",
+                })
+            })
+            .collect()
+    }
+
+    /// The host is replaced with the mock server by the policy
+    fn gacha_url(authkey: &str) -> String {
+        format!("https://public-operation-hk4e-sg.hoyoverse.com/gacha_info/api/getGachaLog?authkey_ver=1&lang=en&authkey={authkey}&gacha_type=301")
+    }
+
+    async fn pull(
+        policy: FetchPolicy,
+        gacha_url: String,
+        last_end_id: Option<String>,
+        checkpoints: BTreeMap<String, GachaRecordCheckpoint>,
+    ) -> (Result<()>, Fragments) {
+        let fragments = Mutex::new(Vec::new());
+        let result = create_fetcher_channel(
+            GenshinGacha,
+            GenshinGacha,
+            PullRequest {
+                reqwest: create_reqwest(&HttpClientConfig::default()).unwrap(),
+                uid: String::from("100000001"),
+                gacha_url,
+                gacha_type_and_last_query_mappings: BTreeMap::from([(
+                    String::from("301"),
+                    last_end_id,
+                )]),
+                policy,
+                cancellation: CancellationToken::new(),
+            },
+            checkpoints,
+            |fragment| {
+                fragments.lock().unwrap().push(fragment);
+                std::future::ready(Ok(()))
+            },
+        )
+        .await;
+
+        (result, fragments.into_inner().unwrap())
+    }
+
+    fn pulled_ids(fragments: &Fragments) -> Vec<String> {
+        fragments
+            .iter()
+            .filter_map(|fragment| match fragment {
+                GachaRecordFetcherChannelFragment::Data { records, .. } => Some(records),
+                _ => None,
+            })
+            .flatten()
+            .map(|record| record.id.clone())
+            .collect()
+    }
+
+    fn last_checkpoint(fragments: &Fragments) -> Option<&GachaRecordCheckpoint> {
+        fragments.iter().rev().find_map(|fragment| match fragment {
+            GachaRecordFetcherChannelFragment::Checkpoint(checkpoint) => Some(checkpoint),
+            _ => None,
+        })
+    }
+
+    #[tokio::test]
+    async fn pull_walks_every_page_until_history_end() {
+        let server = MockGachaServer::start().await;
+        let ids = ids(45);
+        server.add_hoyoverse_records("301", records(&ids));
+
+        let (result, fragments) = pull(
+            server.fetch_policy(),
+            gacha_url("valid"),
+            None,
+            BTreeMap::new(),
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(pulled_ids(&fragments), ids);
+        assert!(fragments.iter().any(|fragment| matches!(
+            fragment,
+            GachaRecordFetcherChannelFragment::Finished { gacha_type, total: 45 } if gacha_type == "301"
+        )));
+        match fragments.last() {
+            Some(GachaRecordFetcherChannelFragment::Summary(summary)) => {
+                assert_eq!(summary.gacha_types.len(), 1);
+                assert_eq!(summary.gacha_types[0].fetched, 45);
+                assert_eq!(summary.gacha_types[0].inserted, None);
+            }
+            fragment => panic!("Expected a summary, got {fragment:?}"),
+        }
+        assert!(last_checkpoint(&fragments).unwrap().reached_end);
+        // The os_usa region of the mock
+        assert!(fragments
+            .iter()
+            .any(|fragment| matches!(fragment, GachaRecordFetcherChannelFragment::TimeZone(-5))));
+
+        // 20 + 20 + 5 records, then an empty page
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].end_id.as_deref(), Some("0"));
+        assert_eq!(requests[1].end_id.as_deref(), Some(ids[19].as_str()));
+        assert_eq!(requests[2].end_id.as_deref(), Some(ids[39].as_str()));
+        assert!(requests.iter().all(|request| request.size == Some(20)));
+    }
+
+    #[tokio::test]
+    async fn pull_stops_at_last_end_id() {
+        let server = MockGachaServer::start().await;
+        let ids = ids(45);
+        server.add_hoyoverse_records("301", records(&ids));
+
+        let (result, fragments) = pull(
+            server.fetch_policy(),
+            gacha_url("valid"),
+            Some(ids[25].clone()),
+            BTreeMap::new(),
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(pulled_ids(&fragments), ids[..25]);
+        assert!(last_checkpoint(&fragments).unwrap().reached_end);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn pull_with_nothing_new_fetches_a_single_page() {
+        let server = MockGachaServer::start().await;
+        let ids = ids(10);
+        server.add_hoyoverse_records("301", records(&ids));
+
+        let (result, fragments) = pull(
+            server.fetch_policy(),
+            gacha_url("valid"),
+            Some(ids[0].clone()),
+            BTreeMap::new(),
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert!(pulled_ids(&fragments).is_empty());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn pull_resumes_from_unfinished_checkpoint() {
+        let server = MockGachaServer::start().await;
+        let ids = ids(45);
+        server.add_hoyoverse_records("301", records(&ids));
+
+        // A previous pull saved the first page and died
+        let checkpoints = BTreeMap::from([(
+            String::from("301"),
+            GachaRecordCheckpoint {
+                gacha_type: String::from("301"),
+                end_id: ids[19].clone(),
+                floor_end_id: None,
+                reached_end: false,
+            },
+        )]);
+        let (result, fragments) = pull(
+            server.fetch_policy(),
+            gacha_url("valid"),
+            Some(ids[0].clone()),
+            checkpoints,
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(pulled_ids(&fragments), ids[20..]);
+        assert_eq!(
+            server.requests()[0].end_id.as_deref(),
+            Some(ids[19].as_str())
+        );
+        assert!(last_checkpoint(&fragments).unwrap().reached_end);
+    }
+
+    #[tokio::test]
+    async fn pull_in_preferred_language() {
+        let server = MockGachaServer::start().await;
+        server.add_hoyoverse_records("301", records(&ids(5)));
+
+        let policy = FetchPolicy {
+            language: Some(String::from("zh-cn")),
+            ..server.fetch_policy()
+        };
+        let (result, _) = pull(policy, gacha_url("valid"), None, BTreeMap::new()).await;
+
+        assert!(result.is_ok());
+        assert!(server
+            .requests()
+            .iter()
+            .all(|request| request.lang.as_deref() == Some("zh-cn")));
+    }
+
+    #[tokio::test]
+    async fn pull_retries_rate_limited_requests() {
+        let server = MockGachaServer::start().await;
+        let ids = ids(5);
+        server.add_hoyoverse_records("301", records(&ids));
+        server.respond_retcodes(&[-110]);
+
+        let (result, fragments) = pull(
+            server.fetch_policy(),
+            gacha_url("valid"),
+            None,
+            BTreeMap::new(),
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(pulled_ids(&fragments), ids);
+        assert!(fragments.iter().any(|fragment| matches!(
+            fragment,
+            GachaRecordFetcherChannelFragment::Retrying { attempt: 1, .. }
+        )));
+    }
+
+    #[tokio::test]
+    async fn pull_fails_with_expired_authkey() {
+        let server = MockGachaServer::start().await;
+        server.add_hoyoverse_records("301", records(&ids(5)));
+
+        let (result, fragments) = pull(
+            server.fetch_policy(),
+            gacha_url(EXPIRED_AUTHKEY),
+            None,
+            BTreeMap::new(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::TimeoutdGachaUrl)));
+        assert!(pulled_ids(&fragments).is_empty());
+        // Not a transient error, never retried
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod genshin;
+#[allow(clippy::module_inception)]
 pub mod hoyoverse;
 pub mod starrail;
 pub mod utilities;
+pub mod zenlesszonezero;
@@ -1,24 +1,36 @@
 extern crate async_trait;
 extern crate reqwest;
 extern crate serde;
+extern crate serde_json;
 extern crate url;
 
 use std::any::Any;
 use std::cmp::Ordering;
+use std::fs::File;
 use std::path::{Path, PathBuf};
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::gacha::diff::KeyedGachaRecord;
 use crate::gacha::hoyoverse::utilities::{lookup_cognosphere_dir, lookup_mihoyo_dir};
+use crate::gacha::policy::FetchPolicy;
+use crate::gacha::provider::{ExportFormat, GachaProvider, PullReceiver, PullRequest};
 use crate::gacha::utilities::{
     lookup_gacha_urls_from_endpoint, lookup_path_line_from_keyword, lookup_valid_cache_data_dir,
 };
+use crate::gacha::{srgf, uigf4};
 use crate::gacha::{GachaRecord, GachaUrl, GachaUrlFinder, GameDataDirectoryFinder};
+use crate::storage::entity_account::AccountFacet;
+use crate::storage::Storage;
 use async_trait::async_trait;
 use reqwest::Client as Reqwest;
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
-use super::hoyoverse::{GachaRecordFetcherChannel, HoyoverseGachaRecordFetcher};
-use super::utilities::fetch_gacha_records;
+use super::hoyoverse::{
+    create_fetcher_channel, GachaRecordFetcherChannel, HoyoverseGachaRecordFetcher,
+    HoyoverseGachaRecordPage,
+};
+use super::utilities::{fetch_gacha_records, find_hoyoverse_gacha_url_and_validate_consistency};
 
 #[derive(Default, Deserialize)]
 pub struct StarRailGacha;
@@ -54,9 +66,21 @@ impl GameDataDirectoryFinder for StarRailGacha {
     }
 }
 
+/// Gacha Types
+///   Pulled gacha type -> stored gacha types.
+pub const GACHA_TYPE_MAPPINGS: &[(&str, &[&str])] = &[
+    ("1", &["1"]),
+    ("2", &["2"]),
+    ("11", &["11"]),
+    ("12", &["12"]),
+    ("21", &["21"]), // Collaboration character
+    ("22", &["22"]), // Collaboration light cone
+];
+
 /// Gacha Url
 
 const ENDPOINT: &str = "/common/gacha_record/api/getGachaLog?";
+const GACHA_TYPE_KEY: &str = "gacha_type";
 
 impl GachaUrlFinder for StarRailGacha {
     fn find_gacha_urls<P: AsRef<Path>>(&self, game_data_dir: P) -> Result<Vec<GachaUrl>> {
@@ -122,24 +146,35 @@ impl HoyoverseGachaRecordFetcher for StarRailGacha {
         gacha_url: &str,
         gacha_type: Option<&str>,
         end_id: Option<&str>,
-    ) -> Result<Option<Vec<Self::Target>>> {
+        policy: &FetchPolicy,
+    ) -> Result<Option<HoyoverseGachaRecordPage<Self::Target>>> {
         let response = fetch_gacha_records::<StarRailGachaRecordPagination>(
-            reqwest, ENDPOINT, gacha_url, gacha_type, end_id,
+            reqwest,
+            ENDPOINT,
+            GACHA_TYPE_KEY,
+            gacha_url,
+            gacha_type,
+            end_id,
+            policy,
         )
         .await?;
 
-        Ok(response.data.map(|pagination| pagination.list))
+        Ok(response.data.map(|pagination| HoyoverseGachaRecordPage {
+            records: pagination.list,
+            time_zone: pagination.region_time_zone,
+        }))
     }
 
     async fn fetch_gacha_records_any_uid(
         &self,
         reqwest: &Reqwest,
         gacha_url: &str,
+        policy: &FetchPolicy,
     ) -> Result<Option<String>> {
         let result = self
-            .fetch_gacha_records(reqwest, gacha_url, None, None)
+            .fetch_gacha_records(reqwest, gacha_url, None, None, policy)
             .await?;
-        Ok(result.and_then(|gacha_records| gacha_records.first().map(|record| record.uid.clone())))
+        Ok(result.and_then(|page| page.records.first().map(|record| record.uid.clone())))
     }
 }
 
@@ -147,3 +182,153 @@ impl HoyoverseGachaRecordFetcher for StarRailGacha {
 impl GachaRecordFetcherChannel<StarRailGachaRecord> for StarRailGacha {
     type Fetcher = Self;
 }
+
+/// Gacha Provider
+
+#[async_trait]
+impl GachaProvider for StarRailGacha {
+    fn facet(&self) -> AccountFacet {
+        AccountFacet::StarRail
+    }
+
+    fn gacha_type_mappings(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        GACHA_TYPE_MAPPINGS
+    }
+
+    fn find_game_data_directories(&self) -> Result<Vec<PathBuf>> {
+        GameDataDirectoryFinder::find_game_data_directories(self)
+    }
+
+    fn find_gacha_urls(&self, game_data_dir: &Path) -> Result<Vec<GachaUrl>> {
+        GachaUrlFinder::find_gacha_urls(self, game_data_dir)
+    }
+
+    async fn find_valid_gacha_url(
+        &self,
+        reqwest: &Reqwest,
+        uid: &str,
+        gacha_urls: &[GachaUrl],
+        policy: &FetchPolicy,
+    ) -> Result<GachaUrl> {
+        find_hoyoverse_gacha_url_and_validate_consistency(
+            reqwest,
+            self,
+            &AccountFacet::StarRail,
+            uid,
+            gacha_urls,
+            policy,
+        )
+        .await
+    }
+
+    async fn find_last_query(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        gacha_types: &[&str],
+    ) -> Result<Option<String>> {
+        Ok(storage
+            .find_starrail_gacha_records_latest(uid, gacha_types)
+            .await?
+            .map(|record| record.id))
+    }
+
+    async fn find_stored_records(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        gacha_types: &[&str],
+    ) -> Result<Vec<KeyedGachaRecord>> {
+        let mut records = Vec::new();
+        for gacha_type in gacha_types {
+            for record in storage
+                .find_starrail_gacha_records(uid, Some(gacha_type), None)
+                .await?
+            {
+                records.push(KeyedGachaRecord::new(&record)?);
+            }
+        }
+        Ok(records)
+    }
+
+    async fn pull_gacha_records(
+        &self,
+        request: PullRequest,
+        receiver: &PullReceiver<'_>,
+    ) -> Result<()> {
+        create_fetcher_channel(
+            StarRailGacha,
+            StarRailGacha,
+            request,
+            receiver.find_checkpoints().await?,
+            |fragment| {
+                receiver.receive(fragment, |data| async move {
+                    receiver.storage.save_starrail_gacha_records(&data).await
+                })
+            },
+        )
+        .await
+    }
+
+    fn export_format(&self, format: ExportFormat) -> Option<(&'static str, &'static str)> {
+        Some(match format {
+            ExportFormat::Classic => ("Star Rail GachaLog Format", "SRGF"),
+            ExportFormat::Uigf4 => ("Uniform Interchangeable GachaLog Format", "UIGFv4"),
+        })
+    }
+
+    async fn import_gacha_records(&self, storage: &Storage, uid: &str, file: File) -> Result<u64> {
+        let value: serde_json::Value = serde_json::from_reader(file)?;
+
+        // UIGF v4 groups the accounts by game, SRGF carries a single account
+        let gacha_records = if uigf4::UIGF4::is_uigf4(&value) {
+            let uigf = uigf4::UIGF4::from_value(value)?;
+            uigf4::convert_hkrpg_to_offical(&uigf, uid)?
+        } else {
+            let mut srgf = srgf::SRGF::from_value(value)?;
+            if srgf.info.uid != uid {
+                return Err(Error::UIGFOrSRGFMismatchedUID {
+                    expected: uid.to_owned(),
+                    actual: srgf.info.uid,
+                });
+            }
+
+            srgf::convert_srgf_to_offical(&mut srgf)?
+        };
+
+        storage.save_starrail_gacha_records(&gacha_records).await
+    }
+
+    async fn export_gacha_records(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        format: ExportFormat,
+        now: &OffsetDateTime,
+        writer: File,
+    ) -> Result<()> {
+        let gacha_records = storage.find_starrail_gacha_records(uid, None, None).await?;
+        let lang = gacha_records
+            .first()
+            .map(|v| v.lang.clone())
+            .unwrap_or("en-us".to_owned());
+        let time_zone = storage
+            .find_account_time_zone(&AccountFacet::StarRail, uid)
+            .await?;
+
+        match format {
+            ExportFormat::Classic => {
+                // convert to srgf and write
+                let srgf_list = srgf::convert_offical_to_srgf(&gacha_records)?;
+                let srgf = srgf::SRGF::new(uid.to_owned(), lang, time_zone, now, srgf_list)?;
+                srgf.to_writer(writer, false)
+            }
+            ExportFormat::Uigf4 => {
+                // convert to uigf v4 and write
+                let hkrpg = uigf4::convert_offical_to_hkrpg(uid, lang, time_zone, &gacha_records);
+                let uigf = uigf4::UIGF4::new(now).with_hkrpg(vec![hkrpg]);
+                uigf.to_writer(writer, false)
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    cmp::Ordering,
     collections::{hash_map::Entry, HashMap},
     path::{Path, PathBuf},
 };
@@ -12,7 +13,8 @@ use url::Url;
 use crate::{
     error::{Error, Result},
     gacha::{
-        utilities::{create_default_reqwest, GachaResponse, GACHA_URL_CACHED},
+        policy::FetchPolicy,
+        utilities::{override_url_origin, send_gacha_request, GachaResponse, GACHA_URL_CACHED},
         GachaRecord, GachaUrl,
     },
     storage::entity_account::AccountFacet,
@@ -44,12 +46,22 @@ pub(super) fn lookup_mihoyo_dir() -> PathBuf {
     }
 }
 
+/// Compare gacha record ids numerically, as strings they mis-order once the lengths differ
+pub fn compare_gacha_record_ids(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u128>(), b.parse::<u128>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+    }
+}
+
 pub(super) async fn fetch_gacha_records<T: Sized + DeserializeOwned>(
     reqwest: &Reqwest,
     endpoint: &str,
+    gacha_type_key: &str,
     gacha_url: &str,
     gacha_type: Option<&str>,
     end_id: Option<&str>,
+    policy: &FetchPolicy,
 ) -> Result<GachaResponse<T>> {
     let endpoint_start = gacha_url.find(endpoint).ok_or(Error::IllegalGachaUrl)?;
     let base_url = &gacha_url[0..endpoint_start + endpoint.len()];
@@ -60,30 +72,39 @@ pub(super) async fn fetch_gacha_records<T: Sized + DeserializeOwned>(
         .collect();
 
     let origin_gacha_type = queries
-        .get("gacha_type")
+        .get(gacha_type_key)
         .cloned()
         .ok_or(Error::IllegalGachaUrl)?;
     let origin_end_id = queries.get("end_id").cloned();
     let gacha_type = gacha_type.unwrap_or(&origin_gacha_type);
 
-    queries.remove("gacha_type");
+    queries.remove(gacha_type_key);
     queries.remove("page");
     queries.remove("size");
     queries.remove("begin_id");
     queries.remove("end_id");
 
+    if let Some(language) = &policy.language {
+        queries.insert(String::from("lang"), language.clone());
+    }
+
     let mut url = Url::parse_with_params(base_url, queries).map_err(|_| Error::IllegalGachaUrl)?;
 
     url.query_pairs_mut()
         .append_pair("page", "1")
-        .append_pair("size", "20")
-        .append_pair("gacha_type", gacha_type);
+        .append_pair("size", &policy.page_size.to_string())
+        .append_pair(gacha_type_key, gacha_type);
 
     if let Some(end_id) = end_id.or(origin_end_id.as_deref()) {
         url.query_pairs_mut().append_pair("end_id", end_id);
     }
 
-    let response: GachaResponse<T> = reqwest.get(url).send().await?.json().await?;
+    if let Some(api_base_url) = &policy.api_base_url {
+        override_url_origin(&mut url, api_base_url)?;
+    }
+
+    let response: GachaResponse<T> =
+        send_gacha_request(reqwest, reqwest.get(url), None, policy).await?;
     let retcode = response.retcode.unwrap_or_default();
     if retcode != 0 {
         if retcode == -101 {
@@ -100,10 +121,12 @@ pub(super) async fn fetch_gacha_records<T: Sized + DeserializeOwned>(
 }
 
 pub async fn find_hoyoverse_gacha_url_and_validate_consistency<Record, Fetcher>(
+    reqwest: &Reqwest,
     fetcher: &Fetcher,
     facet: &AccountFacet,
     uid: &str,
     gacha_urls: &[GachaUrl],
+    policy: &FetchPolicy,
 ) -> Result<GachaUrl>
 where
     Record: GachaRecord + Sized + Serialize + Send + Sync,
@@ -115,7 +138,6 @@ where
     );
     let mut cached = GACHA_URL_CACHED.lock().await;
 
-    let reqwest = create_default_reqwest()?;
     let local_datetime = OffsetDateTime::now_local().map_err(time::Error::from)?;
     let valid_gacha_urls: Vec<&GachaUrl> = gacha_urls
         .iter()
@@ -156,7 +178,7 @@ where
         }
 
         let result = fetcher
-            .fetch_gacha_records_any_uid(&reqwest, gacha_url)
+            .fetch_gacha_records_any_uid(reqwest, gacha_url, policy)
             .await;
         match result {
             Err(Error::GachaRecordRetcode { retcode, message }) => {
@@ -193,3 +215,15 @@ where
 
     Err(Error::VacantGachaUrl)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::compare_gacha_record_ids;
+
+    #[test]
+    fn gacha_record_ids_compare_numerically() {
+        assert!(compare_gacha_record_ids("999", "1000").is_lt());
+        assert!(compare_gacha_record_ids("1700000000000000000", "1000").is_gt());
+        assert!(compare_gacha_record_ids("1000", "1000").is_eq());
+    }
+}
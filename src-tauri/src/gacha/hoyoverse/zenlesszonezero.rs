@@ -0,0 +1,403 @@
+extern crate async_trait;
+extern crate reqwest;
+extern crate serde;
+extern crate url;
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::gacha::diff::KeyedGachaRecord;
+use crate::gacha::hoyoverse::utilities::lookup_mihoyo_dir;
+use crate::gacha::policy::FetchPolicy;
+use crate::gacha::provider::{ExportFormat, GachaProvider, PullReceiver, PullRequest};
+use crate::gacha::uigf4;
+use crate::gacha::utilities::{
+    lookup_gacha_urls_from_endpoint, lookup_path_line_from_keyword, lookup_valid_cache_data_dir,
+};
+use crate::gacha::{GachaRecord, GachaUrl, GachaUrlFinder, GameDataDirectoryFinder};
+use crate::storage::entity_account::AccountFacet;
+use crate::storage::Storage;
+use async_trait::async_trait;
+use reqwest::Client as Reqwest;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use super::hoyoverse::{
+    create_fetcher_channel, GachaRecordFetcherChannel, HoyoverseGachaRecordFetcher,
+    HoyoverseGachaRecordPage,
+};
+use super::utilities::{fetch_gacha_records, find_hoyoverse_gacha_url_and_validate_consistency};
+
+#[derive(Default, Deserialize)]
+pub struct ZenlessZoneZeroGacha;
+
+/// Game Directory
+
+impl GameDataDirectoryFinder for ZenlessZoneZeroGacha {
+    fn find_game_data_directories(&self) -> Result<Vec<PathBuf>> {
+        let mihoyo_dir = lookup_mihoyo_dir();
+        let mut directories = Vec::new();
+
+        const INTERNATIONAL_PLAYER_LOG: &str = "ZenlessZoneZero/Player.log";
+        const INTERNATIONAL_DIR_KEYWORD: &str = "/ZenlessZoneZero_Data/";
+
+        let mut player_log = mihoyo_dir.join(INTERNATIONAL_PLAYER_LOG);
+        if let Some(directory) =
+            lookup_path_line_from_keyword(player_log, INTERNATIONAL_DIR_KEYWORD)?
+        {
+            directories.push(directory);
+        }
+
+        const CHINESE_PLAYER_LOG: &str = "绝区零/Player.log";
+        const CHINESE_DIR_KEYWORD: &str = "/ZenlessZoneZero_Data/";
+
+        player_log = mihoyo_dir.join(CHINESE_PLAYER_LOG);
+        if let Some(directory) = lookup_path_line_from_keyword(player_log, CHINESE_DIR_KEYWORD)? {
+            directories.push(directory);
+        }
+
+        Ok(directories)
+    }
+}
+
+/// Gacha Types
+///   Pulled gacha type -> stored gacha types.
+///   1: Stable Channel, 2: Exclusive Channel, 3: W-Engine Channel, 5: Bangboo Channel
+pub const GACHA_TYPE_MAPPINGS: &[(&str, &[&str])] =
+    &[("1", &["1"]), ("2", &["2"]), ("3", &["3"]), ("5", &["5"])];
+
+/// Gacha Url
+
+const ENDPOINT: &str = "/common/gacha_record/api/getGachaLog?";
+// The records carry the detailed gacha type (e.g. 2001), the search type is the real one
+const GACHA_TYPE_KEY: &str = "real_gacha_type";
+// Star Rail shares the endpoint, only the host tells them apart
+const HOST_KEYWORD: &str = "public-operation-nap";
+
+impl GachaUrlFinder for ZenlessZoneZeroGacha {
+    fn find_gacha_urls<P: AsRef<Path>>(&self, game_data_dir: P) -> Result<Vec<GachaUrl>> {
+        let cache_data_dir = lookup_valid_cache_data_dir(game_data_dir)?;
+        let gacha_urls = lookup_gacha_urls_from_endpoint(cache_data_dir, ENDPOINT)?;
+        Ok(gacha_urls
+            .into_iter()
+            .filter(|gacha_url| gacha_url.value.contains(HOST_KEYWORD))
+            .collect())
+    }
+}
+
+/// Gacha Record
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ZenlessZoneZeroGachaRecord {
+    pub id: String,
+    pub uid: String,
+    pub gacha_id: String,
+    /// Real gacha type that was searched: 1, 2, 3 or 5.
+    ///   Replaces the detailed type of the API (e.g. 2001), which is not kept:
+    ///   the banner is still told by `gacha_id`, and the stored gacha types,
+    ///   last queries and UIGF v4 `nap` items all use the real gacha type.
+    pub gacha_type: String,
+    pub item_id: String,
+    pub count: String,
+    pub time: String,
+    pub name: String,
+    pub lang: String,
+    pub item_type: String,
+    pub rank_type: String,
+}
+
+impl GachaRecord for ZenlessZoneZeroGachaRecord {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl PartialOrd for ZenlessZoneZeroGachaRecord {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.id.partial_cmp(&other.id)
+    }
+}
+
+/// Gacha Record Fetcher
+
+#[allow(unused)]
+#[derive(Deserialize)]
+pub(crate) struct ZenlessZoneZeroGachaRecordPagination {
+    page: String,
+    size: String,
+    // total: String,
+    list: Vec<ZenlessZoneZeroGachaRecord>,
+    region: String,
+    region_time_zone: i8,
+}
+
+#[async_trait]
+impl HoyoverseGachaRecordFetcher for ZenlessZoneZeroGacha {
+    type Target = ZenlessZoneZeroGachaRecord;
+
+    async fn fetch_gacha_records(
+        &self,
+        reqwest: &Reqwest,
+        gacha_url: &str,
+        gacha_type: Option<&str>,
+        end_id: Option<&str>,
+        policy: &FetchPolicy,
+    ) -> Result<Option<HoyoverseGachaRecordPage<Self::Target>>> {
+        let response = fetch_gacha_records::<ZenlessZoneZeroGachaRecordPagination>(
+            reqwest,
+            ENDPOINT,
+            GACHA_TYPE_KEY,
+            gacha_url,
+            gacha_type,
+            end_id,
+            policy,
+        )
+        .await?;
+
+        Ok(response.data.map(|pagination| HoyoverseGachaRecordPage {
+            records: pagination
+                .list
+                .into_iter()
+                .map(|mut record| {
+                    // See `ZenlessZoneZeroGachaRecord::gacha_type`
+                    if let Some(gacha_type) = gacha_type {
+                        record.gacha_type = gacha_type.to_owned();
+                    }
+                    record
+                })
+                .collect(),
+            time_zone: pagination.region_time_zone,
+        }))
+    }
+
+    async fn fetch_gacha_records_any_uid(
+        &self,
+        reqwest: &Reqwest,
+        gacha_url: &str,
+        policy: &FetchPolicy,
+    ) -> Result<Option<String>> {
+        let result = self
+            .fetch_gacha_records(reqwest, gacha_url, None, None, policy)
+            .await?;
+        Ok(result.and_then(|page| page.records.first().map(|record| record.uid.clone())))
+    }
+}
+
+#[async_trait]
+impl GachaRecordFetcherChannel<ZenlessZoneZeroGachaRecord> for ZenlessZoneZeroGacha {
+    type Fetcher = Self;
+}
+
+/// Gacha Provider
+
+#[async_trait]
+impl GachaProvider for ZenlessZoneZeroGacha {
+    fn facet(&self) -> AccountFacet {
+        AccountFacet::ZenlessZoneZero
+    }
+
+    fn gacha_type_mappings(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        GACHA_TYPE_MAPPINGS
+    }
+
+    fn find_game_data_directories(&self) -> Result<Vec<PathBuf>> {
+        GameDataDirectoryFinder::find_game_data_directories(self)
+    }
+
+    fn find_gacha_urls(&self, game_data_dir: &Path) -> Result<Vec<GachaUrl>> {
+        GachaUrlFinder::find_gacha_urls(self, game_data_dir)
+    }
+
+    async fn find_valid_gacha_url(
+        &self,
+        reqwest: &Reqwest,
+        uid: &str,
+        gacha_urls: &[GachaUrl],
+        policy: &FetchPolicy,
+    ) -> Result<GachaUrl> {
+        find_hoyoverse_gacha_url_and_validate_consistency(
+            reqwest,
+            self,
+            &AccountFacet::ZenlessZoneZero,
+            uid,
+            gacha_urls,
+            policy,
+        )
+        .await
+    }
+
+    async fn find_last_query(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        gacha_types: &[&str],
+    ) -> Result<Option<String>> {
+        Ok(storage
+            .find_zenlesszonezero_gacha_records_latest(uid, gacha_types)
+            .await?
+            .map(|record| record.id))
+    }
+
+    async fn find_stored_records(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        gacha_types: &[&str],
+    ) -> Result<Vec<KeyedGachaRecord>> {
+        let mut records = Vec::new();
+        for gacha_type in gacha_types {
+            for record in storage
+                .find_zenlesszonezero_gacha_records(uid, Some(gacha_type), None)
+                .await?
+            {
+                records.push(KeyedGachaRecord::new(&record)?);
+            }
+        }
+        Ok(records)
+    }
+
+    async fn pull_gacha_records(
+        &self,
+        request: PullRequest,
+        receiver: &PullReceiver<'_>,
+    ) -> Result<()> {
+        create_fetcher_channel(
+            ZenlessZoneZeroGacha,
+            ZenlessZoneZeroGacha,
+            request,
+            receiver.find_checkpoints().await?,
+            |fragment| {
+                receiver.receive(fragment, |data| async move {
+                    receiver
+                        .storage
+                        .save_zenlesszonezero_gacha_records(&data)
+                        .await
+                })
+            },
+        )
+        .await
+    }
+
+    fn export_format(&self, _format: ExportFormat) -> Option<(&'static str, &'static str)> {
+        // Both are UIGF v4
+        Some(("Uniform Interchangeable GachaLog Format", "UIGF"))
+    }
+
+    async fn import_gacha_records(&self, storage: &Storage, uid: &str, file: File) -> Result<u64> {
+        let uigf = uigf4::UIGF4::from_reader(file)?;
+        let gacha_records = uigf4::convert_nap_to_offical(&uigf, uid)?;
+        storage
+            .save_zenlesszonezero_gacha_records(&gacha_records)
+            .await
+    }
+
+    async fn export_gacha_records(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        _format: ExportFormat,
+        now: &OffsetDateTime,
+        writer: File,
+    ) -> Result<()> {
+        let gacha_records = storage
+            .find_zenlesszonezero_gacha_records(uid, None, None)
+            .await?;
+        let lang = gacha_records
+            .first()
+            .map(|v| v.lang.clone())
+            .unwrap_or("en-us".to_owned());
+        let time_zone = storage
+            .find_account_time_zone(&AccountFacet::ZenlessZoneZero, uid)
+            .await?;
+
+        // convert to uigf v4 and write
+        let nap = uigf4::convert_offical_to_nap(uid, lang, time_zone, &gacha_records);
+        let uigf = uigf4::UIGF4::new(now).with_nap(vec![nap]);
+        uigf.to_writer(writer, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::{ZenlessZoneZeroGacha, ZenlessZoneZeroGachaRecord};
+    use crate::gacha::hoyoverse::hoyoverse::create_fetcher_channel;
+    use crate::gacha::mock::MockGachaServer;
+    use crate::gacha::provider::PullRequest;
+    use crate::gacha::GachaRecordFetcherChannelFragment;
+    use crate::http::{create_reqwest, HttpClientConfig};
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+    use tokio_util::sync::CancellationToken;
+
+    #[tokio::test]
+    async fn pull_by_real_gacha_type() {
+        let server = MockGachaServer::start().await;
+        let records = (1..=3)
+            .map(|n| {
+                json!({
+                    "id": format!("172000000000000000{n}"),
+                    "uid": "1000000001",
+                    "gacha_id": "2001",
+                    "gacha_type": "2001",
+                    "item_id": "1191",
+                    "count": "1",
+                    "time": "2024-07-04 12:00:00",
+                    "name": "Ellen",
+                    "lang": "en-us",
+                    "item_type": "Agents",
+                    "rank_type": "4",
+                })
+            })
+            .collect();
+        server.add_hoyoverse_records("2", records);
+
+        let fragments = Mutex::new(Vec::new());
+        let result = create_fetcher_channel(
+            ZenlessZoneZeroGacha,
+            ZenlessZoneZeroGacha,
+            PullRequest {
+                reqwest: create_reqwest(&HttpClientConfig::default()).unwrap(),
+                uid: String::from("1000000001"),
+                gacha_url: String::from("https://public-operation-nap-sg.hoyoverse.com/common/gacha_record/api/getGachaLog?authkey_ver=1&lang=en&authkey=valid&real_gacha_type=1"),
+                gacha_type_and_last_query_mappings: BTreeMap::from([(String::from("2"), None)]),
+                policy: server.fetch_policy(),
+                cancellation: CancellationToken::new(),
+            },
+            BTreeMap::new(),
+            |fragment| {
+                fragments.lock().unwrap().push(fragment);
+                std::future::ready(Ok(()))
+            },
+        )
+        .await;
+        assert!(result.is_ok());
+
+        let pulled: Vec<ZenlessZoneZeroGachaRecord> = fragments
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .filter_map(|fragment| match fragment {
+                GachaRecordFetcherChannelFragment::Data { records, .. } => Some(records),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        assert_eq!(pulled.len(), 3);
+        // Stored under the real gacha type that was searched
+        assert!(pulled.iter().all(|record| record.gacha_type == "2"));
+        assert!(server
+            .requests()
+            .iter()
+            .all(|request| request.gacha_type == "2"));
+    }
+}
@@ -2,25 +2,171 @@ extern crate async_trait;
 extern crate form_urlencoded;
 extern crate reqwest;
 extern crate serde;
+extern crate serde_json;
 extern crate time;
 extern crate tokio;
 extern crate url;
 
 use crate::error::{Error, Result};
-use crate::gacha::utilities::GachaResponse;
-use crate::gacha::GachaRecord;
+use crate::gacha::policy::FetchPolicy;
+use crate::gacha::provider::{PullContext, PullRequest};
+use crate::gacha::retry::retry_with_backoff;
+use crate::gacha::task::{cancellable, cancellable_sleep};
+use crate::gacha::utilities::{override_url_origin, send_gacha_request, GachaResponse};
+use crate::gacha::{GachaRecord, GachaRecordFetcherChannelFragment, GachaRecordPullSummary};
 use async_trait::async_trait;
 use reqwest::Client as Reqwest;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::any::Any;
 use std::collections::{BTreeMap, HashMap};
 use std::future::Future;
-use std::path::{Path, PathBuf};
-use time::OffsetDateTime;
+use time::macros::format_description;
+use time::PrimitiveDateTime;
 use tracing::debug;
 use url::Url;
 
+/// Kuro Region
+///   The record API is served from a different host for global and CN servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KuroRegion {
+    Global,
+    #[serde(rename = "cn")]
+    China,
+}
+
+/// Server ids (`svr_id`) of the CN servers: official and bilibili
+const CHINA_SERVER_IDS: &[&str] = &[
+    "76402e5b20be2c39f095a152090afddc",
+    "919752ae5ea09c1ced910dd668a63ffb",
+];
+
+/// Server ids (`svr_id`) of the America and Europe servers,
+/// the other servers are in UTC+8
+const AMERICA_SERVER_ID: &str = "591d6af3a3090d8ea00d8f86cf6d7501";
+const EUROPE_SERVER_ID: &str = "6eb2a235b30d05efd77bedb5cf60999e";
+
+/// The queries of a gacha url, they follow its `#/record?` fragment
+fn gacha_url_queries(gacha_url: &str) -> HashMap<String, String> {
+    let query_str = gacha_url
+        .split_once('?')
+        .map(|(_, query_str)| query_str)
+        .unwrap_or_default();
+    form_urlencoded::parse(query_str.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+/// UTC offset in hours of the server of the gacha url, the record times are in it
+pub fn kuro_server_time_zone(gacha_url: &str) -> i8 {
+    match gacha_url_queries(gacha_url)
+        .get("svr_id")
+        .map(String::as_str)
+    {
+        Some(AMERICA_SERVER_ID) => -5,
+        Some(EUROPE_SERVER_ID) => 1,
+        _ => 8,
+    }
+}
+
+impl KuroRegion {
+    /// Derive from the gacha url origin, else from its `svr_area` or `svr_id`
+    pub fn from_gacha_url(gacha_url: &str) -> Self {
+        if let Some(host) = Url::parse(gacha_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_owned))
+        {
+            if host.ends_with(".aki-game.com") {
+                return Self::China;
+            } else if host.ends_with(".aki-game.net") {
+                return Self::Global;
+            }
+        }
+
+        let queries = gacha_url_queries(gacha_url);
+        let is_china = queries.get("svr_area").map(String::as_str) == Some("cn")
+            || queries
+                .get("svr_id")
+                .is_some_and(|server_id| CHINA_SERVER_IDS.contains(&server_id.as_str()));
+
+        if is_china {
+            Self::China
+        } else {
+            Self::Global
+        }
+    }
+
+    pub fn record_query_url(&self) -> &'static str {
+        match self {
+            Self::Global => "https://gmserver-api.aki-game2.net/gacha/record/query",
+            Self::China => "https://gmserver-api.aki-game2.com/gacha/record/query",
+        }
+    }
+}
+
+/// Gacha Record Fingerprint
+///   Kuro records have no id. Within a second, a record is told apart by its
+///   position counted from the oldest one, which stays put when newer ones arrive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KuroGachaRecordFingerprint {
+    pub time: String,
+    pub resource_id: i32,
+    pub position: u32,
+}
+
+impl KuroGachaRecordFingerprint {
+    /// Fingerprint the `(time, resource_id)` of records given newest first,
+    /// the result is in the same order.
+    pub fn of_records<'a, I>(records: I) -> Vec<Self>
+    where
+        I: DoubleEndedIterator<Item = (&'a str, i32)>,
+    {
+        let mut positions = HashMap::<&str, u32>::new();
+        let mut fingerprints: Vec<Self> = records
+            .rev()
+            .map(|(time, resource_id)| {
+                let position = positions.entry(time).or_default();
+                let fingerprint = Self {
+                    time: time.to_owned(),
+                    resource_id,
+                    position: *position,
+                };
+                *position += 1;
+                fingerprint
+            })
+            .collect();
+        fingerprints.reverse();
+        fingerprints
+    }
+}
+
+/// Gacha Record Tail
+///   The latest stored records of a pool, to tell the new records apart.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KuroGachaRecordTail {
+    /// Time of the latest stored record, None if nothing is stored
+    pub last_time: Option<String>,
+    /// Fingerprints of the stored records within that second
+    pub fingerprints: Vec<KuroGachaRecordFingerprint>,
+}
+
+impl KuroGachaRecordTail {
+    /// Whether the fetched record is not stored yet
+    pub fn is_new(&self, fingerprint: &KuroGachaRecordFingerprint) -> Result<bool> {
+        let Some(last_time) = &self.last_time else {
+            return Ok(true);
+        };
+
+        let format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+        let record_time =
+            PrimitiveDateTime::parse(&fingerprint.time, &format).map_err(time::Error::from)?;
+        let last_time = PrimitiveDateTime::parse(last_time, &format).map_err(time::Error::from)?;
+
+        Ok(record_time > last_time
+            || (record_time == last_time && !self.fingerprints.contains(fingerprint)))
+    }
+}
+
 /// Gacha Record Fetcher
 #[async_trait]
 pub trait KuroGachaRecordFetcher {
@@ -32,103 +178,137 @@ pub trait KuroGachaRecordFetcher {
         uid: &str,
         gacha_url: &str,
         gacha_type: Option<&str>,
-        last_time: Option<&str>,
+        tail: &KuroGachaRecordTail,
+        policy: &FetchPolicy,
     ) -> Result<Option<Vec<Self::Target>>>;
 }
 
 /// Gacha Record Fetcher Channel
 
-#[allow(unused)]
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub enum KuroGachaRecordFetcherChannelFragment<T: GachaRecord + Sized + Serialize + Send + Sync> {
-    Sleeping,
-    Ready(String),
-    Pagination(u32),
-    Data(Vec<T>),
-    Finished,
-}
-
 #[async_trait]
 pub trait KuroGachaRecordFetcherChannel<T: GachaRecord + Sized + Serialize + Send + Sync> {
     type Fetcher: KuroGachaRecordFetcher<Target = T> + Send + Sync;
 
     async fn pull_gacha_records(
         &self,
-        reqwest: &Reqwest,
-        fetcher: &Self::Fetcher,
-        sender: &tokio::sync::mpsc::Sender<KuroGachaRecordFetcherChannelFragment<T>>,
-        uid: &str,
-        gacha_url: &str,
+        pull: &PullContext<'_, Self::Fetcher, T>,
         gacha_type: &str,
-        last_time: Option<&str>,
-    ) -> Result<()> {
-        const SLEEPING: u64 = 3;
+        tail: &KuroGachaRecordTail,
+    ) -> Result<u64> {
+        let retry_policy = pull.policy.retry_policy();
 
-        sender
-            .send(KuroGachaRecordFetcherChannelFragment::Ready(
-                gacha_type.to_owned(),
-            ))
+        pull.sender
+            .send(GachaRecordFetcherChannelFragment::Ready {
+                gacha_type: gacha_type.to_owned(),
+            })
             .await
             .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
-        tokio::time::sleep(tokio::time::Duration::from_secs(SLEEPING)).await;
+        cancellable_sleep(pull.cancellation, pull.policy.page_delay()).await?;
 
-        let gacha_records = fetcher
-            .fetch_gacha_records(reqwest, uid, gacha_url, Some(gacha_type), last_time)
-            .await?;
+        let gacha_records = cancellable(
+            pull.cancellation,
+            retry_with_backoff(
+                &retry_policy,
+                || {
+                    pull.fetcher.fetch_gacha_records(
+                        pull.reqwest,
+                        pull.uid,
+                        pull.gacha_url,
+                        Some(gacha_type),
+                        tail,
+                        pull.policy,
+                    )
+                },
+                |attempt, delay| async move {
+                    pull.sender
+                        .send(GachaRecordFetcherChannelFragment::Retrying {
+                            gacha_type: gacha_type.to_owned(),
+                            attempt,
+                            delay_millis: delay.as_millis() as u64,
+                        })
+                        .await
+                        .map_err(|_| Error::GachaRecordFetcherChannelSend)
+                },
+            ),
+        )
+        .await?;
 
+        // The whole pool comes in a single batch
+        let mut total: u64 = 0;
         if let Some(gacha_records) = gacha_records {
-            sender
-                .send(KuroGachaRecordFetcherChannelFragment::Data(gacha_records))
+            total = gacha_records.len() as u64;
+            pull.sender
+                .send(GachaRecordFetcherChannelFragment::Data {
+                    gacha_type: gacha_type.to_owned(),
+                    records: gacha_records,
+                    total,
+                })
                 .await
                 .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
         }
 
-        sender
-            .send(KuroGachaRecordFetcherChannelFragment::Finished)
+        pull.sender
+            .send(GachaRecordFetcherChannelFragment::Finished {
+                gacha_type: gacha_type.to_owned(),
+                total,
+            })
             .await
             .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
-        Ok(())
+        Ok(total)
     }
 
     async fn pull_all_gacha_records(
         &self,
-        reqwest: &Reqwest,
-        fetcher: &Self::Fetcher,
-        sender: &tokio::sync::mpsc::Sender<KuroGachaRecordFetcherChannelFragment<T>>,
-        uid: &str,
-        gacha_url: &str,
-        gacha_type_and_last_time_mappings: &BTreeMap<String, Option<String>>,
+        pull: &PullContext<'_, Self::Fetcher, T>,
+        gacha_type_and_tail_mappings: &BTreeMap<String, KuroGachaRecordTail>,
     ) -> Result<()> {
-        for (gacha_type, last_time) in gacha_type_and_last_time_mappings {
-            self.pull_gacha_records(
-                reqwest,
-                fetcher,
-                sender,
-                uid,
-                gacha_url,
-                gacha_type,
-                last_time.as_deref(),
-            )
-            .await?;
+        pull.sender
+            .send(GachaRecordFetcherChannelFragment::TimeZone(
+                kuro_server_time_zone(pull.gacha_url),
+            ))
+            .await
+            .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
+
+        let mut summary = GachaRecordPullSummary::default();
+        for (gacha_type, tail) in gacha_type_and_tail_mappings {
+            // Stop between pools, the records already sent are kept
+            let result = if pull.cancellation.is_cancelled() {
+                Err(Error::TaskCancelled)
+            } else {
+                self.pull_gacha_records(pull, gacha_type, tail).await
+            };
+
+            if let Err(Error::TaskCancelled) = result {
+                pull.sender
+                    .send(GachaRecordFetcherChannelFragment::Cancelled {
+                        gacha_type: gacha_type.to_owned(),
+                    })
+                    .await
+                    .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
+            }
+
+            summary.push(gacha_type, result?);
         }
+
+        pull.sender
+            .send(GachaRecordFetcherChannelFragment::Summary(summary))
+            .await
+            .map_err(|_| Error::GachaRecordFetcherChannelSend)?;
         Ok(())
     }
 }
 
 pub async fn create_kuro_fetcher_channel<Record, FetcherChannel, F, Fut>(
     fetcher_channel: FetcherChannel,
-    reqwest: Reqwest,
     fetcher: FetcherChannel::Fetcher,
-    uid: String,
-    gacha_url: String,
-    gacha_type_and_last_time_mappings: BTreeMap<String, Option<String>>,
+    request: PullRequest,
+    gacha_type_and_tail_mappings: BTreeMap<String, KuroGachaRecordTail>,
     receiver_fn: F,
 ) -> Result<()>
 where
     Record: GachaRecord + Sized + Serialize + Send + Sync,
     FetcherChannel: KuroGachaRecordFetcherChannel<Record> + Send + Sync + 'static,
-    F: Fn(KuroGachaRecordFetcherChannelFragment<Record>) -> Fut,
+    F: Fn(GachaRecordFetcherChannelFragment<Record>) -> Fut,
     Fut: Future<Output = Result<()>>,
 {
     use tokio::spawn;
@@ -136,15 +316,9 @@ where
 
     let (sender, mut receiver) = channel(1);
     let task = spawn(async move {
+        let pull = PullContext::new(&request, &fetcher, &sender);
         fetcher_channel
-            .pull_all_gacha_records(
-                &reqwest,
-                &fetcher,
-                &sender,
-                &uid,
-                &gacha_url,
-                &gacha_type_and_last_time_mappings,
-            )
+            .pull_all_gacha_records(&pull, &gacha_type_and_tail_mappings)
             .await
     });
 
@@ -161,10 +335,11 @@ pub(super) async fn fetch_kuro_gacha_records<T: Sized + DeserializeOwned>(
     endpoint: &str,
     gacha_url: &str,
     gacha_type: Option<&str>,
+    policy: &FetchPolicy,
 ) -> Result<GachaResponse<T>> {
     let endpoint_start = gacha_url.find(endpoint).ok_or(Error::IllegalGachaUrl)?;
-    // let base_url = &gacha_url[0..endpoint_start + endpoint.len()];
-    let base_url = "https://gmserver-api.aki-game2.net/gacha/record/query";
+    let region = KuroRegion::from_gacha_url(gacha_url);
+    let base_url = region.record_query_url();
     let query_str = &gacha_url[endpoint_start + endpoint.len()..];
 
     let queries: HashMap<String, String> = form_urlencoded::parse(query_str.as_bytes())
@@ -192,7 +367,10 @@ pub(super) async fn fetch_kuro_gacha_records<T: Sized + DeserializeOwned>(
         .cloned()
         .ok_or(Error::IllegalGachaUrl)?;
     let gacha_type = gacha_type.unwrap_or(&origin_gacha_type).to_string();
-    let language_code = String::from("en");
+    let language_code = policy
+        .language
+        .clone()
+        .unwrap_or_else(|| String::from("en"));
     // Conversion as of 5/26/24
     // resources_id -> cardPoolId
     // gacha_type -> cardPoolType
@@ -207,9 +385,14 @@ pub(super) async fn fetch_kuro_gacha_records<T: Sized + DeserializeOwned>(
     data.insert("recordId", &record_id);
     data.insert("serverId", &server_id);
 
-    let url = Url::parse(base_url).map_err(|_| Error::IllegalGachaUrl)?;
+    let mut url = Url::parse(base_url).map_err(|_| Error::IllegalGachaUrl)?;
+    if let Some(api_base_url) = &policy.api_base_url {
+        override_url_origin(&mut url, api_base_url)?;
+    }
     debug!("Fetch URL {}: {:?}", url, data);
-    let response: GachaResponse<T> = reqwest.post(url).json(&data).send().await?.json().await?;
+    let body = serde_json::to_value(&data)?;
+    let response: GachaResponse<T> =
+        send_gacha_request(reqwest, reqwest.post(url).json(&data), Some(body), policy).await?;
     let retcode = response.retcode.unwrap_or_default();
     if retcode != 0 {
         if retcode == -101 {
@@ -224,3 +407,44 @@ pub(super) async fn fetch_kuro_gacha_records<T: Sized + DeserializeOwned>(
         Ok(response)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{kuro_server_time_zone, KuroRegion};
+
+    #[test]
+    fn region_from_gacha_url() {
+        assert_eq!(
+            KuroRegion::from_gacha_url("https://aki-gm-resources-oversea.aki-game.net/aki/gacha/index.html#/record?svr_id=abc&svr_area=global"),
+            KuroRegion::Global
+        );
+        assert_eq!(
+            KuroRegion::from_gacha_url(
+                "https://aki-gm-resources.aki-game.com/aki/gacha/index.html#/record?svr_id=abc"
+            ),
+            KuroRegion::China
+        );
+        assert_eq!(
+            KuroRegion::from_gacha_url("https://example.com/aki/gacha/index.html#/record?svr_id=76402e5b20be2c39f095a152090afddc"),
+            KuroRegion::China
+        );
+        assert_eq!(
+            KuroRegion::from_gacha_url(
+                "https://example.com/aki/gacha/index.html#/record?svr_area=cn"
+            ),
+            KuroRegion::China
+        );
+    }
+
+    #[test]
+    fn server_time_zone_from_gacha_url() {
+        assert_eq!(
+            kuro_server_time_zone("https://aki-gm-resources-oversea.aki-game.net/aki/gacha/index.html#/record?svr_id=6eb2a235b30d05efd77bedb5cf60999e&svr_area=global"),
+            1
+        );
+        assert_eq!(
+            kuro_server_time_zone("https://aki-gm-resources.aki-game.com/aki/gacha/index.html#/record?svr_id=76402e5b20be2c39f095a152090afddc"),
+            8
+        );
+    }
+}
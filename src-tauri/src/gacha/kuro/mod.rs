@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod kuro;
 pub mod utilities;
 pub mod wutheringwaves;
@@ -6,17 +6,14 @@ use tracing::debug;
 
 use crate::{
     error::{Error, Result},
-    gacha::{
-        utilities::{create_default_reqwest, GACHA_URL_CACHED},
-        GachaRecord, GachaUrl,
-    },
+    gacha::{utilities::GACHA_URL_CACHED, GachaRecord, GachaUrl},
     storage::entity_account::AccountFacet,
 };
 
 use super::kuro::KuroGachaRecordFetcher;
 
 pub(crate) async fn find_kuro_gacha_url_and_validate_consistency<Record, Fetcher>(
-    fetcher: &Fetcher,
+    _fetcher: &Fetcher,
     facet: &AccountFacet,
     uid: &str,
     gacha_urls: &[GachaUrl],
@@ -31,7 +28,6 @@ where
     );
     let mut cached = GACHA_URL_CACHED.lock().await;
 
-    let reqwest = create_default_reqwest()?;
     let local_datetime = OffsetDateTime::now_local().map_err(time::Error::from)?;
     let valid_gacha_urls: Vec<&GachaUrl> = gacha_urls.iter().collect();
 
@@ -4,23 +4,32 @@ extern crate serde;
 
 use std::any::Any;
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use time::macros::format_description;
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::gacha::diff::KeyedGachaRecord;
 use crate::gacha::kuro::kuro::fetch_kuro_gacha_records;
+use crate::gacha::policy::FetchPolicy;
+use crate::gacha::provider::{ExportFormat, GachaProvider, PullReceiver, PullRequest};
+use crate::gacha::wwpf;
 use crate::gacha::{GachaRecord, GachaUrl, GachaUrlFinder, GameDataDirectoryFinder};
+use crate::storage::entity_account::AccountFacet;
+use crate::storage::Storage;
 use async_trait::async_trait;
 use linkify::LinkFinder;
 use reqwest::Client as Reqwest;
 use serde::{Deserialize, Serialize};
-use time::{OffsetDateTime, PrimitiveDateTime};
-use tracing::{debug, info};
-use url::Url;
+use time::OffsetDateTime;
+use tracing::debug;
 
-use super::kuro::{KuroGachaRecordFetcher, KuroGachaRecordFetcherChannel};
+use super::kuro::{
+    create_kuro_fetcher_channel, KuroGachaRecordFetcher, KuroGachaRecordFetcherChannel,
+    KuroGachaRecordFingerprint, KuroGachaRecordTail, KuroRegion,
+};
+use super::utilities::find_kuro_gacha_url_and_validate_consistency;
 
 #[derive(Default, Deserialize)]
 pub struct WutheringWavesGacha;
@@ -28,12 +37,85 @@ pub struct WutheringWavesGacha;
 /// Game Directory
 impl GameDataDirectoryFinder for WutheringWavesGacha {
     fn find_game_data_directories(&self) -> Result<Vec<PathBuf>> {
-        let mut directories = Vec::new();
+        let directories = Vec::new();
 
         Ok(directories)
     }
 }
 
+/// Pool Types
+///   The Kuro `cardPoolType` of every convene, pulled and stored as its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WutheringWavesPoolType {
+    FeaturedResonator,
+    FeaturedWeapon,
+    StandardResonator,
+    StandardWeapon,
+    Beginner,
+    BeginnerChoice,
+    GiveBack,
+}
+
+impl WutheringWavesPoolType {
+    pub const ALL: [Self; 7] = [
+        Self::FeaturedResonator,
+        Self::FeaturedWeapon,
+        Self::StandardResonator,
+        Self::StandardWeapon,
+        Self::Beginner,
+        Self::BeginnerChoice,
+        Self::GiveBack,
+    ];
+
+    pub fn from_value(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|pool_type| pool_type.value() == value)
+    }
+
+    pub const fn value(&self) -> &'static str {
+        match self {
+            Self::FeaturedResonator => "1",
+            Self::FeaturedWeapon => "2",
+            Self::StandardResonator => "3",
+            Self::StandardWeapon => "4",
+            Self::Beginner => "5",
+            Self::BeginnerChoice => "6",
+            Self::GiveBack => "7",
+        }
+    }
+
+    pub const fn display_name(&self) -> &'static str {
+        match self {
+            Self::FeaturedResonator => "Featured Resonator Convene",
+            Self::FeaturedWeapon => "Featured Weapon Convene",
+            Self::StandardResonator => "Standard Resonator Convene",
+            Self::StandardWeapon => "Standard Weapon Convene",
+            Self::Beginner => "Beginner Convene",
+            Self::BeginnerChoice => "Beginner's Choice Convene",
+            Self::GiveBack => "Giveback Custom Convene",
+        }
+    }
+}
+
+impl std::fmt::Display for WutheringWavesPoolType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.display_name())
+    }
+}
+
+/// Gacha Types
+///   Pulled card pool type -> stored card pool types, one for every pool type.
+pub const GACHA_TYPE_MAPPINGS: &[(&str, &[&str])] = &[
+    ("1", &["1"]), // Featured Resonator
+    ("2", &["2"]), // Featured Weapon
+    ("3", &["3"]), // Standard Resonator
+    ("4", &["4"]), // Standard Weapon
+    ("5", &["5"]), // Beginner
+    ("6", &["6"]), // Beginner's Choice
+    ("7", &["7"]), // Giveback Custom
+];
+
 /// Gacha URL
 const ENDPOINT: &str = "aki/gacha/index.html#/record?";
 
@@ -42,17 +124,22 @@ impl GachaUrlFinder for WutheringWavesGacha {
         let mut result = Vec::new();
 
         let logs_file = game_data_dir.as_ref().join("Client/Saved/Logs/Client.log");
-        println!("{:?}", logs_file);
+        debug!("Lookup wutheringwaves gacha urls from: {logs_file:?}");
         let file = File::open(logs_file)?;
         let lines = BufReader::new(file).lines();
-        for line in lines.flatten() {
-            if line.contains(ENDPOINT) {
-                println!("{}", line);
-                let finder = LinkFinder::new();
-                let links: Vec<_> = finder.links(&line).collect();
-                assert_eq!(1, links.len());
-                let url = &links[0];
-                println!("{:?}", url.as_str());
+        let finder = LinkFinder::new();
+        for line in lines.map_while(std::io::Result::ok) {
+            if !line.contains(ENDPOINT) {
+                continue;
+            }
+
+            // The last link of the line is the one opened by the game
+            if let Some(url) = finder
+                .links(&line)
+                .filter(|link| link.as_str().contains(ENDPOINT))
+                .last()
+            {
+                debug!("Found wutheringwaves gacha url: {}", url.as_str());
                 result.push(GachaUrl {
                     addr: None,
                     creation_time: OffsetDateTime::now_utc(),
@@ -82,16 +169,30 @@ pub struct WutheringWavesGachaRecord {
     pub name: String,
     pub count: i32,
     pub time: String,
+    /// Position within the records of the same second, counted from the oldest
+    #[serde(default)]
+    pub ordinal: u32,
 }
 
 impl GachaRecord for WutheringWavesGachaRecord {
+    // Fetched records are not stored yet, they are identified by their key
     fn id(&self) -> String {
-        self.id.unwrap().to_string()
+        self.id
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| self.key())
     }
 
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    // The natural key of the stored records, the ordinal tells identical pulls apart
+    fn key(&self) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            self.card_pool_type, self.time, self.resource_id, self.ordinal
+        )
+    }
 }
 
 impl PartialOrd for WutheringWavesGachaRecord {
@@ -111,38 +212,35 @@ impl KuroGachaRecordFetcher for WutheringWavesGacha {
         uid: &str,
         gacha_url: &str,
         gacha_type: Option<&str>,
-        last_time: Option<&str>,
+        tail: &KuroGachaRecordTail,
+        policy: &FetchPolicy,
     ) -> Result<Option<Vec<Self::Target>>> {
+        if let Some(pool_type) = gacha_type.and_then(WutheringWavesPoolType::from_value) {
+            debug!("Fetch wutheringwaves gacha records of pool: {pool_type}");
+        }
+
         let response = fetch_kuro_gacha_records::<Vec<WutheringWavesGachaRecord>>(
-            reqwest, ENDPOINT, gacha_url, gacha_type,
+            reqwest, ENDPOINT, gacha_url, gacha_type, policy,
         )
         .await?;
 
-        let format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
-        let data = response.data.unwrap();
-        let updata = data
-            .iter()
-            .filter(|record| {
-                if let Some(last_time) = last_time {
-                    let now_odt = OffsetDateTime::now_utc();
-                    let now_pdt = PrimitiveDateTime::new(now_odt.date(), now_odt.time());
-
-                    let record_time =
-                        PrimitiveDateTime::parse(&record.time, &format).unwrap_or(now_pdt);
-                    let filter_time =
-                        PrimitiveDateTime::parse(last_time, &format).unwrap_or(now_pdt);
-                    return record_time > filter_time;
-                }
+        // The whole pool history comes back, newest first. Keep only what the tail lacks
+        let data = response.data.unwrap_or_default();
+        let fingerprints = KuroGachaRecordFingerprint::of_records(
+            data.iter()
+                .map(|record| (record.time.as_str(), record.resource_id)),
+        );
 
-                true
-            })
-            .map(|record| {
-                let mut record = record.clone();
+        let mut updata = Vec::new();
+        for (record, fingerprint) in data.into_iter().zip(fingerprints) {
+            if tail.is_new(&fingerprint)? {
+                let mut record = record;
                 record.uid = Some(String::from(uid));
                 record.gacha_type = gacha_type.map(str::to_string);
-                record
-            })
-            .collect();
+                record.ordinal = fingerprint.position;
+                updata.push(record);
+            }
+        }
         Ok(Some(updata))
     }
 }
@@ -151,3 +249,464 @@ impl KuroGachaRecordFetcher for WutheringWavesGacha {
 impl KuroGachaRecordFetcherChannel<WutheringWavesGachaRecord> for WutheringWavesGacha {
     type Fetcher = Self;
 }
+
+/// Gacha Provider
+
+#[async_trait]
+impl GachaProvider for WutheringWavesGacha {
+    fn facet(&self) -> AccountFacet {
+        AccountFacet::WutheringWaves
+    }
+
+    fn gacha_type_mappings(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        GACHA_TYPE_MAPPINGS
+    }
+
+    fn find_game_data_directories(&self) -> Result<Vec<PathBuf>> {
+        GameDataDirectoryFinder::find_game_data_directories(self)
+    }
+
+    fn find_gacha_urls(&self, game_data_dir: &Path) -> Result<Vec<GachaUrl>> {
+        GachaUrlFinder::find_gacha_urls(self, game_data_dir)
+    }
+
+    async fn find_valid_gacha_url(
+        &self,
+        _reqwest: &Reqwest,
+        uid: &str,
+        gacha_urls: &[GachaUrl],
+        _policy: &FetchPolicy,
+    ) -> Result<GachaUrl> {
+        find_kuro_gacha_url_and_validate_consistency(
+            self,
+            &AccountFacet::WutheringWaves,
+            uid,
+            gacha_urls,
+        )
+        .await
+    }
+
+    async fn find_last_query(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        gacha_types: &[&str],
+    ) -> Result<Option<String>> {
+        Ok(storage
+            .find_wutheringwaves_gacha_records_latest(uid, gacha_types)
+            .await?
+            .map(|record| record.time))
+    }
+
+    async fn find_stored_records(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        gacha_types: &[&str],
+    ) -> Result<Vec<KeyedGachaRecord>> {
+        let mut records = Vec::new();
+        for gacha_type in gacha_types {
+            for record in storage
+                .find_wutheringwaves_gacha_records(uid, Some(gacha_type), None)
+                .await?
+            {
+                records.push(KeyedGachaRecord::new(&record)?);
+            }
+        }
+        Ok(records)
+    }
+
+    async fn pull_gacha_records(
+        &self,
+        request: PullRequest,
+        receiver: &PullReceiver<'_>,
+    ) -> Result<()> {
+        if receiver.save_to_storage {
+            let region = KuroRegion::from_gacha_url(&request.gacha_url);
+            receiver
+                .storage
+                .save_account_region(&AccountFacet::WutheringWaves, &request.uid, region)
+                .await?;
+        }
+
+        let tails = find_gacha_record_tails(
+            receiver.storage,
+            &request.uid,
+            &request.gacha_type_and_last_query_mappings,
+        )
+        .await?;
+
+        create_kuro_fetcher_channel(
+            WutheringWavesGacha,
+            WutheringWavesGacha,
+            request,
+            tails,
+            |fragment| {
+                receiver.receive(fragment, |data| async move {
+                    receiver
+                        .storage
+                        .save_wutheringwaves_gacha_records(&data)
+                        .await
+                })
+            },
+        )
+        .await
+    }
+
+    fn export_format(&self, format: ExportFormat) -> Option<(&'static str, &'static str)> {
+        match format {
+            ExportFormat::Classic => Some(("Wuthering Waves Proprietary Format", "WWPF")),
+            ExportFormat::Uigf4 => None,
+        }
+    }
+
+    async fn import_gacha_records(&self, storage: &Storage, uid: &str, file: File) -> Result<u64> {
+        let wwpf = wwpf::WWPF::from_reader(file)?;
+        if wwpf.info.uid != uid {
+            return Err(Error::UIGFOrSRGFMismatchedUID {
+                expected: uid.to_owned(),
+                actual: wwpf.info.uid,
+            });
+        }
+
+        // Records already stored are matched by their natural key, ordinal included
+        let gacha_records = wwpf::convert_wwpf_to_offical(&wwpf);
+        storage
+            .save_wutheringwaves_gacha_records(&gacha_records)
+            .await
+    }
+
+    async fn export_gacha_records(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        _format: ExportFormat,
+        now: &OffsetDateTime,
+        writer: File,
+    ) -> Result<()> {
+        let gacha_records = storage
+            .find_wutheringwaves_gacha_records(uid, None, None)
+            .await?;
+        let time_zone = storage
+            .find_account_time_zone(&AccountFacet::WutheringWaves, uid)
+            .await?;
+
+        // convert to wwpf and write
+        let wwpf_list = wwpf::convert_offical_to_wwpf(&gacha_records);
+        let wwpf = wwpf::WWPF::new(uid.to_owned(), time_zone, now, wwpf_list);
+        wwpf.to_writer(writer, false)
+    }
+}
+
+/// Fingerprint the stored records at the last time of each pool
+async fn find_gacha_record_tails(
+    storage: &Storage,
+    uid: &str,
+    gacha_type_and_last_time_mappings: &BTreeMap<String, Option<String>>,
+) -> Result<BTreeMap<String, KuroGachaRecordTail>> {
+    let mut tails = BTreeMap::new();
+    for (gacha_type, last_time) in gacha_type_and_last_time_mappings {
+        let fingerprints = match last_time {
+            Some(last_time) => {
+                let stored_gacha_types = GACHA_TYPE_MAPPINGS
+                    .iter()
+                    .find(|(value, _)| *value == gacha_type)
+                    .map(|(_, stored_gacha_types)| stored_gacha_types.to_vec())
+                    .unwrap_or_else(|| vec![gacha_type.as_str()]);
+
+                storage
+                    .find_wutheringwaves_gacha_records_at(uid, &stored_gacha_types, last_time)
+                    .await?
+                    .into_iter()
+                    .map(|record| KuroGachaRecordFingerprint {
+                        time: record.time,
+                        resource_id: record.resource_id,
+                        position: record.ordinal,
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        tails.insert(
+            gacha_type.clone(),
+            KuroGachaRecordTail {
+                last_time: last_time.clone(),
+                fingerprints,
+            },
+        );
+    }
+
+    Ok(tails)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::{WutheringWavesGacha, WutheringWavesGachaRecord, WutheringWavesPoolType};
+    use crate::error::{Error, Result};
+    use crate::gacha::kuro::kuro::{
+        create_kuro_fetcher_channel, KuroGachaRecordFingerprint, KuroGachaRecordTail,
+    };
+    use crate::gacha::mock::MockGachaServer;
+    use crate::gacha::provider::{find_provider, ExportFormat, PullRequest};
+    use crate::gacha::{GachaRecord, GachaRecordFetcherChannelFragment};
+    use crate::http::{create_reqwest, HttpClientConfig};
+    use crate::storage::entity_account::AccountFacet;
+    use crate::storage::Storage;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+    use std::fs::File;
+    use std::sync::Mutex;
+    use time::OffsetDateTime;
+    use tokio_util::sync::CancellationToken;
+
+    fn records(times: &[&str]) -> Vec<Value> {
+        times
+            .iter()
+            .map(|time| {
+                json!({
+                    "cardPoolType": "Featured Resonator Convene",
+                    "resourceId": 21050043,
+                    "qualityLevel": 3,
+                    "resourceType": "Weapons",
+                    "name": "Sword of Voyager",
+                    "count": 1,
+                    "time": time,
+                })
+            })
+            .collect()
+    }
+
+    /// Tail of the given stored records, newest first
+    fn tail(records: &[Value]) -> KuroGachaRecordTail {
+        let fingerprints = KuroGachaRecordFingerprint::of_records(records.iter().map(|record| {
+            (
+                record["time"].as_str().unwrap(),
+                record["resourceId"].as_i64().unwrap() as i32,
+            )
+        }));
+        let last_time = fingerprints
+            .first()
+            .map(|fingerprint| fingerprint.time.clone());
+
+        KuroGachaRecordTail {
+            fingerprints: fingerprints
+                .into_iter()
+                .filter(|fingerprint| Some(&fingerprint.time) == last_time.as_ref())
+                .collect(),
+            last_time,
+        }
+    }
+
+    async fn pull(
+        server: &MockGachaServer,
+        mappings: BTreeMap<String, KuroGachaRecordTail>,
+    ) -> (Result<()>, Vec<WutheringWavesGachaRecord>) {
+        let gacha_url = "https://aki-gm-resources-oversea.aki-game.net/aki/gacha/index.html#/record?svr_id=abc&player_id=500000001&lang=en&gacha_id=100001&gacha_type=1&svr_area=global&record_id=def&resources_id=ghi";
+        let records = Mutex::new(Vec::new());
+        let result = create_kuro_fetcher_channel(
+            WutheringWavesGacha,
+            WutheringWavesGacha,
+            PullRequest {
+                reqwest: create_reqwest(&HttpClientConfig::default()).unwrap(),
+                uid: String::from("500000001"),
+                gacha_url: gacha_url.to_owned(),
+                gacha_type_and_last_query_mappings: BTreeMap::new(),
+                policy: server.fetch_policy(),
+                cancellation: CancellationToken::new(),
+            },
+            mappings,
+            |fragment| {
+                if let GachaRecordFetcherChannelFragment::Data { records: data, .. } = fragment {
+                    records.lock().unwrap().extend(data);
+                }
+                std::future::ready(Ok(()))
+            },
+        )
+        .await;
+
+        (result, records.into_inner().unwrap())
+    }
+
+    #[tokio::test]
+    async fn pull_records_newer_than_tail() {
+        let server = MockGachaServer::start().await;
+        server.add_kuro_records(
+            "1",
+            records(&[
+                "2024-05-23 10:00:00",
+                "2024-05-24 10:00:00",
+                "2024-05-25 10:00:00",
+            ]),
+        );
+        server.add_kuro_records("2", records(&["2024-05-26 10:00:00"]));
+
+        let mappings = BTreeMap::from([
+            (String::from("1"), tail(&records(&["2024-05-23 10:00:00"]))),
+            (String::from("2"), KuroGachaRecordTail::default()),
+        ]);
+        let (result, records) = pull(&server, mappings).await;
+
+        assert!(result.is_ok());
+        let times: Vec<&str> = records.iter().map(|record| record.time.as_str()).collect();
+        assert_eq!(
+            times,
+            [
+                "2024-05-25 10:00:00",
+                "2024-05-24 10:00:00",
+                "2024-05-26 10:00:00"
+            ]
+        );
+        assert!(records
+            .iter()
+            .all(|record| record.uid.as_deref() == Some("500000001")));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].gacha_type, "1");
+        assert_eq!(requests[1].gacha_type, "2");
+    }
+
+    #[tokio::test]
+    async fn pull_records_within_the_stored_second() {
+        let server = MockGachaServer::start().await;
+        // A 10-pull shares one second; only the oldest 4 were stored last time
+        let ten_pull = records(&["2024-05-25 10:00:00"; 10]);
+        server.add_kuro_records("1", ten_pull.clone());
+
+        let mappings = BTreeMap::from([(String::from("1"), tail(&ten_pull[6..]))]);
+        let (result, records) = pull(&server, mappings).await;
+
+        assert!(result.is_ok());
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| record.ordinal)
+                .collect::<Vec<_>>(),
+            [9, 8, 7, 6, 5, 4]
+        );
+
+        // Nothing new once all of them are stored
+        let mappings = BTreeMap::from([(String::from("1"), tail(&ten_pull))]);
+        let (result, records) = pull(&server, mappings).await;
+
+        assert!(result.is_ok());
+        assert!(records.is_empty());
+    }
+
+    #[tokio::test]
+    async fn pull_fails_on_malformed_time() {
+        let server = MockGachaServer::start().await;
+        server.add_kuro_records("1", records(&["not a time"]));
+
+        let mappings =
+            BTreeMap::from([(String::from("1"), tail(&records(&["2024-05-23 10:00:00"])))]);
+        let (result, records) = pull(&server, mappings).await;
+
+        assert!(matches!(result, Err(Error::Time(_))));
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn fetched_records_are_identified_by_key() {
+        let mut record: WutheringWavesGachaRecord =
+            serde_json::from_value(records(&["2024-05-25 10:00:00"]).remove(0)).unwrap();
+        assert_eq!(record.id(), record.key());
+
+        record.id = Some(42);
+        assert_eq!(record.id(), "42");
+    }
+
+    #[tokio::test]
+    async fn export_and_import_through_wutheringwaves_storage() {
+        let directory = std::env::temp_dir().join(format!("wwpf-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let open = |name: &str| {
+            let path = directory.join(name);
+            async move {
+                let storage = Storage::new_with_database_file(path).await.unwrap();
+                storage.initialize().await.unwrap();
+                storage
+                    .create_account(&AccountFacet::WutheringWaves, "500000001", "", None, None)
+                    .await
+                    .unwrap();
+                storage
+            }
+        };
+
+        // Two identical pulls within the same second, told apart by their ordinal
+        let gacha_records = records(&["2024-05-25 10:00:00", "2024-05-25 10:00:00"])
+            .into_iter()
+            .enumerate()
+            .map(|(ordinal, mut record)| {
+                record["uid"] = json!("500000001");
+                record["gacha_type"] = json!("1");
+                record["ordinal"] = json!(ordinal);
+                serde_json::from_value(record).unwrap()
+            })
+            .collect::<Vec<WutheringWavesGachaRecord>>();
+        let source = open("source.db").await;
+        source
+            .save_wutheringwaves_gacha_records(&gacha_records)
+            .await
+            .unwrap();
+
+        let provider = find_provider(&AccountFacet::WutheringWaves);
+        let file = directory.join("wwpf.json");
+        provider
+            .export_gacha_records(
+                &source,
+                "500000001",
+                ExportFormat::Classic,
+                &OffsetDateTime::now_utc(),
+                File::create(&file).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let target = open("target.db").await;
+        let import =
+            || provider.import_gacha_records(&target, "500000001", File::open(&file).unwrap());
+        assert_eq!(import().await.unwrap(), 2);
+        assert_eq!(import().await.unwrap(), 0);
+
+        let keys = |records: Vec<WutheringWavesGachaRecord>| {
+            records.iter().map(GachaRecord::key).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            keys(
+                target
+                    .find_wutheringwaves_gacha_records("500000001", None, None)
+                    .await
+                    .unwrap()
+            ),
+            keys(gacha_records)
+        );
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn every_pool_type_is_pulled() {
+        let mappings = find_provider(&AccountFacet::WutheringWaves).gacha_type_mappings();
+        assert_eq!(mappings.len(), WutheringWavesPoolType::ALL.len());
+
+        for pool_type in WutheringWavesPoolType::ALL {
+            assert!(mappings
+                .iter()
+                .any(|(gacha_type, _)| *gacha_type == pool_type.value()));
+            assert_eq!(
+                WutheringWavesPoolType::from_value(pool_type.value()),
+                Some(pool_type)
+            );
+        }
+        assert_eq!(
+            WutheringWavesPoolType::GiveBack.to_string(),
+            "Giveback Custom Convene"
+        );
+    }
+}
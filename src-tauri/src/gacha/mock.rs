@@ -0,0 +1,251 @@
+extern crate form_urlencoded;
+extern crate hyper;
+extern crate serde_json;
+extern crate tokio;
+
+use super::policy::FetchPolicy;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Authkey that the mock answers with retcode -101 (authkey timeout)
+pub const EXPIRED_AUTHKEY: &str = "expired";
+
+/// Mock Gacha Server
+///   Emulates the HoYoverse `getGachaLog` and the Kuro `gacha/record/query` APIs on localhost.
+///   Point `FetchPolicy::api_base_url` at `base_url()` to use it.
+pub struct MockGachaServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Default)]
+struct MockState {
+    /// gacha_type -> records, newest first
+    hoyoverse_records: BTreeMap<String, Vec<Value>>,
+    /// cardPoolType -> records, newest first
+    kuro_records: BTreeMap<String, Vec<Value>>,
+    /// Retcodes to answer the next requests with, before serving records
+    retcodes: VecDeque<i32>,
+    requests: Vec<MockRequest>,
+}
+
+/// A request received by the mock server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockRequest {
+    pub gacha_type: String,
+    pub end_id: Option<String>,
+    pub size: Option<u32>,
+    pub lang: Option<String>,
+}
+
+impl MockGachaServer {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle(state.clone(), request))) }
+        });
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let addr = server.local_addr();
+        let (shutdown, receiver) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            receiver.await.ok();
+        }));
+
+        Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Fetch policy against the mock, without delays and with few retries
+    pub fn fetch_policy(&self) -> FetchPolicy {
+        FetchPolicy {
+            page_delay_millis: 0,
+            burst_size: 0,
+            request_timeout_secs: 5,
+            max_retries: 2,
+            retry_base_delay_millis: 10,
+            retry_max_delay_millis: 20,
+            api_base_url: Some(self.base_url()),
+            ..Default::default()
+        }
+    }
+
+    pub fn add_hoyoverse_records(&self, gacha_type: &str, records: Vec<Value>) {
+        let mut state = self.state.lock().unwrap();
+        let entry = state
+            .hoyoverse_records
+            .entry(gacha_type.to_owned())
+            .or_default();
+        entry.extend(records);
+        entry.sort_by_key(|record| std::cmp::Reverse(record_id(record)));
+    }
+
+    pub fn add_kuro_records(&self, card_pool_type: &str, records: Vec<Value>) {
+        let mut state = self.state.lock().unwrap();
+        let entry = state
+            .kuro_records
+            .entry(card_pool_type.to_owned())
+            .or_default();
+        entry.extend(records);
+        entry.sort_by(|a, b| b["time"].as_str().cmp(&a["time"].as_str()));
+    }
+
+    /// Answer the next requests with these retcodes, e.g. -110 (visit too frequently)
+    pub fn respond_retcodes(&self, retcodes: &[i32]) {
+        self.state.lock().unwrap().retcodes.extend(retcodes);
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockGachaServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+fn record_id(record: &Value) -> u64 {
+    record["id"]
+        .as_str()
+        .and_then(|id| id.parse().ok())
+        .unwrap_or_default()
+}
+
+async fn handle(
+    state: Arc<Mutex<MockState>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let path = request.uri().path().to_owned();
+    let body = match (request.method(), path.as_str()) {
+        (&Method::GET, path) if path.ends_with("/getGachaLog") => {
+            let query = request.uri().query().unwrap_or_default().to_owned();
+            get_gacha_log(&state, &query)
+        }
+        (&Method::POST, "/gacha/record/query") => {
+            let body = hyper::body::to_bytes(request.into_body())
+                .await
+                .unwrap_or_default();
+            query_gacha_record(&state, &body)
+        }
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::empty())
+                .unwrap())
+        }
+    };
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap())
+}
+
+/// HoYoverse: GET .../getGachaLog?authkey=&gacha_type=&size=&end_id=
+///   Zenless Zone Zero sends `real_gacha_type` instead of `gacha_type`.
+fn get_gacha_log(state: &Mutex<MockState>, query: &str) -> Value {
+    let queries: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+
+    let mut state = state.lock().unwrap();
+    let gacha_type = queries
+        .get("gacha_type")
+        .or_else(|| queries.get("real_gacha_type"))
+        .cloned()
+        .unwrap_or_default();
+    let size = queries.get("size").and_then(|size| size.parse().ok());
+    let end_id = queries.get("end_id").cloned();
+    state.requests.push(MockRequest {
+        gacha_type: gacha_type.clone(),
+        end_id: end_id.clone(),
+        size,
+        lang: queries.get("lang").cloned(),
+    });
+
+    if queries.get("authkey").map(String::as_str) == Some(EXPIRED_AUTHKEY) {
+        return json!({ "retcode": -101, "message": "authkey timeout", "data": null });
+    }
+
+    if let Some(retcode) = state.retcodes.pop_front() {
+        return json!({ "retcode": retcode, "message": "visit too frequently", "data": null });
+    }
+
+    let size = size.unwrap_or(20).min(20) as usize;
+    let end_id: u64 = end_id.and_then(|id| id.parse().ok()).unwrap_or_default();
+    let list: Vec<Value> = state
+        .hoyoverse_records
+        .get(&gacha_type)
+        .map(|records| {
+            records
+                .iter()
+                .filter(|record| end_id == 0 || record_id(record) < end_id)
+                .take(size)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    json!({
+        "retcode": 0,
+        "message": "OK",
+        "data": {
+            "page": "1",
+            "size": size.to_string(),
+            "total": "0",
+            "list": list,
+            "region": "os_usa",
+            "region_time_zone": -5,
+        }
+    })
+}
+
+/// Kuro: POST /gacha/record/query { cardPoolType, playerId, recordId, ... }
+fn query_gacha_record(state: &Mutex<MockState>, body: &[u8]) -> Value {
+    let body: Value = serde_json::from_slice(body).unwrap_or_default();
+
+    let mut state = state.lock().unwrap();
+    let card_pool_type = body["cardPoolType"].as_str().unwrap_or_default().to_owned();
+    state.requests.push(MockRequest {
+        gacha_type: card_pool_type.clone(),
+        end_id: None,
+        size: None,
+        lang: body["languageCode"].as_str().map(str::to_owned),
+    });
+
+    if body["recordId"].as_str().unwrap_or_default().is_empty() {
+        return json!({ "code": -1, "message": "record id is required", "data": null });
+    }
+
+    if let Some(retcode) = state.retcodes.pop_front() {
+        return json!({ "code": retcode, "message": "visit too frequently", "data": null });
+    }
+
+    let data = state
+        .kuro_records
+        .get(&card_pool_type)
+        .cloned()
+        .unwrap_or_default();
+
+    json!({ "code": 0, "message": "success", "data": data })
+}
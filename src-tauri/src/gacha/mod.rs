@@ -1,12 +1,24 @@
+pub mod archive;
 pub mod dict;
+pub mod diff;
+#[allow(clippy::module_inception)]
 mod gacha;
 pub mod hoyoverse;
 pub mod kuro;
 pub mod plugin;
-mod utilities;
+pub mod policy;
+pub mod provider;
+pub mod retry;
+pub mod task;
+pub mod utilities;
 
 pub mod srgf;
 pub mod uigf;
+pub mod uigf4;
+pub mod wwpf;
+
+#[cfg(test)]
+mod mock;
 
 pub use gacha::*;
 // pub use plugin::*;
@@ -1,92 +1,60 @@
 extern crate reqwest;
 extern crate tauri;
 extern crate time;
+extern crate tokio_util;
 
-use super::hoyoverse::genshin::GenshinGacha;
-use super::hoyoverse::hoyoverse::create_fetcher_channel;
-use super::hoyoverse::hoyoverse::GachaRecordFetcherChannelFragment;
-use super::hoyoverse::starrail::StarRailGacha;
-use super::hoyoverse::utilities::find_hoyoverse_gacha_url_and_validate_consistency;
-use super::kuro::kuro::create_kuro_fetcher_channel;
-use super::kuro::kuro::KuroGachaRecordFetcherChannelFragment;
-use super::kuro::utilities::find_kuro_gacha_url_and_validate_consistency;
-use super::kuro::wutheringwaves::WutheringWavesGacha;
-use super::srgf;
-use super::uigf;
-use super::utilities::create_default_reqwest;
-use super::GachaUrl;
-use super::GachaUrlFinder;
-use super::GameDataDirectoryFinder;
+use super::archive::ArchivedPull;
+use super::diff::{
+    diff_gacha_records, find_gacha_record_gaps, GachaRecordPullDiff, GachaRecordPullRepair,
+    KeyedGachaRecord, StoredGachaRecordKeys,
+};
+use super::provider::{find_provider, ExportFormat, PullProgress, PullReceiver, PullRequest};
+use super::task::{cancellable, Task, TaskKind, TaskRegistry};
 use crate::constants;
 use crate::error::{Error, Result};
-use crate::storage::entity_account::AccountFacet;
+use crate::settings::SettingsStore;
+use crate::storage::entity_account::{AccountFacet, AccountProperties, Model as AccountModel};
 use crate::storage::Storage;
+use serde::Serialize;
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::path::PathBuf;
 use tauri::plugin::{Builder as TauriPluginBuilder, TauriPlugin};
 use time::format_description;
+use time::format_description::well_known::Rfc3339;
 use time::{OffsetDateTime, UtcOffset};
+use tokio_util::sync::CancellationToken;
 
 /// Tauri commands
 
 #[tauri::command]
 async fn find_game_data_directories(facet: AccountFacet) -> Result<Vec<PathBuf>> {
-    match facet {
-        AccountFacet::Genshin => GenshinGacha.find_game_data_directories(),
-        AccountFacet::StarRail => StarRailGacha.find_game_data_directories(),
-        AccountFacet::WutheringWaves => WutheringWavesGacha.find_game_data_directories(),
-    }
+    find_provider(&facet).find_game_data_directories()
 }
 
 #[tauri::command]
 async fn find_gacha_url(
+    settings: tauri::State<'_, SettingsStore>,
     facet: AccountFacet,
     uid: String,
     game_data_dir: PathBuf,
 ) -> Result<String> {
-    let gacha_url: GachaUrl = match facet {
-        AccountFacet::Genshin => {
-            let gacha_urls = GenshinGacha.find_gacha_urls(game_data_dir)?;
-            find_hoyoverse_gacha_url_and_validate_consistency(
-                &GenshinGacha,
-                &facet,
-                &uid,
-                &gacha_urls,
-            )
-            .await?
-        }
-        AccountFacet::StarRail => {
-            let gacha_urls = StarRailGacha.find_gacha_urls(game_data_dir)?;
-            find_hoyoverse_gacha_url_and_validate_consistency(
-                &StarRailGacha,
-                &facet,
-                &uid,
-                &gacha_urls,
-            )
-            .await?
-        }
-        AccountFacet::WutheringWaves => {
-            let gacha_urls = WutheringWavesGacha.find_gacha_urls(game_data_dir)?;
-            find_kuro_gacha_url_and_validate_consistency(
-                &WutheringWavesGacha,
-                &facet,
-                &uid,
-                &gacha_urls,
-            )
-            .await?
-        }
-        AccountFacet::WutheringWaves => {
-            let gacha_urls = WutheringWavesGacha.find_gacha_urls(game_data_dir)?;
-            find_kuro_gacha_url_and_validate_consistency(
-                &WutheringWavesGacha,
-                &facet,
-                &uid,
-                &gacha_urls,
-            )
-            .await?
-        }
-    };
+    discover_gacha_url(&settings, &facet, &uid, game_data_dir).await
+}
+
+async fn discover_gacha_url(
+    settings: &SettingsStore,
+    facet: &AccountFacet,
+    uid: &str,
+    game_data_dir: PathBuf,
+) -> Result<String> {
+    let reqwest = settings.create_reqwest()?;
+    let policy = settings.get().fetch_policy.resolve(facet);
+    let provider = find_provider(facet);
+    let gacha_urls = provider.find_gacha_urls(&game_data_dir)?;
+    let gacha_url = provider
+        .find_valid_gacha_url(&reqwest, uid, &gacha_urls, &policy)
+        .await?;
 
     Ok(gacha_url.to_string())
 }
@@ -96,138 +64,611 @@ async fn find_gacha_url(
 async fn pull_all_gacha_records(
     window: tauri::Window,
     storage: tauri::State<'_, Storage>,
+    settings: tauri::State<'_, SettingsStore>,
+    tasks: tauri::State<'_, TaskRegistry>,
     facet: AccountFacet,
-    #[allow(unused)] uid: String,
+    uid: String,
     gacha_url: String,
-    gacha_type_and_last_query_mappings: BTreeMap<String, Option<String>>,
+    gacha_type_and_last_query_mappings: Option<BTreeMap<String, Option<String>>>,
     event_channel: String,
     save_to_storage: Option<bool>,
-) -> Result<()> {
-    let reqwest = create_default_reqwest()?;
+    dry_run: Option<bool>,
+    repair: Option<bool>,
+    task_id: Option<String>,
+) -> Result<PullOutcome> {
     let save_to_storage = save_to_storage.unwrap_or(false);
+    let mode = match (dry_run.unwrap_or(false), repair.unwrap_or(false)) {
+        (false, false) => PullMode::Incremental,
+        (true, false) => PullMode::DryRun,
+        (false, true) => PullMode::Repair,
+        (true, true) => return Err(Error::UnsupportedOperation),
+    };
+    let task_id = task_id.unwrap_or_else(|| Task::default_id(TaskKind::Pull, &facet, &uid));
 
     // TODO: validate uid and gacha_url consistency ?
 
-    match facet {
-        AccountFacet::Genshin => {
-            create_fetcher_channel(
-                GenshinGacha,
-                reqwest,
-                GenshinGacha,
-                gacha_url,
-                gacha_type_and_last_query_mappings,
-                |fragment| async {
-                    window.emit(&event_channel, &fragment)?;
-                    if save_to_storage {
-                        if let GachaRecordFetcherChannelFragment::Data(data) = fragment {
-                            storage.save_genshin_gacha_records(&data).await?;
-                        }
-                    }
-                    Ok(())
-                },
-            )
-            .await?
+    pull_gacha_records_with_task(
+        &storage,
+        &settings,
+        &tasks,
+        PullTask {
+            id: &task_id,
+            facet: &facet,
+            uid: &uid,
+            gacha_url,
+            gacha_type_and_last_query_mappings: gacha_type_and_last_query_mappings
+                .unwrap_or_default(),
+            save_to_storage,
+            mode,
+            batch: None,
+        },
+        &|fragment| Ok(window.emit(&event_channel, fragment)?),
+    )
+    .await
+}
+
+/// Pull Mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PullMode {
+    /// Pull the records newer than the last query
+    Incremental,
+    /// Walk the whole server window and diff it against storage, saving nothing
+    DryRun,
+    /// Walk the whole server window and save the records missing from storage
+    Repair,
+}
+
+/// Pull Report
+///   What a full walk of the server window found.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum PullReport {
+    Diff(GachaRecordPullDiff),
+    Repair(GachaRecordPullRepair),
+}
+
+/// Pull Outcome
+///   Cancelled pulls keep the records already saved, and report them rather than an error.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PullOutcome {
+    /// Completed or cancelled
+    status: AccountPullStatus,
+    /// Number of records newly saved to storage
+    inserted: u64,
+    /// None for an incremental or cancelled pull
+    report: Option<PullReport>,
+}
+
+/// Pull Task
+///   A pull as it is registered in the task registry.
+struct PullTask<'a> {
+    id: &'a str,
+    facet: &'a AccountFacet,
+    uid: &'a str,
+    gacha_url: String,
+    /// Empty to pull every gacha type, with the last queries derived from storage
+    gacha_type_and_last_query_mappings: BTreeMap<String, Option<String>>,
+    save_to_storage: bool,
+    mode: PullMode,
+    /// Batch the pull belongs to, cancelling it cancels the pull
+    batch: Option<&'a CancellationToken>,
+}
+
+/// Pull the gacha records as a registered task.
+async fn pull_gacha_records_with_task(
+    storage: &Storage,
+    settings: &SettingsStore,
+    tasks: &TaskRegistry,
+    task: PullTask<'_>,
+    emit: &(dyn Fn(serde_json::Value) -> Result<()> + Send + Sync),
+) -> Result<PullOutcome> {
+    let PullTask {
+        id: task_id,
+        facet,
+        uid,
+        gacha_url,
+        gacha_type_and_last_query_mappings,
+        save_to_storage,
+        mode,
+        batch,
+    } = task;
+    let gacha_type_and_last_query_mappings = if gacha_type_and_last_query_mappings.is_empty() {
+        find_gacha_type_and_last_query_mappings(storage, facet, uid).await?
+    } else {
+        gacha_type_and_last_query_mappings
+    };
+    let (gacha_type_and_last_query_mappings, save_to_storage) = match mode {
+        PullMode::Incremental => (gacha_type_and_last_query_mappings, save_to_storage),
+        PullMode::DryRun | PullMode::Repair => {
+            let mappings = gacha_type_and_last_query_mappings
+                .into_keys()
+                .map(|gacha_type| (gacha_type, None))
+                .collect();
+            (mappings, mode == PullMode::Repair)
         }
-        AccountFacet::StarRail => {
-            create_fetcher_channel(
-                StarRailGacha,
-                reqwest,
-                StarRailGacha,
-                gacha_url,
-                gacha_type_and_last_query_mappings,
-                |fragment| async {
-                    window.emit(&event_channel, &fragment)?;
-                    if save_to_storage {
-                        if let GachaRecordFetcherChannelFragment::Data(data) = fragment {
-                            storage.save_starrail_gacha_records(&data).await?;
-                        }
-                    }
-                    Ok(())
+    };
+
+    // Both full walks compare against the records stored before it
+    let stored = match mode {
+        PullMode::Incremental => BTreeMap::new(),
+        PullMode::DryRun | PullMode::Repair => {
+            let gacha_types = gacha_type_and_last_query_mappings
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            find_stored_gacha_records(storage, facet, uid, &gacha_types).await?
+        }
+    };
+
+    let reqwest = settings.create_reqwest()?;
+    let mut policy = settings.get().fetch_policy.resolve(facet);
+    if let Some(language) = find_preferred_language(storage, facet, uid).await? {
+        policy.language = Some(language);
+    }
+
+    let cancellation = match batch {
+        Some(batch) => tasks.register_child(task_id, TaskKind::Pull, facet, uid, batch)?,
+        None => tasks.register(task_id, TaskKind::Pull, facet, uid)?,
+    };
+    policy.archive = match settings.response_archive().start_pull(facet, uid) {
+        Ok(archive) => archive,
+        Err(error) => {
+            let result = Err(error);
+            tasks.finish(task_id, &result);
+            return result;
+        }
+    };
+    let request = PullRequest {
+        reqwest,
+        uid: uid.to_owned(),
+        gacha_url,
+        gacha_type_and_last_query_mappings,
+        policy,
+        cancellation,
+    };
+    let observer = |progress: PullProgress| {
+        tasks.progress(task_id, progress.records);
+        emit(progress.fragment)
+    };
+    let receiver = PullReceiver::new(storage, facet, uid, save_to_storage, &observer);
+    let receiver = match mode {
+        PullMode::Incremental => receiver,
+        PullMode::DryRun => receiver.collect_records(),
+        PullMode::Repair => receiver.collect_records().skip_stored_records(
+            stored
+                .iter()
+                .map(|(gacha_type, records)| {
+                    (gacha_type.clone(), StoredGachaRecordKeys::new(records))
+                })
+                .collect(),
+        ),
+    };
+
+    let result = find_provider(facet)
+        .pull_gacha_records(request, &receiver)
+        .await;
+
+    tasks.finish(task_id, &result);
+    let (status, report) = match result {
+        Ok(()) => (
+            AccountPullStatus::Completed,
+            report_pulled_gacha_records(mode, stored, receiver.take_collected()),
+        ),
+        // A partial walk would report the records past it as missing
+        Err(Error::TaskCancelled) => (AccountPullStatus::Cancelled, None),
+        Err(error) => return Err(error),
+    };
+
+    Ok(PullOutcome {
+        status,
+        inserted: receiver.inserted(),
+        report,
+    })
+}
+
+/// Stored records of every pulled gacha type, keyed by the pulled gacha type
+async fn find_stored_gacha_records(
+    storage: &Storage,
+    facet: &AccountFacet,
+    uid: &str,
+    gacha_types: &[&str],
+) -> Result<BTreeMap<String, Vec<KeyedGachaRecord>>> {
+    let provider = find_provider(facet);
+
+    let mut stored = BTreeMap::new();
+    for gacha_type in gacha_types {
+        let stored_gacha_types = provider
+            .gacha_type_mappings()
+            .iter()
+            .find(|(value, _)| value == gacha_type)
+            .map(|(_, stored_gacha_types)| stored_gacha_types.to_vec())
+            .unwrap_or_else(|| vec![*gacha_type]);
+
+        let records = provider
+            .find_stored_records(storage, uid, &stored_gacha_types)
+            .await?;
+        stored.insert(gacha_type.to_string(), records);
+    }
+
+    Ok(stored)
+}
+
+/// Compare the collected records of a full walk against the stored ones
+fn report_pulled_gacha_records(
+    mode: PullMode,
+    stored: BTreeMap<String, Vec<KeyedGachaRecord>>,
+    mut collected: BTreeMap<String, Vec<KeyedGachaRecord>>,
+) -> Option<PullReport> {
+    match mode {
+        PullMode::Incremental => None,
+        PullMode::DryRun => Some(PullReport::Diff(GachaRecordPullDiff {
+            gacha_types: stored
+                .into_iter()
+                .map(|(gacha_type, stored)| {
+                    let fetched = collected.remove(&gacha_type).unwrap_or_default();
+                    diff_gacha_records(&gacha_type, fetched, stored)
+                })
+                .collect(),
+        })),
+        PullMode::Repair => Some(PullReport::Repair(GachaRecordPullRepair {
+            gacha_types: stored
+                .into_iter()
+                .map(|(gacha_type, stored)| {
+                    let fetched = collected.remove(&gacha_type).unwrap_or_default();
+                    find_gacha_record_gaps(&gacha_type, fetched, &stored)
+                })
+                .collect(),
+        })),
+    }
+}
+
+/// Derive the gacha type and last end id (or last time) mappings from storage
+async fn find_gacha_type_and_last_query_mappings(
+    storage: &Storage,
+    facet: &AccountFacet,
+    uid: &str,
+) -> Result<BTreeMap<String, Option<String>>> {
+    let provider = find_provider(facet);
+
+    let mut mappings = BTreeMap::new();
+    for (gacha_type, stored_gacha_types) in provider.gacha_type_mappings() {
+        let last_query = provider
+            .find_last_query(storage, uid, stored_gacha_types)
+            .await?;
+        mappings.insert(gacha_type.to_string(), last_query);
+    }
+
+    Ok(mappings)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum PullAllAccountsFragment<'a> {
+    #[serde(rename_all = "camelCase")]
+    Account {
+        facet: &'a AccountFacet,
+        uid: &'a str,
+    },
+    #[serde(rename_all = "camelCase")]
+    Progress {
+        facet: &'a AccountFacet,
+        uid: &'a str,
+        fragment: serde_json::Value,
+    },
+    Summary(&'a [AccountPullSummary]),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountPullSummary {
+    facet: AccountFacet,
+    uid: String,
+    status: AccountPullStatus,
+    inserted: u64,
+    error: Option<Error>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum AccountPullStatus {
+    Completed,
+    Failed,
+    /// Skipped, a pull of the account was already running
+    Busy,
+    /// The pull or the whole batch was cancelled before it completed
+    Cancelled,
+}
+
+/// Task id of `pull_all_accounts`, there is one at most
+const PULL_ALL_ACCOUNTS_TASK_ID: &str = "pullAll";
+
+#[tauri::command]
+async fn pull_all_accounts(
+    window: tauri::Window,
+    storage: tauri::State<'_, Storage>,
+    settings: tauri::State<'_, SettingsStore>,
+    tasks: tauri::State<'_, TaskRegistry>,
+    event_channel: String,
+) -> Result<Vec<AccountPullSummary>> {
+    let cancellation = tasks.register_batch(PULL_ALL_ACCOUNTS_TASK_ID, TaskKind::PullAll)?;
+
+    let result = pull_accounts(
+        &window,
+        &storage,
+        &settings,
+        &tasks,
+        &cancellation,
+        &event_channel,
+    )
+    .await;
+
+    // A cancelled batch still reports the accounts it went through
+    match &result {
+        Ok(_) if cancellation.is_cancelled() => tasks.finish(
+            PULL_ALL_ACCOUNTS_TASK_ID,
+            &Err::<(), _>(Error::TaskCancelled),
+        ),
+        result => tasks.finish(PULL_ALL_ACCOUNTS_TASK_ID, result),
+    }
+    result
+}
+
+async fn pull_accounts(
+    window: &tauri::Window,
+    storage: &Storage,
+    settings: &SettingsStore,
+    tasks: &TaskRegistry,
+    cancellation: &CancellationToken,
+    event_channel: &str,
+) -> Result<Vec<AccountPullSummary>> {
+    let accounts = storage.find_accounts(None).await?;
+    let mut summaries = Vec::with_capacity(accounts.len());
+
+    for account in accounts {
+        let summary = |status, inserted, error| AccountPullSummary {
+            facet: account.facet.clone(),
+            uid: account.uid.clone(),
+            status,
+            inserted,
+            error,
+        };
+
+        // The account being pulled stops between pages, the remaining ones are not started
+        if cancellation.is_cancelled() {
+            summaries.push(summary(AccountPullStatus::Cancelled, 0, None));
+            continue;
+        }
+
+        let task_id = Task::default_id(TaskKind::Pull, &account.facet, &account.uid);
+        if tasks.is_running(&task_id) {
+            summaries.push(summary(AccountPullStatus::Busy, 0, None));
+            continue;
+        }
+
+        emit_accounts_fragment(
+            window,
+            event_channel,
+            PullAllAccountsFragment::Account {
+                facet: &account.facet,
+                uid: &account.uid,
+            },
+        )?;
+
+        let emit = |fragment| {
+            emit_accounts_fragment(
+                window,
+                event_channel,
+                PullAllAccountsFragment::Progress {
+                    facet: &account.facet,
+                    uid: &account.uid,
+                    fragment,
                 },
             )
-            .await?
-        }
-        AccountFacet::WutheringWaves => {
-            create_kuro_fetcher_channel(
-                WutheringWavesGacha,
-                reqwest,
-                WutheringWavesGacha,
+        };
+
+        // One failed account must not stop the others
+        let result = pull_account(
+            storage,
+            settings,
+            tasks,
+            cancellation,
+            &task_id,
+            &account,
+            &emit,
+        )
+        .await;
+        summaries.push(match result {
+            Ok(outcome) => summary(outcome.status, outcome.inserted, None),
+            // Started by another pull since the check above
+            Err(Error::TaskAlreadyRunning) => summary(AccountPullStatus::Busy, 0, None),
+            Err(error) => summary(AccountPullStatus::Failed, 0, Some(error)),
+        });
+        tasks.progress(PULL_ALL_ACCOUNTS_TASK_ID, 1);
+    }
+
+    emit_accounts_fragment(
+        window,
+        event_channel,
+        PullAllAccountsFragment::Summary(&summaries),
+    )?;
+    Ok(summaries)
+}
+
+fn emit_accounts_fragment(
+    window: &tauri::Window,
+    event_channel: &str,
+    fragment: PullAllAccountsFragment,
+) -> Result<()> {
+    // Summaries hold errors, which are serializable but not cloneable
+    Ok(window.emit(event_channel, serde_json::to_value(fragment)?)?)
+}
+
+async fn pull_account(
+    storage: &Storage,
+    settings: &SettingsStore,
+    tasks: &TaskRegistry,
+    batch: &CancellationToken,
+    task_id: &str,
+    account: &AccountModel,
+    emit: &(dyn Fn(serde_json::Value) -> Result<()> + Send + Sync),
+) -> Result<PullOutcome> {
+    let facet = &account.facet;
+    let uid = &account.uid;
+    let mappings = find_gacha_type_and_last_query_mappings(storage, facet, uid).await?;
+
+    let pull = |gacha_url: String| {
+        pull_gacha_records_with_task(
+            storage,
+            settings,
+            tasks,
+            PullTask {
+                id: task_id,
+                facet,
                 uid,
                 gacha_url,
-                gacha_type_and_last_query_mappings,
-                |fragment| async {
-                    window.emit(&event_channel, &fragment)?;
-                    if save_to_storage {
-                        if let KuroGachaRecordFetcherChannelFragment::Data(data) = fragment {
-                            storage.save_wutheringwaves_gacha_records(&data).await?;
-                        }
-                    }
-                    Ok(())
-                },
-            )
-            .await?
-        }
+                gacha_type_and_last_query_mappings: mappings.clone(),
+                save_to_storage: true,
+                mode: PullMode::Incremental,
+                batch: Some(batch),
+            },
+            emit,
+        )
+    };
+
+    let outcome = match account.gacha_url.clone() {
+        Some(gacha_url) => match pull(gacha_url).await {
+            Err(Error::TimeoutdGachaUrl) => {
+                pull(rediscover_gacha_url(storage, settings, account).await?).await?
+            }
+            result => result?,
+        },
+        None => pull(rediscover_gacha_url(storage, settings, account).await?).await?,
+    };
+    if outcome.status == AccountPullStatus::Cancelled {
+        return Ok(outcome);
     }
 
-    Ok(())
+    let mut properties = account
+        .properties
+        .as_ref()
+        .map(|properties| properties.0.clone())
+        .unwrap_or_default();
+    let now = OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .map_err(time::Error::from)?;
+    properties.insert(String::from("lastGachaUpdated"), now.into());
+    storage
+        .update_account_properties(facet, uid, Some(&AccountProperties(properties)))
+        .await?;
+
+    Ok(outcome)
+}
+
+async fn rediscover_gacha_url(
+    storage: &Storage,
+    settings: &SettingsStore,
+    account: &AccountModel,
+) -> Result<String> {
+    let gacha_url = discover_gacha_url(
+        settings,
+        &account.facet,
+        &account.uid,
+        PathBuf::from(&account.game_data_dir),
+    )
+    .await?;
+
+    storage
+        .update_account_gacha_url(&account.facet, &account.uid, Some(&gacha_url))
+        .await?;
+    Ok(gacha_url)
+}
+
+/// Language the account prefers its records in, none for the gacha url language
+async fn find_preferred_language(
+    storage: &Storage,
+    facet: &AccountFacet,
+    uid: &str,
+) -> Result<Option<String>> {
+    let account = storage.try_find_account(facet, uid).await?;
+    Ok(account
+        .and_then(|account| account.properties)
+        .and_then(|properties| properties.preferred_language().map(str::to_owned)))
 }
 
 #[tauri::command]
 async fn import_gacha_records(
     storage: tauri::State<'_, Storage>,
+    tasks: tauri::State<'_, TaskRegistry>,
     facet: AccountFacet,
     uid: String,
     file: String,
+    task_id: Option<String>,
 ) -> Result<u64> {
-    let file = File::open(file)?;
-
-    match facet {
-        AccountFacet::Genshin => {
-            let mut uigf = uigf::UIGF::from_reader(file)?;
-            if uigf.info.uid != uid {
-                return Err(Error::UIGFOrSRGFMismatchedUID {
-                    expected: uid,
-                    actual: uigf.info.uid,
-                });
-            }
-
-            let gacha_records = uigf::convert_uigf_to_offical(&mut uigf)?;
-            storage.save_genshin_gacha_records(&gacha_records).await
-        }
-        AccountFacet::StarRail => {
-            let mut srgf = srgf::SRGF::from_reader(file)?;
-            if srgf.info.uid != uid {
-                return Err(Error::UIGFOrSRGFMismatchedUID {
-                    expected: uid,
-                    actual: srgf.info.uid,
-                });
-            }
+    let task_id = task_id.unwrap_or_else(|| Task::default_id(TaskKind::Import, &facet, &uid));
+    let cancellation = tasks.register(&task_id, TaskKind::Import, &facet, &uid)?;
 
-            let gacha_records = srgf::convert_srgf_to_offical(&mut srgf)?;
-            storage.save_starrail_gacha_records(&gacha_records).await
-        }
-        AccountFacet::WutheringWaves => {
-            //TODO: ww-ify this
-            let mut srgf = srgf::SRGF::from_reader(file)?;
-            if srgf.info.uid != uid {
-                return Err(Error::UIGFOrSRGFMismatchedUID {
-                    expected: uid,
-                    actual: srgf.info.uid,
-                });
-            }
+    let result = cancellable(
+        &cancellation,
+        import_gacha_records_from_file(&storage, facet, uid, file),
+    )
+    .await;
 
-            let gacha_records = srgf::convert_srgf_to_offical(&mut srgf)?;
-            storage.save_starrail_gacha_records(&gacha_records).await
-        }
+    if let Ok(changes) = result {
+        tasks.progress(&task_id, changes);
     }
+    tasks.finish(&task_id, &result);
+    result
+}
+
+async fn import_gacha_records_from_file(
+    storage: &Storage,
+    facet: AccountFacet,
+    uid: String,
+    file: String,
+) -> Result<u64> {
+    let file = File::open(file)?;
+    find_provider(&facet)
+        .import_gacha_records(storage, &uid, file)
+        .await
 }
 
 #[tauri::command]
 async fn export_gacha_records(
     storage: tauri::State<'_, Storage>,
+    tasks: tauri::State<'_, TaskRegistry>,
     facet: AccountFacet,
     uid: String,
     directory: String,
+    format: Option<ExportFormat>,
+    task_id: Option<String>,
+) -> Result<PathBuf> {
+    let task_id = task_id.unwrap_or_else(|| Task::default_id(TaskKind::Export, &facet, &uid));
+    let cancellation = tasks.register(&task_id, TaskKind::Export, &facet, &uid)?;
+
+    let result = export_gacha_records_to_directory(
+        &storage,
+        &cancellation,
+        facet,
+        uid,
+        format.unwrap_or_default(),
+        directory,
+    )
+    .await;
+
+    tasks.finish(&task_id, &result);
+    result
+}
+
+/// Export into a temporary file renamed once complete,
+/// so that a failed or cancelled export leaves no partial file behind.
+async fn export_gacha_records_to_directory(
+    storage: &Storage,
+    cancellation: &CancellationToken,
+    facet: AccountFacet,
+    uid: String,
+    format: ExportFormat,
+    directory: String,
 ) -> Result<PathBuf> {
     let locale_offset = UtcOffset::current_local_offset().map_err(time::Error::from)?;
     let now = OffsetDateTime::now_utc().to_offset(locale_offset);
@@ -238,80 +679,80 @@ async fn export_gacha_records(
     }
 
     // output file
-    let format = format_description::parse("[year][month][day]_[hour][minute][second]")
+    let time_format = format_description::parse("[year][month][day]_[hour][minute][second]")
         .map_err(time::Error::from)?;
-    let time = now.format(&format).map_err(time::Error::from)?;
+    let time = now.format(&time_format).map_err(time::Error::from)?;
 
-    let (primary, format) = match facet {
-        AccountFacet::Genshin => ("Unified Standardized GenshinData Format", "UIGF"),
-        AccountFacet::StarRail => ("Star Rail GachaLog Format", "SRGF"),
-        AccountFacet::WutheringWaves => ("Wuthering Waves Proprietary Format", "WWPF"),
-    };
+    let provider = find_provider(&facet);
+    let (primary, short) = provider
+        .export_format(format)
+        .ok_or(Error::UnsupportedOperation)?;
     let filename = format!(
         "{}_{}_{}_{uid}_{time}.json",
         constants::NAME,
         primary,
-        format
+        short
     );
     let filename = directory.join(filename);
-    let writer = File::create(&filename)?;
+    let temporary = filename.with_extension("json.tmp");
+    let writer = File::create(&temporary)?;
+    let result = cancellable(
+        cancellation,
+        provider.export_gacha_records(storage, &uid, format, &now, writer),
+    )
+    .await
+    .and_then(|_| Ok(std::fs::rename(&temporary, &filename)?));
 
-    match facet {
-        AccountFacet::Genshin => {
-            let gacha_records = storage.find_genshin_gacha_records(&uid, None, None).await?;
-            let lang = gacha_records
-                .first()
-                .map(|v| v.lang.clone())
-                .unwrap_or("en-us".to_owned());
-
-            // convert to uigf and write
-            let uigf_list = uigf::convert_offical_to_uigf(&gacha_records)?;
-            let uigf = uigf::UIGF::new(uid, lang, &now, uigf_list)?;
-            uigf.to_writer(writer, false)?;
-        }
-        AccountFacet::StarRail => {
-            let gacha_records = storage
-                .find_starrail_gacha_records(&uid, None, None)
-                .await?;
-            let lang = gacha_records
-                .first()
-                .map(|v| v.lang.clone())
-                .unwrap_or("en-us".to_owned());
-            let time_zone = 8; // TODO: export time zone
-
-            // convert to srgf and write
-            let srgf_list = srgf::convert_offical_to_srgf(&gacha_records)?;
-            let srgf = srgf::SRGF::new(uid, lang, time_zone, &now, srgf_list)?;
-            srgf.to_writer(writer, false)?;
-        }
-        AccountFacet::WutheringWaves => {
-            //TODO: This is badge
-            let gacha_records = storage
-                .find_starrail_gacha_records(&uid, None, None)
-                .await?;
-            let lang = gacha_records
-                .first()
-                .map(|v| v.lang.clone())
-                .unwrap_or("en-us".to_owned());
-            let time_zone = 8; // TODO: export time zone
-
-            // convert to srgf and write
-            let srgf_list = srgf::convert_offical_to_srgf(&gacha_records)?;
-            let srgf = srgf::SRGF::new(uid, lang, time_zone, &now, srgf_list)?;
-            srgf.to_writer(writer, false)?;
-        }
+    if let Err(error) = result {
+        let _ = std::fs::remove_file(&temporary);
+        return Err(error);
+    }
+
+    Ok(filename)
+}
+
+#[tauri::command]
+async fn find_response_archives(
+    settings: tauri::State<'_, SettingsStore>,
+) -> Result<Vec<ArchivedPull>> {
+    settings.response_archive().find_pulls()
+}
+
+#[tauri::command]
+async fn export_response_archive(
+    settings: tauri::State<'_, SettingsStore>,
+    pull_id: String,
+    directory: String,
+) -> Result<PathBuf> {
+    let directory = PathBuf::from(directory);
+    if !directory.exists() {
+        std::fs::create_dir(&directory)?;
     }
 
+    let filename = directory.join(format!("{}_Responses_{pull_id}.json", constants::NAME));
+    let writer = File::create(&filename)?;
+    settings.response_archive().export_pull(&pull_id, writer)?;
+
     Ok(filename)
 }
 
+#[tauri::command]
+async fn find_tasks(tasks: tauri::State<'_, TaskRegistry>) -> Result<Vec<Task>> {
+    Ok(tasks.tasks())
+}
+
+#[tauri::command]
+async fn cancel_task(tasks: tauri::State<'_, TaskRegistry>, task_id: String) -> Result<bool> {
+    Ok(tasks.cancel(&task_id))
+}
+
 /// Tauri plugin
 
 #[derive(Default)]
 pub struct GachaPluginBuilder {}
 
 impl GachaPluginBuilder {
-    const PLUGIN_NAME: &str = "gacha";
+    const PLUGIN_NAME: &'static str = "gacha";
 
     pub fn new() -> Self {
         Self::default()
@@ -319,13 +760,114 @@ impl GachaPluginBuilder {
 
     pub fn build(self) -> TauriPlugin<tauri::Wry> {
         TauriPluginBuilder::new(Self::PLUGIN_NAME)
+            .setup(|app_handle| {
+                use tauri::Manager;
+                app_handle.manage(TaskRegistry::default());
+                Ok(())
+            })
             .invoke_handler(tauri::generate_handler![
                 find_game_data_directories,
                 find_gacha_url,
                 pull_all_gacha_records,
+                pull_all_accounts,
                 import_gacha_records,
-                export_gacha_records
+                export_gacha_records,
+                find_response_archives,
+                export_response_archive,
+                find_tasks,
+                cancel_task
             ])
             .build()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::{pull_gacha_records_with_task, AccountPullStatus, PullMode, PullTask};
+    use crate::gacha::mock::MockGachaServer;
+    use crate::gacha::task::TaskRegistry;
+    use crate::settings::SettingsStore;
+    use crate::storage::entity_account::AccountFacet;
+    use crate::storage::Storage;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn cancelled_pull_reports_the_saved_records() {
+        let directory = std::env::temp_dir().join(format!("plugin-cancel-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let storage = Storage::new_with_database_file(directory.join("database.db"))
+            .await
+            .unwrap();
+        storage.initialize().await.unwrap();
+        storage
+            .create_account(&AccountFacet::Genshin, "100000001", "", None, None)
+            .await
+            .unwrap();
+
+        let server = MockGachaServer::start().await;
+        server.add_hoyoverse_records(
+            "301",
+            (0..45)
+                .map(|n| {
+                    json!({
+                        "id": (1_700_000_000_000_000_000u64 + n).to_string(),
+                        "uid": "100000001",
+                        "gacha_type": "301",
+                        "item_id": "",
+                        "count": "1",
+                        "time": "2023-05-01 12:00:00",
+                        "name": "Cool Steel",
+                        "lang": "en-us",
+                        "item_type": "Weapon",
+                        "rank_type": "3",
+                    })
+                })
+                .collect(),
+        );
+        let settings =
+            SettingsStore::new_with_settings_file(directory.join("settings.json")).unwrap();
+        let mut updated = settings.get();
+        updated.fetch_policy.default = server.fetch_policy();
+        settings.update(updated).unwrap();
+
+        // Closing the dialog once the first page is saved
+        let tasks = TaskRegistry::default();
+        let outcome = pull_gacha_records_with_task(
+            &storage,
+            &settings,
+            &tasks,
+            PullTask {
+                id: "pull",
+                facet: &AccountFacet::Genshin,
+                uid: "100000001",
+                gacha_url: String::from("https://public-operation-hk4e-sg.hoyoverse.com/gacha_info/api/getGachaLog?authkey_ver=1&lang=en&authkey=valid&gacha_type=301"),
+                gacha_type_and_last_query_mappings: BTreeMap::from([(String::from("301"), None)]),
+                save_to_storage: true,
+                mode: PullMode::Incremental,
+                batch: None,
+            },
+            &|fragment| {
+                if fragment.get("saved").is_some() {
+                    tasks.cancel("pull");
+                }
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.status, AccountPullStatus::Cancelled);
+        assert!(outcome.report.is_none());
+        let stored = storage
+            .find_genshin_gacha_records("100000001", None, None)
+            .await
+            .unwrap();
+        assert!(outcome.inserted > 0 && outcome.inserted < 45);
+        assert_eq!(outcome.inserted, stored.len() as u64);
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
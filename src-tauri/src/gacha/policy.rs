@@ -0,0 +1,192 @@
+extern crate serde;
+
+use crate::gacha::archive::PullArchive;
+use crate::gacha::retry::RetryPolicy;
+use crate::storage::entity_account::AccountFacet;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The gacha log API never returns more than 20 records per page
+const MAX_PAGE_SIZE: u32 = 20;
+
+/// Fetch Policy
+///   Pacing, timeout, retry and endpoint knobs for pulling gacha records.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FetchPolicy {
+    /// Records requested per page
+    pub page_size: u32,
+    /// Delay after each page
+    pub page_delay_millis: u64,
+    /// Number of pages between burst pauses, 0 to disable
+    pub burst_size: u32,
+    /// Extra pause after every `burst_size` pages
+    pub burst_pause_millis: u64,
+    /// Timeout of a single request
+    pub request_timeout_secs: u64,
+    /// Retries of a transient failure before giving up
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled on each following one
+    pub retry_base_delay_millis: u64,
+    /// Upper bound of the backoff between retries
+    pub retry_max_delay_millis: u64,
+    /// Replace the scheme, host and port of the gacha record API,
+    /// e.g. with a local mock server
+    pub api_base_url: Option<String>,
+    /// Language of the fetched records, replaces the `lang` of the gacha url,
+    /// e.g. `en-us`, `zh-cn`. None to keep the one the game used
+    pub language: Option<String>,
+    /// Where to archive the raw page responses, set per pull from the settings
+    #[serde(skip)]
+    pub archive: Option<PullArchive>,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        Self {
+            page_size: MAX_PAGE_SIZE,
+            page_delay_millis: 1000,
+            burst_size: 5,
+            burst_pause_millis: 3000,
+            request_timeout_secs: 30,
+            max_retries: RetryPolicy::default().max_retries,
+            retry_base_delay_millis: RetryPolicy::default().base_delay.as_millis() as u64,
+            retry_max_delay_millis: RetryPolicy::default().max_delay.as_millis() as u64,
+            api_base_url: None,
+            language: None,
+            archive: None,
+        }
+    }
+}
+
+impl FetchPolicy {
+    /// Clamp values the server or the client would choke on.
+    pub fn normalized(mut self) -> Self {
+        self.page_size = self.page_size.clamp(1, MAX_PAGE_SIZE);
+        self.request_timeout_secs = self.request_timeout_secs.max(1);
+        self.retry_max_delay_millis = self
+            .retry_max_delay_millis
+            .max(self.retry_base_delay_millis);
+        self
+    }
+
+    pub fn page_delay(&self) -> Duration {
+        Duration::from_millis(self.page_delay_millis)
+    }
+
+    pub fn burst_pause(&self) -> Duration {
+        Duration::from_millis(self.burst_pause_millis)
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs)
+    }
+
+    /// Whether a burst pause is due before the given page (1-based).
+    pub fn is_burst_pause(&self, pagination: u32) -> bool {
+        self.burst_size > 0 && pagination % self.burst_size == 0
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.max_retries,
+            base_delay: Duration::from_millis(self.retry_base_delay_millis),
+            max_delay: Duration::from_millis(self.retry_max_delay_millis),
+        }
+    }
+}
+
+/// Fetch Policy Override
+///   Only the present fields replace the default policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FetchPolicyOverride {
+    pub page_size: Option<u32>,
+    pub page_delay_millis: Option<u64>,
+    pub burst_size: Option<u32>,
+    pub burst_pause_millis: Option<u64>,
+    pub request_timeout_secs: Option<u64>,
+    pub max_retries: Option<u32>,
+    pub retry_base_delay_millis: Option<u64>,
+    pub retry_max_delay_millis: Option<u64>,
+    pub api_base_url: Option<String>,
+    pub language: Option<String>,
+}
+
+/// Fetch Policy Settings
+///   A default policy with per-facet overrides.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FetchPolicySettings {
+    pub default: FetchPolicy,
+    pub overrides: HashMap<AccountFacet, FetchPolicyOverride>,
+}
+
+impl FetchPolicySettings {
+    pub fn resolve(&self, facet: &AccountFacet) -> FetchPolicy {
+        let mut policy = self.default.clone();
+        if let Some(value) = self.overrides.get(facet) {
+            macro_rules! apply {
+                ($($field:ident),*) => {
+                    $(
+                        if let Some(v) = value.$field {
+                            policy.$field = v;
+                        }
+                    )*
+                };
+            }
+
+            apply!(
+                page_size,
+                page_delay_millis,
+                burst_size,
+                burst_pause_millis,
+                request_timeout_secs,
+                max_retries,
+                retry_base_delay_millis,
+                retry_max_delay_millis
+            );
+
+            if value.api_base_url.is_some() {
+                policy.api_base_url = value.api_base_url.clone();
+            }
+            if value.language.is_some() {
+                policy.language = value.language.clone();
+            }
+        }
+
+        policy.normalized()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FetchPolicyOverride, FetchPolicySettings};
+    use crate::storage::entity_account::AccountFacet;
+    use std::time::Duration;
+
+    #[test]
+    fn override_replaces_retry_backoff() {
+        let mut settings = FetchPolicySettings::default();
+        settings.overrides.insert(
+            AccountFacet::StarRail,
+            FetchPolicyOverride {
+                max_retries: Some(3),
+                retry_base_delay_millis: Some(500),
+                retry_max_delay_millis: Some(100),
+                ..Default::default()
+            },
+        );
+
+        let retry_policy = settings.resolve(&AccountFacet::StarRail).retry_policy();
+        assert_eq!(retry_policy.max_retries, 3);
+        assert_eq!(retry_policy.base_delay, Duration::from_millis(500));
+        // Never below the base delay
+        assert_eq!(retry_policy.max_delay, Duration::from_millis(500));
+
+        let retry_policy = settings.resolve(&AccountFacet::Genshin).retry_policy();
+        assert_eq!(retry_policy.base_delay, Duration::from_secs(1));
+        assert_eq!(retry_policy.max_delay, Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,404 @@
+extern crate async_trait;
+extern crate once_cell;
+extern crate reqwest;
+extern crate serde_json;
+extern crate time;
+extern crate tokio;
+extern crate tokio_util;
+
+use super::diff::{KeyedGachaRecord, StoredGachaRecordKeys};
+use super::hoyoverse::genshin::GenshinGacha;
+use super::hoyoverse::starrail::StarRailGacha;
+use super::hoyoverse::zenlesszonezero::ZenlessZoneZeroGacha;
+use super::kuro::wutheringwaves::WutheringWavesGacha;
+use super::policy::FetchPolicy;
+use super::{
+    GachaRecord, GachaRecordCheckpoint, GachaRecordFetcherChannelFragment, GachaRecordPullSummary,
+    GachaUrl,
+};
+use crate::error::Result;
+use crate::storage::entity_account::AccountFacet;
+use crate::storage::Storage;
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use reqwest::Client as Reqwest;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use time::OffsetDateTime;
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+
+/// Gacha Provider
+///   Everything the app needs to know about one game.
+///   Adding a game means implementing this trait and registering it below.
+#[async_trait]
+pub trait GachaProvider: Send + Sync {
+    fn facet(&self) -> AccountFacet;
+
+    /// Gacha types to pull, each with the stored gacha types it covers
+    fn gacha_type_mappings(&self) -> &'static [(&'static str, &'static [&'static str])];
+
+    fn find_game_data_directories(&self) -> Result<Vec<PathBuf>>;
+
+    fn find_gacha_urls(&self, game_data_dir: &Path) -> Result<Vec<GachaUrl>>;
+
+    /// Pick the gacha url that is still valid and belongs to the uid
+    async fn find_valid_gacha_url(
+        &self,
+        reqwest: &Reqwest,
+        uid: &str,
+        gacha_urls: &[GachaUrl],
+        policy: &FetchPolicy,
+    ) -> Result<GachaUrl>;
+
+    /// The last end id (or last time) stored of the given gacha types
+    async fn find_last_query(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        gacha_types: &[&str],
+    ) -> Result<Option<String>>;
+
+    /// Stored records of the given gacha types, to diff the pulled ones against
+    async fn find_stored_records(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        gacha_types: &[&str],
+    ) -> Result<Vec<KeyedGachaRecord>>;
+
+    async fn pull_gacha_records(
+        &self,
+        request: PullRequest,
+        receiver: &PullReceiver<'_>,
+    ) -> Result<()>;
+
+    /// Primary name and short name of the export format, none if unsupported
+    fn export_format(&self, format: ExportFormat) -> Option<(&'static str, &'static str)>;
+
+    async fn import_gacha_records(&self, storage: &Storage, uid: &str, file: File) -> Result<u64>;
+
+    async fn export_gacha_records(
+        &self,
+        storage: &Storage,
+        uid: &str,
+        format: ExportFormat,
+        now: &OffsetDateTime,
+        writer: File,
+    ) -> Result<()>;
+}
+
+/// Export Format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    /// The format of each game: UIGF v2.2, SRGF, WWPF, or UIGF v4 for Zenless Zone Zero
+    #[default]
+    Classic,
+    /// UIGF v4, all games but Wuthering Waves
+    Uigf4,
+}
+
+/// Gacha Provider Registry
+
+static PROVIDERS: Lazy<HashMap<AccountFacet, &'static dyn GachaProvider>> = Lazy::new(|| {
+    let providers: [&'static dyn GachaProvider; 4] = [
+        &GenshinGacha,
+        &StarRailGacha,
+        &WutheringWavesGacha,
+        &ZenlessZoneZeroGacha,
+    ];
+
+    providers
+        .into_iter()
+        .map(|provider| (provider.facet(), provider))
+        .collect()
+});
+
+pub fn find_provider(facet: &AccountFacet) -> &'static dyn GachaProvider {
+    PROVIDERS
+        .get(facet)
+        .copied()
+        .unwrap_or_else(|| panic!("No gacha provider registered for facet: {facet}"))
+}
+
+/// Pull Request
+
+pub struct PullRequest {
+    pub reqwest: Reqwest,
+    pub uid: String,
+    pub gacha_url: String,
+    /// Gacha type -> last end id (or last time) stored
+    pub gacha_type_and_last_query_mappings: BTreeMap<String, Option<String>>,
+    pub policy: FetchPolicy,
+    pub cancellation: CancellationToken,
+}
+
+/// Pull Context
+///   A pull request as the fetcher channel walks it, with its fetcher and sender.
+pub struct PullContext<'a, F, T>
+where
+    T: GachaRecord + Sized + Serialize + Send + Sync,
+{
+    pub reqwest: &'a Reqwest,
+    pub fetcher: &'a F,
+    pub sender: &'a Sender<GachaRecordFetcherChannelFragment<T>>,
+    pub uid: &'a str,
+    pub gacha_url: &'a str,
+    pub policy: &'a FetchPolicy,
+    pub cancellation: &'a CancellationToken,
+}
+
+impl<'a, F, T> PullContext<'a, F, T>
+where
+    T: GachaRecord + Sized + Serialize + Send + Sync,
+{
+    pub fn new(
+        request: &'a PullRequest,
+        fetcher: &'a F,
+        sender: &'a Sender<GachaRecordFetcherChannelFragment<T>>,
+    ) -> Self {
+        Self {
+            reqwest: &request.reqwest,
+            fetcher,
+            sender,
+            uid: &request.uid,
+            gacha_url: &request.gacha_url,
+            policy: &request.policy,
+            cancellation: &request.cancellation,
+        }
+    }
+}
+
+/// Pull Progress
+///   A serialized fragment, with the number of records it carries.
+pub struct PullProgress {
+    pub fragment: serde_json::Value,
+    pub records: u64,
+}
+
+/// Pull Receiver
+///   Reports every fragment of a pull, and saves its records and checkpoints.
+pub struct PullReceiver<'a> {
+    pub storage: &'a Storage,
+    pub facet: &'a AccountFacet,
+    pub uid: &'a str,
+    pub save_to_storage: bool,
+    observer: &'a (dyn Fn(PullProgress) -> Result<()> + Send + Sync),
+    inserted: AtomicU64,
+    /// Gacha type -> (inserted, duplicates)
+    saved: Mutex<BTreeMap<String, (u64, u64)>>,
+    /// Gacha type -> fetched records, only when collecting
+    collected: Option<Mutex<BTreeMap<String, Vec<KeyedGachaRecord>>>>,
+    /// Gacha type -> stored keys, only the records not matching one are saved
+    stored_keys: Option<Mutex<BTreeMap<String, StoredGachaRecordKeys>>>,
+}
+
+impl<'a> PullReceiver<'a> {
+    pub fn new(
+        storage: &'a Storage,
+        facet: &'a AccountFacet,
+        uid: &'a str,
+        save_to_storage: bool,
+        observer: &'a (dyn Fn(PullProgress) -> Result<()> + Send + Sync),
+    ) -> Self {
+        Self {
+            storage,
+            facet,
+            uid,
+            save_to_storage,
+            observer,
+            inserted: AtomicU64::new(0),
+            saved: Mutex::new(BTreeMap::new()),
+            collected: None,
+            stored_keys: None,
+        }
+    }
+
+    /// Keep the fetched records of every gacha type, to diff them once pulled
+    pub fn collect_records(mut self) -> Self {
+        self.collected = Some(Mutex::new(BTreeMap::new()));
+        self
+    }
+
+    /// Save only the fetched records not stored yet,
+    /// so that walking the whole window does not duplicate records without an id.
+    pub fn skip_stored_records(
+        mut self,
+        stored_keys: BTreeMap<String, StoredGachaRecordKeys>,
+    ) -> Self {
+        self.stored_keys = Some(Mutex::new(stored_keys));
+        self
+    }
+
+    /// The fetched records of every gacha type, empty when not collecting
+    pub fn take_collected(&self) -> BTreeMap<String, Vec<KeyedGachaRecord>> {
+        self.collected
+            .as_ref()
+            .map(|collected| std::mem::take(&mut *collected.lock().unwrap()))
+            .unwrap_or_default()
+    }
+
+    /// Number of records newly saved to storage
+    pub fn inserted(&self) -> u64 {
+        self.inserted.load(Ordering::Relaxed)
+    }
+
+    /// Checkpoints to resume from, none when not saving to storage.
+    ///   Collected records must cover the window once, so none when collecting either.
+    pub async fn find_checkpoints(&self) -> Result<BTreeMap<String, GachaRecordCheckpoint>> {
+        if !self.save_to_storage || self.collected.is_some() {
+            return Ok(BTreeMap::new());
+        }
+
+        Ok(self
+            .storage
+            .find_gacha_checkpoints(self.facet, self.uid)
+            .await?
+            .into_iter()
+            .map(|checkpoint| (checkpoint.gacha_type.clone(), checkpoint))
+            .collect())
+    }
+
+    pub async fn receive<T, F, Fut>(
+        &self,
+        fragment: GachaRecordFetcherChannelFragment<T>,
+        save: F,
+    ) -> Result<()>
+    where
+        T: GachaRecord + Sized + Serialize + Send + Sync,
+        F: FnOnce(Vec<T>) -> Fut,
+        Fut: Future<Output = Result<u64>>,
+    {
+        let fragment = match fragment {
+            GachaRecordFetcherChannelFragment::Summary(summary) if self.save_to_storage => {
+                GachaRecordFetcherChannelFragment::Summary(self.fill_summary(summary))
+            }
+            fragment => fragment,
+        };
+        self.emit(&fragment)?;
+
+        if let (
+            Some(collected),
+            GachaRecordFetcherChannelFragment::Data {
+                gacha_type,
+                records,
+                ..
+            },
+        ) = (&self.collected, &fragment)
+        {
+            let records = records
+                .iter()
+                .map(KeyedGachaRecord::new)
+                .collect::<Result<Vec<_>>>()?;
+            collected
+                .lock()
+                .unwrap()
+                .entry(gacha_type.clone())
+                .or_default()
+                .extend(records);
+        }
+
+        if !self.save_to_storage {
+            return Ok(());
+        }
+
+        match fragment {
+            GachaRecordFetcherChannelFragment::Data {
+                gacha_type,
+                records,
+                ..
+            } => {
+                let fetched = records.len() as u64;
+                let records = match &self.stored_keys {
+                    Some(stored_keys) => {
+                        let mut stored_keys = stored_keys.lock().unwrap();
+                        let stored_keys = stored_keys.entry(gacha_type.clone()).or_default();
+                        records
+                            .into_iter()
+                            .filter(|record| !stored_keys.take(&record.key()))
+                            .collect()
+                    }
+                    None => records,
+                };
+                let inserted = save(records).await?;
+                let duplicates = fetched.saturating_sub(inserted);
+                self.inserted.fetch_add(inserted, Ordering::Relaxed);
+
+                let (total_inserted, total_duplicates) = {
+                    let mut saved = self.saved.lock().unwrap();
+                    let entry = saved.entry(gacha_type.clone()).or_default();
+                    entry.0 += inserted;
+                    entry.1 += duplicates;
+                    *entry
+                };
+                self.emit(&GachaRecordFetcherChannelFragment::<T>::Saved {
+                    gacha_type,
+                    inserted,
+                    duplicates,
+                    total_inserted,
+                    total_duplicates,
+                })?;
+            }
+            GachaRecordFetcherChannelFragment::Checkpoint(checkpoint) => {
+                self.storage
+                    .save_gacha_checkpoint(self.facet, self.uid, &checkpoint)
+                    .await?;
+            }
+            // Before the records it is sent with, so that they are stamped in it
+            GachaRecordFetcherChannelFragment::TimeZone(time_zone) => {
+                self.storage
+                    .save_account_time_zone(self.facet, self.uid, time_zone)
+                    .await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn emit<T>(&self, fragment: &GachaRecordFetcherChannelFragment<T>) -> Result<()>
+    where
+        T: GachaRecord + Sized + Serialize + Send + Sync,
+    {
+        let records = match fragment {
+            GachaRecordFetcherChannelFragment::Data { records, .. } => records.len() as u64,
+            _ => 0,
+        };
+        (self.observer)(PullProgress {
+            fragment: serde_json::to_value(fragment)?,
+            records,
+        })
+    }
+
+    /// Add the inserted and duplicate counts of every gacha type saved so far
+    fn fill_summary(&self, mut summary: GachaRecordPullSummary) -> GachaRecordPullSummary {
+        let saved = self.saved.lock().unwrap();
+        for count in summary.gacha_types.iter_mut() {
+            let (inserted, duplicates) = saved.get(&count.gacha_type).copied().unwrap_or_default();
+            count.inserted = Some(inserted);
+            count.duplicates = Some(duplicates);
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_provider;
+    use crate::storage::entity_account::AccountFacet;
+    use sea_orm::Iterable;
+
+    #[test]
+    fn every_facet_has_a_provider() {
+        for facet in AccountFacet::iter() {
+            let provider = find_provider(&facet);
+            assert_eq!(provider.facet(), facet);
+            assert!(!provider.gacha_type_mappings().is_empty());
+        }
+    }
+}
@@ -0,0 +1,146 @@
+extern crate rand;
+extern crate reqwest;
+extern crate tokio;
+extern crate tracing;
+
+use crate::error::{Error, Result};
+use rand::Rng;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::time::Duration;
+use tracing::debug;
+
+/// Retcodes that mean "slow down and try again", not a real failure.
+///   -110: visit too frequently
+const RATE_LIMIT_RETCODES: &[i32] = &[-110];
+
+/// Retry Policy
+///   Exponential backoff with equal jitter, for transient fetch failures.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the given retry attempt (1-based).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        // Keep at least half of the backoff, randomize the rest
+        let half = backoff / 2;
+        let jitter = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+        half + Duration::from_millis(jitter)
+    }
+}
+
+/// Whether the error is worth retrying:
+/// connect errors, timeouts, dropped connections, 5xx responses and rate-limit retcodes.
+pub fn is_transient_error(error: &Error) -> bool {
+    match error {
+        Error::Reqwest(error) => {
+            error.is_timeout()
+                || error.is_connect()
+                || is_dropped_connection(error)
+                || error
+                    .status()
+                    .map(|status| {
+                        status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    })
+                    .unwrap_or(false)
+        }
+        Error::GachaRecordRetcode { retcode, .. } => RATE_LIMIT_RETCODES.contains(retcode),
+        _ => false,
+    }
+}
+
+/// Whether an IO error in the source chain means the connection dropped mid-request.
+///   Other request errors, such as a malformed response, fail the same way on retry.
+fn is_dropped_connection(error: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        if let Some(error) = error.downcast_ref::<std::io::Error>() {
+            return matches!(
+                error.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+            );
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Run the operation, retrying transient failures according to the policy.
+/// `on_retry` is called with the attempt number and delay before each sleep.
+pub async fn retry_with_backoff<T, F, Fut, R, RFut>(
+    policy: &RetryPolicy,
+    mut operation: F,
+    mut on_retry: R,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+    R: FnMut(u32, Duration) -> RFut,
+    RFut: Future<Output = Result<()>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Err(error) if attempt < policy.max_retries && is_transient_error(&error) => {
+                attempt += 1;
+                let delay = policy.delay(attempt);
+                debug!(
+                    "Transient fetch error, retry {}/{} in {:?}: {}",
+                    attempt, policy.max_retries, delay, error
+                );
+                on_retry(attempt, delay).await?;
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_transient_error;
+    use crate::error::Error;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn malformed_response_is_not_retried() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream.write_all(b"NOT-HTTP garbage\r\n\r\n").await;
+        });
+
+        let error = reqwest::get(format!("http://{addr}/"))
+            .await
+            .expect_err("malformed response");
+        assert!(error.is_request());
+        assert!(!is_transient_error(&Error::Reqwest(error)));
+    }
+}
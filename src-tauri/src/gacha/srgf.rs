@@ -6,7 +6,7 @@ extern crate time;
 use crate::constants::{ID, VERSION};
 use crate::error::{Error, Result};
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Write};
+use std::io::Write;
 use time::OffsetDateTime;
 
 use super::hoyoverse::starrail::StarRailGachaRecord;
@@ -19,7 +19,7 @@ const SRGF_VERSION: &str = "v1.0";
 pub struct SRGFInfo {
     pub uid: String,
     pub lang: String,
-    pub region_time_zone: u8,
+    pub region_time_zone: i8,
     pub export_timestamp: Option<i64>,
     pub export_app: Option<String>,
     pub export_app_version: Option<String>,
@@ -52,7 +52,7 @@ impl SRGF {
     pub fn new(
         uid: String,
         lang: String,
-        time_zone: u8,
+        time_zone: i8,
         time: &OffsetDateTime,
         list: SRGFList,
     ) -> Result<Self> {
@@ -71,8 +71,8 @@ impl SRGF {
         })
     }
 
-    pub fn from_reader(reader: impl Read) -> Result<Self> {
-        Ok(serde_json::from_reader(reader)?)
+    pub fn from_value(value: serde_json::Value) -> Result<Self> {
+        Ok(serde_json::from_value(value)?)
     }
 
     pub fn to_writer(&self, writer: impl Write, pretty: bool) -> Result<()> {
@@ -0,0 +1,317 @@
+extern crate serde;
+extern crate time;
+extern crate tokio;
+extern crate tokio_util;
+extern crate tracing;
+
+use crate::error::{Error, Result};
+use crate::storage::entity_account::AccountFacet;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+/// Maximum number of finished tasks kept for status queries
+const MAX_FINISHED_TASKS: usize = 32;
+
+/// Task Kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskKind {
+    Pull,
+    /// Pulls of every account, one after another
+    PullAll,
+    Import,
+    Export,
+}
+
+impl std::fmt::Display for TaskKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pull => f.write_str("pull"),
+            Self::PullAll => f.write_str("pullAll"),
+            Self::Import => f.write_str("import"),
+            Self::Export => f.write_str("export"),
+        }
+    }
+}
+
+/// Task Status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Task
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub id: String,
+    pub kind: TaskKind,
+    /// None for a task over every account
+    pub facet: Option<AccountFacet>,
+    pub uid: Option<String>,
+    pub status: TaskStatus,
+    pub progress: u64,
+    pub error: Option<String>,
+    pub created_at: OffsetDateTime,
+    pub finished_at: Option<OffsetDateTime>,
+    #[serde(skip)]
+    token: CancellationToken,
+}
+
+impl Task {
+    /// Default task id: `kind:facet:uid`
+    pub fn default_id(kind: TaskKind, facet: &AccountFacet, uid: &str) -> String {
+        format!("{}:{}:{}", kind, facet, uid)
+    }
+}
+
+/// Task Registry
+///   Tracks long-running operations by id, with progress and status.
+#[derive(Debug, Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<HashMap<String, Task>>,
+}
+
+impl TaskRegistry {
+    pub fn register(
+        &self,
+        id: &str,
+        kind: TaskKind,
+        facet: &AccountFacet,
+        uid: &str,
+    ) -> Result<CancellationToken> {
+        debug!("Register task: id={id}, kind={kind}, facet={facet}, uid={uid}");
+        let token = CancellationToken::new();
+        self.insert(id, kind, Some(facet.clone()), Some(uid.to_owned()), token)
+    }
+
+    /// Register a task of a batch, cancelled along with the batch
+    pub fn register_child(
+        &self,
+        id: &str,
+        kind: TaskKind,
+        facet: &AccountFacet,
+        uid: &str,
+        parent: &CancellationToken,
+    ) -> Result<CancellationToken> {
+        debug!("Register child task: id={id}, kind={kind}, facet={facet}, uid={uid}");
+        let token = parent.child_token();
+        self.insert(id, kind, Some(facet.clone()), Some(uid.to_owned()), token)
+    }
+
+    /// Register a task over every account
+    pub fn register_batch(&self, id: &str, kind: TaskKind) -> Result<CancellationToken> {
+        debug!("Register task: id={id}, kind={kind}");
+        self.insert(id, kind, None, None, CancellationToken::new())
+    }
+
+    fn insert(
+        &self,
+        id: &str,
+        kind: TaskKind,
+        facet: Option<AccountFacet>,
+        uid: Option<String>,
+        token: CancellationToken,
+    ) -> Result<CancellationToken> {
+        let mut tasks = self.tasks.lock().unwrap();
+        if let Some(task) = tasks.get(id) {
+            if task.status == TaskStatus::Running {
+                return Err(Error::TaskAlreadyRunning);
+            }
+        }
+
+        Self::evict_finished(&mut tasks);
+
+        tasks.insert(
+            id.to_owned(),
+            Task {
+                id: id.to_owned(),
+                kind,
+                facet,
+                uid,
+                status: TaskStatus::Running,
+                progress: 0,
+                error: None,
+                created_at: OffsetDateTime::now_utc(),
+                finished_at: None,
+                token: token.clone(),
+            },
+        );
+
+        Ok(token)
+    }
+
+    pub fn progress(&self, id: &str, delta: u64) {
+        if let Some(task) = self.tasks.lock().unwrap().get_mut(id) {
+            task.progress += delta;
+        }
+    }
+
+    pub fn finish<T>(&self, id: &str, result: &Result<T>) {
+        if let Some(task) = self.tasks.lock().unwrap().get_mut(id) {
+            task.status = match result {
+                Ok(_) => TaskStatus::Completed,
+                Err(Error::TaskCancelled) => TaskStatus::Cancelled,
+                Err(error) => {
+                    task.error = Some(error.to_string());
+                    TaskStatus::Failed
+                }
+            };
+            task.finished_at = Some(OffsetDateTime::now_utc());
+            debug!("Finish task: id={id}, status={:?}", task.status);
+        }
+    }
+
+    pub fn is_running(&self, id: &str) -> bool {
+        self.tasks
+            .lock()
+            .unwrap()
+            .get(id)
+            .map_or(false, |task| task.status == TaskStatus::Running)
+    }
+
+    /// Request cancellation. Returns false if the task is unknown or not running.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.tasks.lock().unwrap().get(id) {
+            Some(task) if task.status == TaskStatus::Running => {
+                debug!("Cancel task: id={id}");
+                task.token.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn tasks(&self) -> Vec<Task> {
+        let mut tasks: Vec<Task> = self.tasks.lock().unwrap().values().cloned().collect();
+        tasks.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        tasks
+    }
+
+    fn evict_finished(tasks: &mut HashMap<String, Task>) {
+        let mut finished: Vec<(String, OffsetDateTime)> = tasks
+            .values()
+            .filter_map(|task| task.finished_at.map(|at| (task.id.clone(), at)))
+            .collect();
+
+        if finished.len() < MAX_FINISHED_TASKS {
+            return;
+        }
+
+        finished.sort_by(|a, b| a.1.cmp(&b.1));
+        for (id, _) in finished
+            .iter()
+            .take(finished.len() + 1 - MAX_FINISHED_TASKS)
+        {
+            tasks.remove(id);
+        }
+    }
+}
+
+/// Run the future unless the token is cancelled first.
+pub async fn cancellable<T, F>(token: &CancellationToken, future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => Err(Error::TaskCancelled),
+        result = future => result,
+    }
+}
+
+/// Sleep for the duration, returning early with an error if cancelled.
+pub async fn cancellable_sleep(
+    token: &CancellationToken,
+    duration: tokio::time::Duration,
+) -> Result<()> {
+    cancellable(token, async {
+        tokio::time::sleep(duration).await;
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TaskKind, TaskRegistry, TaskStatus};
+    use crate::error::Error;
+    use crate::storage::entity_account::AccountFacet;
+
+    #[test]
+    fn batch_task_is_cancelled_apart_from_account_tasks() {
+        let tasks = TaskRegistry::default();
+        let batch = tasks.register_batch("pullAll", TaskKind::PullAll).unwrap();
+        let pull = tasks
+            .register(
+                "pull:genshin:100000001",
+                TaskKind::Pull,
+                &AccountFacet::Genshin,
+                "100000001",
+            )
+            .unwrap();
+        assert!(matches!(
+            tasks.register_batch("pullAll", TaskKind::PullAll),
+            Err(Error::TaskAlreadyRunning)
+        ));
+
+        assert!(tasks.cancel("pullAll"));
+        assert!(batch.is_cancelled());
+        assert!(!pull.is_cancelled());
+        assert!(tasks.is_running("pull:genshin:100000001"));
+
+        tasks.finish("pullAll", &Err::<(), _>(Error::TaskCancelled));
+        assert!(!tasks.is_running("pullAll"));
+        let batch = tasks
+            .tasks()
+            .into_iter()
+            .find(|task| task.id == "pullAll")
+            .unwrap();
+        assert_eq!(batch.status, TaskStatus::Cancelled);
+        assert_eq!(batch.uid, None);
+    }
+
+    #[test]
+    fn child_task_is_cancelled_with_its_batch() {
+        let tasks = TaskRegistry::default();
+        let batch = tasks.register_batch("pullAll", TaskKind::PullAll).unwrap();
+        let pull = tasks
+            .register_child(
+                "pull:genshin:100000001",
+                TaskKind::Pull,
+                &AccountFacet::Genshin,
+                "100000001",
+                &batch,
+            )
+            .unwrap();
+
+        assert!(tasks.cancel("pullAll"));
+        assert!(pull.is_cancelled());
+
+        // Cancelling the child alone leaves the batch running
+        let batch = tasks
+            .register_batch("pullAll:2", TaskKind::PullAll)
+            .unwrap();
+        let pull = tasks
+            .register_child(
+                "pull:genshin:100000002",
+                TaskKind::Pull,
+                &AccountFacet::Genshin,
+                "100000002",
+                &batch,
+            )
+            .unwrap();
+        assert!(tasks.cancel("pull:genshin:100000002"));
+        assert!(pull.is_cancelled());
+        assert!(!batch.is_cancelled());
+    }
+}
@@ -9,7 +9,7 @@ use crate::gacha::hoyoverse::genshin::GenshinGachaRecord;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::io::{Read, Write};
+use std::io::Write;
 use time::format_description;
 use time::OffsetDateTime;
 
@@ -53,7 +53,7 @@ pub struct UIGF {
 }
 
 impl UIGF {
-    const FORMAT_TIME: &str = "[year]-[month]-[day] [hour]:[minute]:[second]";
+    const FORMAT_TIME: &'static str = "[year]-[month]-[day] [hour]:[minute]:[second]";
     pub fn new(uid: String, lang: String, time: &OffsetDateTime, list: UIGFList) -> Result<Self> {
         let format = format_description::parse(Self::FORMAT_TIME).map_err(time::Error::from)?;
         let export_time = time.format(&format).map_err(time::Error::from)?;
@@ -72,8 +72,8 @@ impl UIGF {
         })
     }
 
-    pub fn from_reader(reader: impl Read) -> Result<Self> {
-        Ok(serde_json::from_reader(reader)?)
+    pub fn from_value(value: serde_json::Value) -> Result<Self> {
+        Ok(serde_json::from_value(value)?)
     }
 
     pub fn to_writer(&self, writer: impl Write, pretty: bool) -> Result<()> {
@@ -95,14 +95,16 @@ lazy_static! {
    *       301             |       301
    *       400             |       301
    *       302             |       302
+   *       500             |       500
    */
   pub static ref GACHA_TYPE_UIGF_MAPPINGS: HashMap<String, String> = {
-    let mut m = HashMap::with_capacity(5);
+    let mut m = HashMap::with_capacity(6);
     m.insert(String::from("100"), String::from("100"));
     m.insert(String::from("200"), String::from("200"));
     m.insert(String::from("301"), String::from("301"));
     m.insert(String::from("400"), String::from("301")); // 400 -> 301
     m.insert(String::from("302"), String::from("302"));
+    m.insert(String::from("500"), String::from("500")); // Chronicled Wish
     m
   };
 }
@@ -196,3 +198,29 @@ pub fn convert_offical_to_uigf(records: &[GenshinGachaRecord]) -> Result<UIGFLis
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::convert_offical_to_uigf;
+    use crate::gacha::hoyoverse::genshin::GenshinGachaRecord;
+
+    #[test]
+    fn maps_chronicled_wish() {
+        let record = GenshinGachaRecord {
+            id: String::from("1710000000000000001"),
+            uid: String::from("100000001"),
+            gacha_type: String::from("500"),
+            item_id: String::from("10000002"),
+            count: String::from("1"),
+            time: String::from("2024-03-13 12:00:00"),
+            name: String::from("Kamisato Ayaka"),
+            lang: String::from("en-us"),
+            item_type: String::from("Character"),
+            rank_type: String::from("5"),
+        };
+
+        let list = convert_offical_to_uigf(&[record]).unwrap();
+        assert_eq!(list[0].gacha_type, "500");
+        assert_eq!(list[0].uigf_gacha_type, "500");
+    }
+}
@@ -0,0 +1,647 @@
+extern crate serde;
+extern crate serde_json;
+extern crate time;
+
+use crate::constants::{ID, VERSION};
+use crate::error::{Error, Result};
+use crate::gacha::dict::embedded;
+use crate::storage::entity_account::AccountFacet;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use time::OffsetDateTime;
+
+use super::hoyoverse::genshin::GenshinGachaRecord;
+use super::hoyoverse::starrail::StarRailGachaRecord;
+use super::hoyoverse::zenlesszonezero::ZenlessZoneZeroGachaRecord;
+use super::uigf::GACHA_TYPE_UIGF_MAPPINGS;
+
+// See: https://uigf.org/zh/standards/uigf.html
+
+const UIGF_VERSION: &str = "v4.0";
+
+/// The standard allows some fields as either a string or a number
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum UIGF4Value {
+    String(String),
+    Number(i64),
+}
+
+impl std::fmt::Display for UIGF4Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(value) => f.write_str(value),
+            Self::Number(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UIGF4Info {
+    pub export_timestamp: UIGF4Value,
+    pub export_app: String,
+    pub export_app_version: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UIGF4Account<Item> {
+    pub uid: UIGF4Value,
+    pub timezone: i8,
+    pub lang: Option<String>,
+    pub list: Vec<Item>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UIGF4Hk4eItem {
+    pub uigf_gacha_type: String,
+    pub gacha_type: String,
+    pub item_id: String,
+    pub count: Option<String>,
+    pub time: String,
+    pub name: Option<String>,
+    pub item_type: Option<String>,
+    pub rank_type: Option<String>,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UIGF4NapItem {
+    pub gacha_id: Option<String>,
+    pub gacha_type: String,
+    pub item_id: String,
+    pub count: Option<String>,
+    pub time: String,
+    pub name: Option<String>,
+    pub item_type: Option<String>,
+    pub rank_type: Option<String>,
+    pub id: String,
+}
+
+/// Unlike Zenless Zone Zero, the gacha id is required
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UIGF4HkrpgItem {
+    pub gacha_id: String,
+    pub gacha_type: String,
+    pub item_id: String,
+    pub count: Option<String>,
+    pub time: String,
+    pub name: Option<String>,
+    pub item_type: Option<String>,
+    pub rank_type: Option<String>,
+    pub id: String,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UIGF4 {
+    pub info: UIGF4Info,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hk4e: Option<Vec<UIGF4Account<UIGF4Hk4eItem>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hkrpg: Option<Vec<UIGF4Account<UIGF4HkrpgItem>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nap: Option<Vec<UIGF4Account<UIGF4NapItem>>>,
+}
+
+impl UIGF4 {
+    pub fn new(time: &OffsetDateTime) -> Self {
+        Self {
+            info: UIGF4Info {
+                export_timestamp: UIGF4Value::Number(time.unix_timestamp()),
+                export_app: ID.into(),
+                export_app_version: VERSION.into(),
+                version: UIGF_VERSION.into(),
+            },
+            hk4e: None,
+            hkrpg: None,
+            nap: None,
+        }
+    }
+
+    /// Earlier versions and SRGF name the version field `uigf_version` or `srgf_version`
+    pub fn is_uigf4(value: &serde_json::Value) -> bool {
+        value
+            .get("info")
+            .and_then(|info| info.get("version"))
+            .is_some()
+    }
+
+    pub fn with_hk4e(mut self, hk4e: Vec<UIGF4Account<UIGF4Hk4eItem>>) -> Self {
+        self.hk4e = Some(hk4e);
+        self
+    }
+
+    pub fn with_hkrpg(mut self, hkrpg: Vec<UIGF4Account<UIGF4HkrpgItem>>) -> Self {
+        self.hkrpg = Some(hkrpg);
+        self
+    }
+
+    pub fn with_nap(mut self, nap: Vec<UIGF4Account<UIGF4NapItem>>) -> Self {
+        self.nap = Some(nap);
+        self
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    pub fn from_value(value: serde_json::Value) -> Result<Self> {
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn to_writer(&self, writer: impl Write, pretty: bool) -> Result<()> {
+        if pretty {
+            Ok(serde_json::to_writer_pretty(writer, self)?)
+        } else {
+            Ok(serde_json::to_writer(writer, self)?)
+        }
+    }
+}
+
+/// Find the account of the uid, a file may carry several accounts of a game
+fn find_account<'a, Item>(
+    accounts: Option<&'a Vec<UIGF4Account<Item>>>,
+    uid: &str,
+) -> Result<&'a UIGF4Account<Item>> {
+    let accounts = accounts.map(Vec::as_slice).unwrap_or_default();
+    accounts
+        .iter()
+        .find(|account| account.uid.to_string() == uid)
+        .ok_or_else(|| Error::UIGFOrSRGFMismatchedUID {
+            expected: uid.to_owned(),
+            actual: accounts
+                .iter()
+                .map(|account| account.uid.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        })
+}
+
+// Convert
+
+/// Name, item type and rank type are optional, fill the missing ones from the dictionary
+fn fill_from_dictionary(
+    facet: &AccountFacet,
+    lang: &str,
+    item_id: &str,
+    name: &Option<String>,
+    item_type: &Option<String>,
+    rank_type: &Option<String>,
+) -> Result<(String, String, String)> {
+    let entry = embedded::id(facet, lang, item_id);
+    let name = name
+        .clone()
+        .or_else(|| entry.map(|entry| entry.item_name.to_string()))
+        .ok_or_else(|| Error::UIGFOrSRGFInvalidField("name".to_owned()))?;
+    let item_type = item_type
+        .clone()
+        .or_else(|| entry.map(|entry| entry.category_name.to_owned()))
+        .ok_or_else(|| Error::UIGFOrSRGFInvalidField("item_type".to_owned()))?;
+    let rank_type = rank_type
+        .clone()
+        .or_else(|| entry.map(|entry| entry.rank_type.to_string()))
+        .ok_or_else(|| Error::UIGFOrSRGFInvalidField("rank_type".to_owned()))?;
+
+    Ok((name, item_type, rank_type))
+}
+
+// UIGF v4 hk4e -> Official GenshinGachaRecord
+impl GenshinGachaRecord {
+    fn try_from_hk4e(value: &UIGF4Hk4eItem, uid: &str, lang: &str) -> Result<Self> {
+        let (name, item_type, rank_type) = fill_from_dictionary(
+            &AccountFacet::Genshin,
+            lang,
+            &value.item_id,
+            &value.name,
+            &value.item_type,
+            &value.rank_type,
+        )?;
+
+        Ok(Self {
+            id: value.id.clone(),
+            uid: uid.to_owned(),
+            gacha_type: value.gacha_type.clone(),
+            item_id: value.item_id.clone(),
+            count: value.count.clone().unwrap_or("1".to_owned()),
+            time: value.time.clone(),
+            name,
+            lang: lang.to_owned(),
+            item_type,
+            rank_type,
+        })
+    }
+}
+
+// Official GenshinGachaRecord -> UIGF v4 hk4e
+impl TryFrom<&GenshinGachaRecord> for UIGF4Hk4eItem {
+    type Error = Error;
+
+    fn try_from(value: &GenshinGachaRecord) -> std::result::Result<Self, Self::Error> {
+        let uigf_gacha_type = GACHA_TYPE_UIGF_MAPPINGS
+            .get(&value.gacha_type)
+            .ok_or_else(|| {
+                Error::UIGFOrSRGFInvalidField(format!("gacha_type={}", value.gacha_type))
+            })?;
+
+        Ok(Self {
+            uigf_gacha_type: uigf_gacha_type.to_owned(),
+            gacha_type: value.gacha_type.clone(),
+            item_id: value.item_id.clone(),
+            count: Some(value.count.clone()),
+            time: value.time.clone(),
+            name: Some(value.name.clone()),
+            item_type: Some(value.item_type.clone()),
+            rank_type: Some(value.rank_type.clone()),
+            id: value.id.clone(),
+        })
+    }
+}
+
+// UIGF v4 hkrpg -> Official StarRailGachaRecord
+impl StarRailGachaRecord {
+    fn try_from_hkrpg(value: &UIGF4HkrpgItem, uid: &str, lang: &str) -> Result<Self> {
+        let (name, item_type, rank_type) = fill_from_dictionary(
+            &AccountFacet::StarRail,
+            lang,
+            &value.item_id,
+            &value.name,
+            &value.item_type,
+            &value.rank_type,
+        )?;
+
+        Ok(Self {
+            id: value.id.clone(),
+            uid: uid.to_owned(),
+            gacha_id: value.gacha_id.clone(),
+            gacha_type: value.gacha_type.clone(),
+            item_id: value.item_id.clone(),
+            count: value.count.clone().unwrap_or("1".to_owned()),
+            time: value.time.clone(),
+            name,
+            lang: lang.to_owned(),
+            item_type,
+            rank_type,
+        })
+    }
+}
+
+// Official StarRailGachaRecord -> UIGF v4 hkrpg
+impl From<&StarRailGachaRecord> for UIGF4HkrpgItem {
+    fn from(value: &StarRailGachaRecord) -> Self {
+        Self {
+            gacha_id: value.gacha_id.clone(),
+            gacha_type: value.gacha_type.clone(),
+            item_id: value.item_id.clone(),
+            count: Some(value.count.clone()),
+            time: value.time.clone(),
+            name: Some(value.name.clone()),
+            item_type: Some(value.item_type.clone()),
+            rank_type: Some(value.rank_type.clone()),
+            id: value.id.clone(),
+        }
+    }
+}
+
+// UIGF v4 nap -> Official ZenlessZoneZeroGachaRecord
+impl ZenlessZoneZeroGachaRecord {
+    fn try_from_nap(value: &UIGF4NapItem, uid: &str, lang: &str) -> Result<Self> {
+        let (name, item_type, rank_type) = fill_from_dictionary(
+            &AccountFacet::ZenlessZoneZero,
+            lang,
+            &value.item_id,
+            &value.name,
+            &value.item_type,
+            &value.rank_type,
+        )?;
+
+        Ok(Self {
+            id: value.id.clone(),
+            uid: uid.to_owned(),
+            gacha_id: value.gacha_id.clone().unwrap_or_default(),
+            gacha_type: value.gacha_type.clone(),
+            item_id: value.item_id.clone(),
+            count: value.count.clone().unwrap_or("1".to_owned()),
+            time: value.time.clone(),
+            name,
+            lang: lang.to_owned(),
+            item_type,
+            rank_type,
+        })
+    }
+}
+
+// Official ZenlessZoneZeroGachaRecord -> UIGF v4 nap
+impl From<&ZenlessZoneZeroGachaRecord> for UIGF4NapItem {
+    fn from(value: &ZenlessZoneZeroGachaRecord) -> Self {
+        Self {
+            gacha_id: Some(value.gacha_id.clone()),
+            gacha_type: value.gacha_type.clone(),
+            item_id: value.item_id.clone(),
+            count: Some(value.count.clone()),
+            time: value.time.clone(),
+            name: Some(value.name.clone()),
+            item_type: Some(value.item_type.clone()),
+            rank_type: Some(value.rank_type.clone()),
+            id: value.id.clone(),
+        }
+    }
+}
+
+pub fn convert_hk4e_to_offical(uigf: &UIGF4, uid: &str) -> Result<Vec<GenshinGachaRecord>> {
+    let account = find_account(uigf.hk4e.as_ref(), uid)?;
+    let lang = account.lang.as_deref().unwrap_or("en-us");
+
+    let mut result = Vec::with_capacity(account.list.len());
+    for item in account.list.iter() {
+        let record = GenshinGachaRecord::try_from_hk4e(item, uid, lang)?;
+        result.push(record);
+    }
+
+    Ok(result)
+}
+
+pub fn convert_hkrpg_to_offical(uigf: &UIGF4, uid: &str) -> Result<Vec<StarRailGachaRecord>> {
+    let account = find_account(uigf.hkrpg.as_ref(), uid)?;
+    let lang = account.lang.as_deref().unwrap_or("en-us");
+
+    let mut result = Vec::with_capacity(account.list.len());
+    for item in account.list.iter() {
+        let record = StarRailGachaRecord::try_from_hkrpg(item, uid, lang)?;
+        result.push(record);
+    }
+
+    Ok(result)
+}
+
+pub fn convert_nap_to_offical(uigf: &UIGF4, uid: &str) -> Result<Vec<ZenlessZoneZeroGachaRecord>> {
+    let account = find_account(uigf.nap.as_ref(), uid)?;
+    let lang = account.lang.as_deref().unwrap_or("en-us");
+
+    let mut result = Vec::with_capacity(account.list.len());
+    for item in account.list.iter() {
+        let record = ZenlessZoneZeroGachaRecord::try_from_nap(item, uid, lang)?;
+        result.push(record);
+    }
+
+    Ok(result)
+}
+
+pub fn convert_offical_to_hk4e(
+    uid: &str,
+    lang: String,
+    timezone: i8,
+    records: &[GenshinGachaRecord],
+) -> Result<UIGF4Account<UIGF4Hk4eItem>> {
+    Ok(UIGF4Account {
+        uid: UIGF4Value::String(uid.to_owned()),
+        timezone,
+        lang: Some(lang),
+        list: records
+            .iter()
+            .map(UIGF4Hk4eItem::try_from)
+            .collect::<Result<_>>()?,
+    })
+}
+
+pub fn convert_offical_to_hkrpg(
+    uid: &str,
+    lang: String,
+    timezone: i8,
+    records: &[StarRailGachaRecord],
+) -> UIGF4Account<UIGF4HkrpgItem> {
+    UIGF4Account {
+        uid: UIGF4Value::String(uid.to_owned()),
+        timezone,
+        lang: Some(lang),
+        list: records.iter().map(UIGF4HkrpgItem::from).collect(),
+    }
+}
+
+pub fn convert_offical_to_nap(
+    uid: &str,
+    lang: String,
+    timezone: i8,
+    records: &[ZenlessZoneZeroGachaRecord],
+) -> UIGF4Account<UIGF4NapItem> {
+    UIGF4Account {
+        uid: UIGF4Value::String(uid.to_owned()),
+        timezone,
+        lang: Some(lang),
+        list: records.iter().map(UIGF4NapItem::from).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        convert_hk4e_to_offical, convert_hkrpg_to_offical, convert_nap_to_offical,
+        convert_offical_to_hk4e, convert_offical_to_hkrpg, UIGF4,
+    };
+    use crate::error::Error;
+    use crate::gacha::hoyoverse::genshin::GenshinGachaRecord;
+    use crate::gacha::hoyoverse::starrail::StarRailGachaRecord;
+    use crate::gacha::srgf;
+    use serde_json::{json, Value};
+    use time::macros::datetime;
+
+    fn uigf4(game: &str, uid: Value, list: Value) -> UIGF4 {
+        serde_json::from_value(json!({
+            "info": {
+                "export_timestamp": "1720000000",
+                "export_app": "test",
+                "export_app_version": "1.0",
+                "version": "v4.0",
+            },
+            game: [{
+                "uid": uid,
+                "timezone": 8,
+                "lang": "en-us",
+                "list": list,
+            }],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn hk4e_import_keeps_chronicled_wish() {
+        let uigf = uigf4(
+            "hk4e",
+            json!("100000001"),
+            json!([{
+                "uigf_gacha_type": "500",
+                "gacha_type": "500",
+                "item_id": "10000002",
+                "time": "2024-03-13 12:00:00",
+                "id": "1710000000000000001",
+            }]),
+        );
+
+        let records = convert_hk4e_to_offical(&uigf, "100000001").unwrap();
+        assert_eq!(records[0].gacha_type, "500");
+        assert_eq!(records[0].name, "Kamisato Ayaka");
+        assert_eq!(records[0].rank_type, "5");
+    }
+
+    #[test]
+    fn hkrpg_import_keeps_collaboration_warps() {
+        let list: Vec<Value> = [("21", "1720000000000000001"), ("22", "1720000000000000002")]
+            .iter()
+            .map(|(gacha_type, id)| {
+                json!({
+                    "gacha_id": "4001",
+                    "gacha_type": gacha_type,
+                    "item_id": "1001",
+                    "time": "2025-07-11 12:00:00",
+                    "name": "Saber",
+                    "item_type": "Character",
+                    "rank_type": "5",
+                    "id": id,
+                })
+            })
+            .collect();
+        let uigf = uigf4("hkrpg", json!("100000001"), Value::Array(list));
+
+        let records = convert_hkrpg_to_offical(&uigf, "100000001").unwrap();
+        let gacha_types: Vec<&str> = records
+            .iter()
+            .map(|record| record.gacha_type.as_str())
+            .collect();
+        assert_eq!(gacha_types, ["21", "22"]);
+        assert!(records.iter().all(|record| record.gacha_id == "4001"));
+
+        let srgf_list = srgf::convert_offical_to_srgf(&records).unwrap();
+        assert_eq!(srgf_list[1].gacha_type, "22");
+    }
+
+    #[test]
+    fn hkrpg_fixture_keeps_every_warp() {
+        let uigf = UIGF4::from_reader(&include_bytes!("fixtures/starrail-uigf4.json")[..]).unwrap();
+
+        let records = convert_hkrpg_to_offical(&uigf, "100000001").unwrap();
+        let gacha_types: Vec<&str> = records
+            .iter()
+            .map(|record| record.gacha_type.as_str())
+            .collect();
+        assert_eq!(gacha_types, ["1", "2", "11", "12", "21", "22"]);
+        assert_eq!(records[4].gacha_id, "5001");
+        assert_eq!(records[5].name, "A Thankless Coronation");
+
+        // Written back as it was read
+        let hkrpg = convert_offical_to_hkrpg("100000001", String::from("en-us"), 8, &records);
+        let uigf = reread(UIGF4::new(&datetime!(2025-07-12 00:00:00 UTC)).with_hkrpg(vec![hkrpg]));
+        assert_eq!(
+            convert_hkrpg_to_offical(&uigf, "100000001").unwrap(),
+            records
+        );
+    }
+
+    #[test]
+    fn nap_import_fills_from_dictionary() {
+        let uigf = uigf4(
+            "nap",
+            json!(100000001),
+            json!([{
+                "gacha_type": "2",
+                "item_id": "1191",
+                "time": "2024-07-04 12:00:00",
+                "id": "1720000000000000001",
+            }, {
+                "gacha_type": "3",
+                "item_id": "14119",
+                "time": "2024-07-04 12:01:00",
+                "id": "1720000000000000002",
+            }, {
+                "gacha_type": "5",
+                "item_id": "54002",
+                "time": "2024-07-04 12:02:00",
+                "id": "1720000000000000003",
+            }]),
+        );
+
+        let records = convert_nap_to_offical(&uigf, "100000001").unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].name, "Ellen");
+        assert_eq!(records[0].item_type, "Agents");
+        assert_eq!(records[0].rank_type, "4");
+        assert_eq!(records[0].count, "1");
+        assert_eq!(records[1].name, "Deep Sea Visitor");
+        assert_eq!(records[1].item_type, "W-Engines");
+        assert_eq!(records[2].name, "Sharkboo");
+        assert_eq!(records[2].item_type, "Bangboo");
+
+        assert!(matches!(
+            convert_nap_to_offical(&uigf, "100000002"),
+            Err(Error::UIGFOrSRGFMismatchedUID { .. })
+        ));
+    }
+
+    /// Written then read back, as another app would
+    fn reread(uigf: UIGF4) -> UIGF4 {
+        let mut bytes = Vec::new();
+        uigf.to_writer(&mut bytes, false).unwrap();
+        UIGF4::from_reader(bytes.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn hk4e_export_reads_back() {
+        let record = GenshinGachaRecord {
+            id: String::from("1710000000000000001"),
+            uid: String::from("600000001"),
+            gacha_type: String::from("400"),
+            item_id: String::from("10000002"),
+            count: String::from("1"),
+            time: String::from("2024-03-13 12:00:00"),
+            name: String::from("Kamisato Ayaka"),
+            lang: String::from("en-us"),
+            item_type: String::from("Character"),
+            rank_type: String::from("5"),
+        };
+        let hk4e = convert_offical_to_hk4e(
+            "600000001",
+            String::from("en-us"),
+            -5,
+            std::slice::from_ref(&record),
+        )
+        .unwrap();
+        assert_eq!(hk4e.list[0].uigf_gacha_type, "301");
+
+        let uigf = reread(UIGF4::new(&datetime!(2024-03-14 00:00:00 UTC)).with_hk4e(vec![hk4e]));
+        assert_eq!(uigf.info.version, "v4.0");
+        assert_eq!(uigf.hk4e.as_ref().unwrap()[0].timezone, -5);
+        assert_eq!(
+            convert_hk4e_to_offical(&uigf, "600000001").unwrap(),
+            [record]
+        );
+    }
+
+    #[test]
+    fn hkrpg_export_reads_back() {
+        let record = StarRailGachaRecord {
+            id: String::from("1720000000000000001"),
+            uid: String::from("100000001"),
+            gacha_id: String::from("4001"),
+            gacha_type: String::from("21"),
+            item_id: String::from("1001"),
+            count: String::from("1"),
+            time: String::from("2025-07-11 12:00:00"),
+            name: String::from("Saber"),
+            lang: String::from("en-us"),
+            item_type: String::from("Character"),
+            rank_type: String::from("5"),
+        };
+        let hkrpg = convert_offical_to_hkrpg(
+            "100000001",
+            String::from("en-us"),
+            8,
+            std::slice::from_ref(&record),
+        );
+
+        let uigf = reread(UIGF4::new(&datetime!(2025-07-12 00:00:00 UTC)).with_hkrpg(vec![hkrpg]));
+        assert!(uigf.hk4e.is_none());
+        assert_eq!(
+            convert_hkrpg_to_offical(&uigf, "100000001").unwrap(),
+            [record]
+        );
+    }
+}
@@ -1,42 +1,33 @@
 extern crate lazy_static;
 extern crate reqwest;
 extern crate serde;
+extern crate serde_json;
+extern crate serde_path_to_error;
 extern crate time;
 extern crate tokio;
 extern crate tracing;
 extern crate url;
 
-use crate::constants;
 use crate::disk_cache::{BlockFile, EntryStore, IndexFile};
 use crate::error::{Error, Result};
-use crate::storage::entity_account::AccountFacet;
+use crate::gacha::policy::FetchPolicy;
+use crate::storage::entity_account::{AccountFacet, AccountProperties};
 use lazy_static::lazy_static;
-use reqwest::Client as Reqwest;
+use reqwest::{Client as Reqwest, RequestBuilder};
 use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
-use std::collections::hash_map::Entry;
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{prelude::BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use time::{OffsetDateTime, UtcOffset};
+use time::macros::format_description;
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
 use tokio::sync::Mutex;
-use tracing::debug;
 use url::Url;
 
-use super::gacha::{GachaRecord, GachaUrl};
-
-pub(super) fn create_default_reqwest() -> Result<reqwest::Client> {
-    Ok(reqwest::Client::builder()
-        .user_agent(format!(
-            "{} v{} by {}",
-            constants::NAME,
-            constants::VERSION,
-            constants::AUTHOR
-        ))
-        .build()?)
-}
+use super::gacha::GachaUrl;
+use super::kuro::kuro::kuro_server_time_zone;
 
 pub(super) fn lookup_path_line_from_keyword<P: AsRef<Path>>(
     path: P,
@@ -209,11 +200,113 @@ pub(super) fn lookup_gacha_urls_from_endpoint<P: AsRef<Path>>(
 
 #[derive(Deserialize)]
 pub(super) struct GachaResponse<T> {
+    #[serde(alias = "code")]
     pub retcode: Option<i32>,
     pub message: String,
     pub data: Option<T>,
 }
 
+impl<T: DeserializeOwned> GachaResponse<T> {
+    /// Report the json path that failed, the servers change their shape now and then
+    pub(super) fn from_body(body: &str) -> Result<Self> {
+        let deserializer = &mut serde_json::Deserializer::from_str(body);
+        serde_path_to_error::deserialize(deserializer).map_err(|error| Error::GachaRecordResponse {
+            path: error.path().to_string(),
+            message: error.inner().to_string(),
+        })
+    }
+}
+
+/// Send a gacha record request and deserialize its response,
+/// archiving the raw response when the policy asks for it.
+pub(super) async fn send_gacha_request<T: DeserializeOwned>(
+    reqwest: &Reqwest,
+    request: RequestBuilder,
+    body: Option<serde_json::Value>,
+    policy: &FetchPolicy,
+) -> Result<GachaResponse<T>> {
+    let request = request.timeout(policy.request_timeout()).build()?;
+    let method = request.method().to_string();
+    let url = request.url().clone();
+
+    let response = reqwest.execute(request).await?;
+    let status = response.status();
+    let error = response.error_for_status_ref().err();
+    let text = response.text().await?;
+
+    if let Some(archive) = &policy.archive {
+        archive.save_page(&method, &url, body, status.as_u16(), &text);
+    }
+    if let Some(error) = error {
+        return Err(error.into());
+    }
+
+    GachaResponse::from_body(&text)
+}
+
+/// Replace the scheme, host and port of the url with the ones of the base url
+pub(super) fn override_url_origin(url: &mut Url, base_url: &str) -> Result<()> {
+    let base_url = Url::parse(base_url).map_err(|_| Error::IllegalGachaUrl)?;
+    url.set_scheme(base_url.scheme())
+        .map_err(|_| Error::IllegalGachaUrl)?;
+    url.set_host(base_url.host_str())
+        .map_err(|_| Error::IllegalGachaUrl)?;
+    url.set_port(base_url.port())
+        .map_err(|_| Error::IllegalGachaUrl)?;
+    Ok(())
+}
+
+/// UTC offset in hours of the server the account plays on, the record times are in it.
+///   The one reported by the last pull, else guessed from the gacha url or the uid.
+pub fn account_time_zone(
+    facet: &AccountFacet,
+    uid: &str,
+    gacha_url: Option<&str>,
+    properties: Option<&AccountProperties>,
+) -> i8 {
+    if let Some(time_zone) = properties.and_then(AccountProperties::time_zone) {
+        return time_zone;
+    }
+
+    match (facet, gacha_url) {
+        (AccountFacet::WutheringWaves, Some(gacha_url)) => kuro_server_time_zone(gacha_url),
+        _ => server_time_zone(facet, uid),
+    }
+}
+
+/// UTC offset in hours of the server the uid plays on, guessed from its region prefix.
+///   The pulls report the actual one, see `account_time_zone`.
+pub fn server_time_zone(facet: &AccountFacet, uid: &str) -> i8 {
+    match facet {
+        // The digits before the last 8: 6: America, 7: Europe, 8 and 18: Asia,
+        // 9: TW/HK/MO, others: China
+        AccountFacet::Genshin | AccountFacet::StarRail => {
+            match uid.get(..uid.len().saturating_sub(8)) {
+                Some("6") => -5,
+                Some("7") => 1,
+                _ => 8,
+            }
+        }
+        // 10: America, 15: Europe, 13: Asia, 17: TW/HK/MO, 8 digits: China
+        AccountFacet::ZenlessZoneZero => match uid.get(..2) {
+            Some("10") if uid.len() == 10 => -5,
+            Some("15") if uid.len() == 10 => 1,
+            _ => 8,
+        },
+        // The uid does not tell the server, only the gacha url does
+        AccountFacet::WutheringWaves => 8,
+    }
+}
+
+/// The UTC time of a record time `yyyy-MM-dd HH:mm:ss` of the server,
+/// none when malformed
+pub fn parse_server_time(time: &str, time_zone: i8) -> Option<OffsetDateTime> {
+    let format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+    let offset = UtcOffset::from_hms(time_zone, 0, 0).ok()?;
+    let time = PrimitiveDateTime::parse(time, &format).ok()?;
+    Some(time.assume_offset(offset).to_offset(UtcOffset::UTC))
+}
+
 //- Find the Gacha url and validate consistency
 //  Hashmap<String, GachaUrl> GACHA_URL_CACHED
 //    key: facet + uid + addr
@@ -222,3 +315,66 @@ pub(super) struct GachaResponse<T> {
 lazy_static! {
     pub static ref GACHA_URL_CACHED: Mutex<HashMap<String, GachaUrl>> = Default::default();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{account_time_zone, parse_server_time, server_time_zone, GachaResponse};
+    use crate::error::Error;
+    use crate::gacha::hoyoverse::genshin::GenshinGachaRecordPagination;
+    use crate::storage::entity_account::{AccountFacet, AccountProperties};
+    use time::macros::datetime;
+
+    #[test]
+    fn gacha_response_errors_report_the_json_path() {
+        let body = r#"{"retcode":0,"message":"OK","data":{"list":[{"id":1}]}}"#;
+        let result = GachaResponse::<GenshinGachaRecordPagination>::from_body(body);
+        assert!(matches!(
+            result,
+            Err(Error::GachaRecordResponse { ref path, .. }) if path == "data.list[0].id"
+        ));
+    }
+
+    #[test]
+    fn record_times_are_converted_from_the_server_time_zone() {
+        assert_eq!(server_time_zone(&AccountFacet::Genshin, "600000001"), -5);
+        assert_eq!(server_time_zone(&AccountFacet::StarRail, "700000001"), 1);
+        assert_eq!(server_time_zone(&AccountFacet::Genshin, "1800000001"), 8);
+        assert_eq!(server_time_zone(&AccountFacet::Genshin, "100000001"), 8);
+        assert_eq!(
+            server_time_zone(&AccountFacet::ZenlessZoneZero, "1000000001"),
+            -5
+        );
+        assert_eq!(
+            server_time_zone(&AccountFacet::ZenlessZoneZero, "10000001"),
+            8
+        );
+
+        assert_eq!(
+            parse_server_time("2024-05-25 10:00:00", -5),
+            Some(datetime!(2024-05-25 15:00:00 UTC))
+        );
+        assert_eq!(
+            parse_server_time("2024-05-25 01:00:00", 8),
+            Some(datetime!(2024-05-24 17:00:00 UTC))
+        );
+        assert_eq!(parse_server_time("not a time", 8), None);
+    }
+
+    #[test]
+    fn stored_time_zone_of_the_account_comes_first() {
+        let facet = AccountFacet::WutheringWaves;
+        let gacha_url = "https://aki-gm-resources-oversea.aki-game.net/aki/gacha/index.html#/record?svr_id=591d6af3a3090d8ea00d8f86cf6d7501&player_id=500000001";
+        let properties: AccountProperties =
+            serde_json::from_value(serde_json::json!({ "timeZone": 1 })).unwrap();
+
+        assert_eq!(account_time_zone(&facet, "500000001", None, None), 8);
+        assert_eq!(
+            account_time_zone(&facet, "500000001", Some(gacha_url), None),
+            -5
+        );
+        assert_eq!(
+            account_time_zone(&facet, "500000001", Some(gacha_url), Some(&properties)),
+            1
+        );
+    }
+}
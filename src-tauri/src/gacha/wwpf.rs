@@ -0,0 +1,127 @@
+extern crate serde;
+extern crate serde_json;
+extern crate time;
+
+use crate::constants::{ID, VERSION};
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use time::OffsetDateTime;
+
+use super::kuro::wutheringwaves::WutheringWavesGachaRecord;
+
+// Wuthering Waves Proprietary Format
+//   The stored records of one account, keyed as they are stored:
+//   card pool type, time, resource id and ordinal.
+
+const WWPF_VERSION: &str = "v1.0";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WWPFInfo {
+    pub uid: String,
+    pub region_time_zone: i8,
+    pub export_timestamp: Option<i64>,
+    pub export_app: Option<String>,
+    pub export_app_version: Option<String>,
+    pub wwpf_version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WWPFListItem {
+    pub gacha_type: String,
+    pub card_pool_type: String,
+    pub resource_id: i32,
+    pub quality_level: i32,
+    pub resource_type: String,
+    pub name: String,
+    pub count: i32,
+    pub time: String,
+    /// Position within the records of the same second, counted from the oldest
+    pub ordinal: u32,
+}
+
+pub type WWPFList = Vec<WWPFListItem>;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WWPF {
+    pub info: WWPFInfo,
+    pub list: WWPFList,
+}
+
+impl WWPF {
+    pub fn new(uid: String, time_zone: i8, time: &OffsetDateTime, list: WWPFList) -> Self {
+        Self {
+            info: WWPFInfo {
+                uid,
+                region_time_zone: time_zone,
+                export_timestamp: Some(time.unix_timestamp()),
+                export_app: Some(ID.into()),
+                export_app_version: Some(VERSION.into()),
+                wwpf_version: WWPF_VERSION.into(),
+            },
+            list,
+        }
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    pub fn to_writer(&self, writer: impl Write, pretty: bool) -> Result<()> {
+        if pretty {
+            Ok(serde_json::to_writer_pretty(writer, self)?)
+        } else {
+            Ok(serde_json::to_writer(writer, self)?)
+        }
+    }
+}
+
+// Convert
+
+// WWPF -> Official WutheringWavesGachaRecord
+impl WutheringWavesGachaRecord {
+    fn from_wwpf(value: &WWPFListItem, uid: &str) -> Self {
+        Self {
+            id: None,
+            uid: Some(uid.to_owned()),
+            gacha_type: Some(value.gacha_type.clone()),
+            card_pool_type: value.card_pool_type.clone(),
+            resource_id: value.resource_id,
+            quality_level: value.quality_level,
+            resource_type: value.resource_type.clone(),
+            name: value.name.clone(),
+            count: value.count,
+            time: value.time.clone(),
+            ordinal: value.ordinal,
+        }
+    }
+}
+
+// Official WutheringWavesGachaRecord -> WWPF
+impl From<&WutheringWavesGachaRecord> for WWPFListItem {
+    fn from(value: &WutheringWavesGachaRecord) -> Self {
+        Self {
+            gacha_type: value.gacha_type.clone().unwrap_or_default(),
+            card_pool_type: value.card_pool_type.clone(),
+            resource_id: value.resource_id,
+            quality_level: value.quality_level,
+            resource_type: value.resource_type.clone(),
+            name: value.name.clone(),
+            count: value.count,
+            time: value.time.clone(),
+            ordinal: value.ordinal,
+        }
+    }
+}
+
+pub fn convert_wwpf_to_offical(wwpf: &WWPF) -> Vec<WutheringWavesGachaRecord> {
+    wwpf.list
+        .iter()
+        .map(|item| WutheringWavesGachaRecord::from_wwpf(item, &wwpf.info.uid))
+        .collect()
+}
+
+pub fn convert_offical_to_wwpf(records: &[WutheringWavesGachaRecord]) -> WWPFList {
+    records.iter().map(WWPFListItem::from).collect()
+}
@@ -0,0 +1,75 @@
+extern crate reqwest;
+extern crate serde;
+extern crate tracing;
+
+use crate::constants;
+use crate::error::Result;
+use reqwest::{Certificate, Client as Reqwest, Proxy};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::debug;
+
+/// Http Client Config
+///   Shared by every client that talks to the network.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HttpClientConfig {
+    /// Proxy url for all requests: `http://`, `https://`, `socks5://` or `socks5h://`
+    pub proxy: Option<String>,
+    /// Extra trusted root certificates, PEM or DER files
+    pub root_certificates: Vec<PathBuf>,
+    pub connect_timeout_secs: u64,
+    /// Total deadline of a request, from connecting until the response body is read.
+    /// A single request may set a shorter or longer one.
+    #[serde(alias = "readTimeoutSecs")]
+    pub request_timeout_secs: u64,
+    /// None to use the default user agent
+    pub user_agent: Option<String>,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            root_certificates: Vec::new(),
+            connect_timeout_secs: 10,
+            request_timeout_secs: 60,
+            user_agent: None,
+        }
+    }
+}
+
+pub fn default_user_agent() -> String {
+    format!(
+        "{} v{} by {}",
+        constants::NAME,
+        constants::VERSION,
+        constants::AUTHOR
+    )
+}
+
+pub fn create_reqwest(config: &HttpClientConfig) -> Result<Reqwest> {
+    let mut builder = Reqwest::builder()
+        .user_agent(config.user_agent.clone().unwrap_or_else(default_user_agent))
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs.max(1)))
+        .timeout(Duration::from_secs(config.request_timeout_secs.max(1)));
+
+    if let Some(proxy) = config.proxy.as_deref().filter(|proxy| !proxy.is_empty()) {
+        debug!("Using http proxy: {proxy}");
+        builder = builder.proxy(Proxy::all(proxy)?);
+    }
+
+    for root_certificate in &config.root_certificates {
+        debug!("Trust root certificate: {}", root_certificate.display());
+        let content = std::fs::read(root_certificate)?;
+        let certificate = if content.starts_with(b"-----BEGIN") {
+            Certificate::from_pem(&content)?
+        } else {
+            Certificate::from_der(&content)?
+        };
+        builder = builder.add_root_certificate(certificate);
+    }
+
+    Ok(builder.build()?)
+}
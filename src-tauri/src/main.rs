@@ -12,6 +12,9 @@ mod constants;
 mod disk_cache;
 mod error;
 mod gacha;
+mod http;
+mod paths;
+mod settings;
 mod storage;
 
 fn main() {
@@ -21,6 +24,7 @@ fn main() {
         .init();
 
     tauri::Builder::default()
+        .plugin(settings::SettingsPluginBuilder::new().build())
         .plugin(storage::StoragePluginBuilder::new().build())
         .plugin(gacha::plugin::GachaPluginBuilder::new().build())
         .setup(|app| {
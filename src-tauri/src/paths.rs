@@ -0,0 +1,175 @@
+extern crate anyhow;
+extern crate tauri;
+extern crate tracing;
+
+use crate::constants::{DATABASE, PORTABLE, RESPONSE_ARCHIVE, SETTINGS};
+use crate::error::Result;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Runtime};
+use tracing::debug;
+
+/// Marker file next to a legacy database, once it is copied to the app data directory
+const LEGACY_DATABASE_MIGRATED: &str = "com.wxwatch.gacha.tracker.db.migrated";
+
+/// App Paths
+///   Where the settings file, the response archive and the database are kept.
+///   One root decides them all, the first of:
+///    - In debug mode   : the src-tauri directory
+///    - In portable mode: next to the executable, marked by a portable file there
+///    - The app config and app data directories
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppPaths {
+    config_dir: PathBuf,
+    data_dir: PathBuf,
+    /// Where earlier releases kept the database, none if it is the data directory
+    legacy_dir: Option<PathBuf>,
+}
+
+impl AppPaths {
+    pub fn new<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Self> {
+        let exe_dir = std::env::current_exe()?
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| anyhow::anyhow!("Unable to resolve executable directory"))?;
+
+        let path_resolver = app_handle.path_resolver();
+        Self::resolve(
+            cfg!(debug_assertions),
+            exe_dir,
+            path_resolver.app_config_dir(),
+            path_resolver.app_data_dir(),
+        )
+    }
+
+    fn resolve(
+        debug: bool,
+        exe_dir: PathBuf,
+        app_config_dir: Option<PathBuf>,
+        app_data_dir: Option<PathBuf>,
+    ) -> Result<Self> {
+        if debug {
+            // Avoid clearing the files with the 'cargo clean' command in debug mode
+            return Ok(Self {
+                config_dir: PathBuf::new(),
+                data_dir: PathBuf::new(),
+                legacy_dir: None,
+            });
+        }
+
+        if exe_dir.join(PORTABLE).is_file() {
+            debug!("Portable mode, the files are next to the executable");
+            return Ok(Self {
+                config_dir: exe_dir.clone(),
+                data_dir: exe_dir,
+                legacy_dir: None,
+            });
+        }
+
+        Ok(Self {
+            config_dir: app_config_dir
+                .ok_or_else(|| anyhow::anyhow!("Unable to resolve app config directory"))?,
+            data_dir: app_data_dir
+                .ok_or_else(|| anyhow::anyhow!("Unable to resolve app data directory"))?,
+            legacy_dir: Some(exe_dir),
+        })
+    }
+
+    pub fn settings_file(&self) -> PathBuf {
+        self.config_dir.join(SETTINGS)
+    }
+
+    /// The response archive, kept next to the settings file
+    pub fn response_archive_dir(&self) -> PathBuf {
+        self.config_dir.join(RESPONSE_ARCHIVE)
+    }
+
+    /// The custom database file of the settings, if any, comes first
+    pub fn database_file(&self, custom_database_file: Option<PathBuf>) -> PathBuf {
+        custom_database_file.unwrap_or_else(|| self.data_dir.join(DATABASE))
+    }
+
+    /// The database earlier releases kept next to the executable,
+    /// none once it has been copied to the data directory.
+    pub fn legacy_database_file(&self) -> Option<PathBuf> {
+        let legacy_dir = self.legacy_dir.as_ref()?;
+        let legacy_database_file = legacy_dir.join(DATABASE);
+        if legacy_database_file.is_file() && !legacy_dir.join(LEGACY_DATABASE_MIGRATED).exists() {
+            Some(legacy_database_file)
+        } else {
+            None
+        }
+    }
+
+    /// Mark the legacy database as copied, it is left in place otherwise
+    pub fn mark_legacy_database_migrated(&self, database_file: &Path) -> Result<()> {
+        if let Some(legacy_dir) = &self.legacy_dir {
+            std::fs::write(
+                legacy_dir.join(LEGACY_DATABASE_MIGRATED),
+                database_file.to_string_lossy().as_bytes(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AppPaths;
+    use crate::constants::{DATABASE, PORTABLE, RESPONSE_ARCHIVE, SETTINGS};
+    use std::path::PathBuf;
+
+    #[test]
+    fn paths_are_resolved_from_one_root() {
+        let exe_dir = std::env::temp_dir().join(format!("paths-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&exe_dir);
+        std::fs::create_dir_all(&exe_dir).unwrap();
+        let config_dir = PathBuf::from("config");
+        let data_dir = PathBuf::from("data");
+        let resolve = |debug| {
+            AppPaths::resolve(
+                debug,
+                exe_dir.clone(),
+                Some(config_dir.clone()),
+                Some(data_dir.clone()),
+            )
+            .unwrap()
+        };
+
+        // The app directories, with the legacy database next to the executable
+        let paths = resolve(false);
+        assert_eq!(paths.settings_file(), config_dir.join(SETTINGS));
+        assert_eq!(
+            paths.response_archive_dir(),
+            config_dir.join(RESPONSE_ARCHIVE)
+        );
+        assert_eq!(paths.database_file(None), data_dir.join(DATABASE));
+        assert_eq!(paths.legacy_database_file(), None);
+        std::fs::write(exe_dir.join(DATABASE), b"").unwrap();
+        assert_eq!(paths.legacy_database_file(), Some(exe_dir.join(DATABASE)));
+        paths
+            .mark_legacy_database_migrated(&data_dir.join(DATABASE))
+            .unwrap();
+        assert_eq!(paths.legacy_database_file(), None);
+
+        // The custom database file of the settings comes first
+        let custom = PathBuf::from("custom.db");
+        assert_eq!(paths.database_file(Some(custom.clone())), custom);
+
+        // Portable mode keeps every file next to the executable
+        std::fs::write(exe_dir.join(PORTABLE), b"").unwrap();
+        let paths = resolve(false);
+        assert_eq!(paths.settings_file(), exe_dir.join(SETTINGS));
+        assert_eq!(paths.response_archive_dir(), exe_dir.join(RESPONSE_ARCHIVE));
+        assert_eq!(paths.database_file(None), exe_dir.join(DATABASE));
+        assert_eq!(paths.legacy_database_file(), None);
+        assert_eq!(paths.database_file(Some(custom.clone())), custom);
+
+        // Debug mode comes before portable mode
+        let paths = resolve(true);
+        assert_eq!(paths.settings_file(), PathBuf::from(SETTINGS));
+        assert_eq!(paths.database_file(None), PathBuf::from(DATABASE));
+        assert_eq!(paths.legacy_database_file(), None);
+
+        std::fs::remove_dir_all(&exe_dir).unwrap();
+    }
+}
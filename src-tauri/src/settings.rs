@@ -0,0 +1,165 @@
+extern crate anyhow;
+extern crate reqwest;
+extern crate serde;
+extern crate serde_json;
+extern crate tauri;
+extern crate tracing;
+
+use crate::constants::RESPONSE_ARCHIVE;
+use crate::error::Result;
+use crate::gacha::archive::{ResponseArchive, ResponseArchiveSettings};
+use crate::gacha::policy::FetchPolicySettings;
+use crate::http::{create_reqwest, HttpClientConfig};
+use crate::paths::AppPaths;
+use reqwest::Client as Reqwest;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::plugin::{Builder as TauriPluginBuilder, TauriPlugin};
+use tauri::{AppHandle, Runtime};
+use tracing::{debug, warn};
+
+/// Settings
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub fetch_policy: FetchPolicySettings,
+    pub http: HttpClientConfig,
+    pub response_archive: ResponseArchiveSettings,
+    /// Custom database file, takes effect on the next launch
+    pub database_file: Option<PathBuf>,
+}
+
+/// Settings Store
+///   Settings kept in memory and persisted as a json file.
+pub struct SettingsStore {
+    pub settings_file: PathBuf,
+    response_archive_dir: PathBuf,
+    settings: RwLock<Settings>,
+}
+
+impl SettingsStore {
+    pub fn new<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Self> {
+        let paths = AppPaths::new(app_handle)?;
+        Self::load(paths.settings_file(), paths.response_archive_dir())
+    }
+
+    /// The response archive is kept next to the settings file
+    pub fn new_with_settings_file<P: AsRef<Path>>(settings_file: P) -> Result<Self> {
+        let settings_file = settings_file.as_ref().to_path_buf();
+        let response_archive_dir = settings_file.with_file_name(RESPONSE_ARCHIVE);
+        Self::load(settings_file, response_archive_dir)
+    }
+
+    fn load(settings_file: PathBuf, response_archive_dir: PathBuf) -> Result<Self> {
+        debug!("Load settings from: {}", settings_file.display());
+
+        let settings = if settings_file.exists() {
+            let file = File::open(&settings_file)?;
+            serde_json::from_reader(file).unwrap_or_else(|error| {
+                // Never refuse to start over a broken settings file
+                warn!("Invalid settings file, fallback to defaults: {error}");
+                Settings::default()
+            })
+        } else {
+            Settings::default()
+        };
+
+        Ok(Self {
+            settings_file,
+            response_archive_dir,
+            settings: RwLock::new(settings),
+        })
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Create a http client from the current settings
+    pub fn create_reqwest(&self) -> Result<Reqwest> {
+        create_reqwest(&self.settings.read().unwrap().http)
+    }
+
+    pub fn response_archive(&self) -> ResponseArchive {
+        ResponseArchive::new(
+            &self.response_archive_dir,
+            self.settings.read().unwrap().response_archive.clone(),
+        )
+    }
+
+    pub fn update(&self, settings: Settings) -> Result<Settings> {
+        // Reject a http config the client cannot be built with
+        create_reqwest(&settings.http)?;
+
+        if let Some(parent) = self.settings_file.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let file = File::create(&self.settings_file)?;
+        serde_json::to_writer_pretty(file, &settings)?;
+
+        debug!("Settings updated: {:?}", settings);
+        *self.settings.write().unwrap() = settings.clone();
+        Ok(settings)
+    }
+}
+
+/// Tauri commands
+
+#[tauri::command]
+fn get_settings(settings: tauri::State<'_, SettingsStore>) -> Settings {
+    settings.get()
+}
+
+#[tauri::command]
+fn update_settings(
+    settings_store: tauri::State<'_, SettingsStore>,
+    settings: Settings,
+) -> Result<Settings> {
+    settings_store.update(settings)
+}
+
+/// Tauri plugin
+
+#[derive(Default)]
+pub struct SettingsPluginBuilder {
+    settings_file: Option<PathBuf>,
+}
+
+impl SettingsPluginBuilder {
+    const PLUGIN_NAME: &'static str = "settings";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[allow(unused)]
+    pub fn settings_file<P: AsRef<Path>>(mut self, settings_file: P) -> Self {
+        self.settings_file = Some(settings_file.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
+        TauriPluginBuilder::new(Self::PLUGIN_NAME)
+            .setup(move |app_handle| {
+                debug!("Setup settings plugin...");
+                let settings = if let Some(settings_file) = self.settings_file {
+                    SettingsStore::new_with_settings_file(settings_file)?
+                } else {
+                    SettingsStore::new(app_handle)?
+                };
+
+                use tauri::Manager;
+                app_handle.manage(settings);
+
+                Ok(())
+            })
+            .invoke_handler(tauri::generate_handler![get_settings, update_settings])
+            .build()
+    }
+}
@@ -8,7 +8,9 @@ use sea_orm::{TryGetError, TryGetable};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map as Json, Value as JsonValue};
 
-#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, EnumIter, DeriveActiveEnum)]
+use crate::gacha::kuro::kuro::KuroRegion;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize, EnumIter, DeriveActiveEnum)]
 #[sea_orm(rs_type = "String", db_type = "Text")]
 pub enum AccountFacet {
     #[sea_orm(string_value = "genshin")]
@@ -20,6 +22,9 @@ pub enum AccountFacet {
     #[sea_orm(string_value = "wutheringwaves")]
     #[serde(rename = "wutheringwaves")]
     WutheringWaves,
+    #[sea_orm(string_value = "zenlesszonezero")]
+    #[serde(rename = "zenlesszonezero")]
+    ZenlessZoneZero,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
@@ -46,6 +51,33 @@ pub enum Relation {}
 
 impl ActiveModelBehavior for ActiveModel {}
 
+impl AccountProperties {
+    pub const PREFERRED_LANGUAGE: &'static str = "preferredLanguage";
+    pub const TIME_ZONE: &'static str = "timeZone";
+    pub const REGION: &'static str = "region";
+
+    /// Language to fetch the gacha records in, overrides the one of the gacha url
+    pub fn preferred_language(&self) -> Option<&str> {
+        self.get(Self::PREFERRED_LANGUAGE)
+            .and_then(JsonValue::as_str)
+            .filter(|language| !language.is_empty())
+    }
+
+    /// UTC offset in hours of the server, as reported by the last pull
+    pub fn time_zone(&self) -> Option<i8> {
+        self.get(Self::TIME_ZONE)
+            .and_then(JsonValue::as_i64)
+            .and_then(|time_zone| <i8 as TryFrom<i64>>::try_from(time_zone).ok())
+    }
+
+    /// Wuthering Waves: server region of the last saved or pulled gacha url
+    pub fn region(&self) -> Option<KuroRegion> {
+        self.get(Self::REGION)
+            .cloned()
+            .and_then(|region| serde_json::from_value(region).ok())
+    }
+}
+
 /// Account Properties converts
 
 impl std::ops::Deref for AccountProperties {
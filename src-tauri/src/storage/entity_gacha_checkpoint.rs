@@ -0,0 +1,38 @@
+extern crate sea_orm;
+
+use super::entity_account::AccountFacet;
+use crate::gacha::GachaRecordCheckpoint;
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "gacha_checkpoints")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: u32,
+    #[sea_orm(indexed)]
+    pub facet: AccountFacet,
+    #[sea_orm(indexed)]
+    pub uid: String,
+    pub gacha_type: String,
+    pub end_id: String,
+    pub floor_end_id: Option<String>,
+    pub reached_end: bool,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// Convert
+
+impl From<Model> for GachaRecordCheckpoint {
+    fn from(value: Model) -> Self {
+        Self {
+            gacha_type: value.gacha_type,
+            end_id: value.end_id,
+            floor_end_id: value.floor_end_id,
+            reached_end: value.reached_end,
+        }
+    }
+}
@@ -19,6 +19,8 @@ pub struct Model {
     pub item_id: String,
     pub count: String,
     pub time: String,
+    /// The time in UTC, none when malformed
+    pub timestamp: Option<TimeDateTimeWithTimeZone>,
     pub name: String,
     pub lang: String,
     pub item_type: String,
@@ -42,6 +44,8 @@ impl From<StarRailGachaRecord> for ActiveModel {
             item_id: ActiveValue::set(value.item_id),
             count: ActiveValue::set(value.count),
             time: ActiveValue::set(value.time),
+            // Stamped on save, from the server time zone of the account
+            timestamp: ActiveValue::not_set(),
             name: ActiveValue::set(value.name),
             lang: ActiveValue::set(value.lang),
             item_type: ActiveValue::set(value.item_type),
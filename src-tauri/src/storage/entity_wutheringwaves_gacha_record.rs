@@ -20,6 +20,9 @@ pub struct Model {
     pub name: String,
     pub count: i32,
     pub time: String,
+    /// The time in UTC, none when malformed
+    pub timestamp: Option<TimeDateTimeWithTimeZone>,
+    pub ordinal: u32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -42,6 +45,9 @@ impl From<WutheringWavesGachaRecord> for ActiveModel {
             name: ActiveValue::set(value.name),
             count: ActiveValue::set(value.count),
             time: ActiveValue::set(value.time),
+            // Stamped on save, from the server time zone of the account
+            timestamp: ActiveValue::not_set(),
+            ordinal: ActiveValue::set(value.ordinal),
         }
     }
 }
@@ -59,6 +65,7 @@ impl From<Model> for WutheringWavesGachaRecord {
             name: value.name,
             count: value.count,
             time: value.time,
+            ordinal: value.ordinal,
         }
     }
 }
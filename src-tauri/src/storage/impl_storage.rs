@@ -1,13 +1,21 @@
+extern crate anyhow;
 extern crate futures;
 extern crate paste;
 extern crate sea_orm;
+extern crate serde;
+extern crate serde_json;
 extern crate tauri;
+extern crate time;
 extern crate tracing;
 
 use super::entity_account::{
     AccountFacet, AccountProperties, ActiveModel as AccountActiveModel, Column as AccountColumn,
     Entity as AccountEntity, Model as AccountModel,
 };
+use super::entity_gacha_checkpoint::{
+    ActiveModel as GachaCheckpointActiveModel, Column as GachaCheckpointColumn,
+    Entity as GachaCheckpointEntity,
+};
 use super::entity_genshin_gacha_record::{
     ActiveModel as GenshinGachaRecordActiveModel, Column as GenshinGachaRecordColumn,
     Entity as GenshinGachaRecordEntity,
@@ -20,27 +28,44 @@ use super::entity_wutheringwaves_gacha_record::{
     ActiveModel as WutheringWavesGachaRecordActiveModel, Column as WutheringWavesGachaRecordColumn,
     Entity as WutheringWavesGachaRecordEntity,
 };
-use super::utilities::{
-    create_index_statements, create_table_statement, execute_statements, is_constraint_unique_err,
+use super::entity_zenlesszonezero_gacha_record::{
+    ActiveModel as ZenlessZoneZeroGachaRecordActiveModel,
+    Column as ZenlessZoneZeroGachaRecordColumn, Entity as ZenlessZoneZeroGachaRecordEntity,
+};
+use super::migrations::{migrate, stamp_gacha_records};
+use super::query::{encode_cursor, integer_order_key, GachaRecordPage, GachaRecordQuery};
+use super::statistics::{
+    GachaRecordItemCount, GachaRecordPeriod, GachaRecordPeriodCount, GachaRecordRankCount,
+    GachaRecordTimeRange,
 };
-use crate::constants::DATABASE;
+use super::utilities::is_constraint_unique_err;
 use crate::error::{Error, Result};
-use crate::gacha::dict;
 use crate::gacha::hoyoverse::genshin::GenshinGachaRecord;
 use crate::gacha::hoyoverse::starrail::StarRailGachaRecord;
+use crate::gacha::hoyoverse::zenlesszonezero::ZenlessZoneZeroGachaRecord;
+use crate::gacha::kuro::kuro::KuroRegion;
 use crate::gacha::kuro::wutheringwaves::WutheringWavesGachaRecord;
+use crate::gacha::provider::find_provider;
+use crate::gacha::utilities::{account_time_zone, parse_server_time};
+use crate::gacha::GachaRecordCheckpoint;
+use crate::paths::AppPaths;
+use crate::settings::SettingsStore;
 use futures::TryStreamExt;
 use paste::paste;
-use sea_orm::sea_query::{Condition, Index, OnConflict};
+use sea_orm::sea_query::{Alias, Condition, Expr, Func, OnConflict, SimpleExpr};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectOptions, Database, DatabaseConnection,
-    DeriveIden, EntityTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait, TransactionTrait,
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectOptions, ConnectionTrait, Database,
+    DatabaseConnection, DbBackend, EntityTrait, ModelTrait, QueryFilter, QueryOrder, QuerySelect,
+    QueryTrait, Statement, TransactionTrait,
 };
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
 use std::path::{Path, PathBuf};
 use tauri::async_runtime::block_on;
 use tauri::plugin::{Builder as TauriPluginBuilder, TauriPlugin};
-use tauri::Runtime;
-use tracing::debug;
+use tauri::{AppHandle, Manager, Runtime};
+use time::OffsetDateTime;
+use tracing::{debug, info};
 
 /// Storage
 
@@ -49,23 +74,91 @@ pub struct Storage {
     pub database: DatabaseConnection,
 }
 
+/// Call the storage method of the facet, e.g. `count_` `_gacha_records_by_rank`
+/// of Genshin Impact is `count_genshin_gacha_records_by_rank`
+macro_rules! dispatch_gacha_records {
+    ($storage: expr, $facet: expr, $prefix: ident, $suffix: ident, ($($arg: expr),*)) => {
+        paste! {
+          match $facet {
+            AccountFacet::Genshin => $storage.[<$prefix genshin $suffix>]($($arg),*).await,
+            AccountFacet::StarRail => $storage.[<$prefix starrail $suffix>]($($arg),*).await,
+            AccountFacet::WutheringWaves => $storage.[<$prefix wutheringwaves $suffix>]($($arg),*).await,
+            AccountFacet::ZenlessZoneZero => $storage.[<$prefix zenlesszonezero $suffix>]($($arg),*).await,
+          }
+        }
+    };
+    // The record types differ, map each result to a common one
+    ($storage: expr, $facet: expr, $prefix: ident, $suffix: ident, ($($arg: expr),*), $map: expr) => {
+        paste! {
+          match $facet {
+            AccountFacet::Genshin => $storage.[<$prefix genshin $suffix>]($($arg),*).await.and_then($map),
+            AccountFacet::StarRail => $storage.[<$prefix starrail $suffix>]($($arg),*).await.and_then($map),
+            AccountFacet::WutheringWaves => $storage.[<$prefix wutheringwaves $suffix>]($($arg),*).await.and_then($map),
+            AccountFacet::ZenlessZoneZero => $storage.[<$prefix zenlesszonezero $suffix>]($($arg),*).await.and_then($map),
+          }
+        }
+    };
+}
+
 impl Storage {
-    pub async fn new() -> Result<Self> {
+    pub async fn new<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Self> {
+        let database_file = Self::resolve_database_file(app_handle).await?;
+        Self::new_with_database_file(database_file).await
+    }
+
+    /// The database file, the custom one of the settings or the one of the app paths.
+    ///   An earlier database next to the executable is copied to the app data directory
+    ///   on first launch, the original is left untouched and marked as migrated.
+    async fn resolve_database_file<R: Runtime>(app_handle: &AppHandle<R>) -> Result<PathBuf> {
+        let paths = AppPaths::new(app_handle)?;
+        let custom_database_file = app_handle
+            .try_state::<SettingsStore>()
+            .and_then(|settings| settings.get().database_file);
+        if custom_database_file.is_some() {
+            return Ok(paths.database_file(custom_database_file));
+        }
+
+        let database_file = paths.database_file(None);
+
         // HACK: See -> https://github.com/lgou2w/HoYo.Gacha/issues/8
-        //  - In debug mode  : the database file is in the src-tauri directory
-        //  - In release mode: the database file is in the same directory as the executable
-        let database_file = if cfg!(debug_assertions) {
-            // Avoid clearing the database file with the 'cargo clean' command in debug mode
-            PathBuf::from(DATABASE)
-        } else {
-            std::env::current_exe()?.parent().unwrap().join(DATABASE)
-        };
+        //   Earlier releases kept the database next to the executable
+        if let Some(legacy_database_file) = paths.legacy_database_file() {
+            if !database_file.exists() {
+                Self::copy_database_file(&legacy_database_file, &database_file).await?;
+                paths.mark_legacy_database_migrated(&database_file)?;
+            }
+        }
 
-        Self::new_with_database_file(database_file).await
+        Ok(database_file)
+    }
+
+    /// Copy a database with the pending journal applied
+    async fn copy_database_file(from: &Path, to: &Path) -> Result<()> {
+        info!("Copy database from {} to {}", from.display(), to.display());
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let database = Database::connect(format!("sqlite://{}?mode=rw", from.display())).await?;
+        database
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                "VACUUM INTO ?",
+                [to.to_string_lossy().into_owned().into()],
+            ))
+            .await?;
+        database.close().await?;
+        Ok(())
     }
 
     pub async fn new_with_database_file<P: AsRef<Path>>(database_file: P) -> Result<Self> {
         let database_file = database_file.as_ref();
+        if let Some(parent) = database_file.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
         let url = format!("sqlite://{}?mode=rwc", database_file.display());
 
         debug!("Create storage with database: {url}");
@@ -85,78 +178,8 @@ impl Storage {
     pub async fn initialize(&self) -> Result<()> {
         debug!("Initializing storage...");
 
-        {
-            debug!("Creating tables...");
-            let create_genshin_table_statement = create_table_statement(GenshinGachaRecordEntity);
-            let create_star_rail_table_statement =
-                create_table_statement(StarRailGachaRecordEntity);
-            let create_wuthering_waves_table_statement =
-                create_table_statement(WutheringWavesGachaRecordEntity);
-            let create_account_statement = create_table_statement(AccountEntity);
-            execute_statements(
-                &self.database,
-                &[
-                    create_genshin_table_statement,
-                    create_star_rail_table_statement,
-                    create_wuthering_waves_table_statement,
-                    create_account_statement,
-                ],
-            )
-            .await?;
-        }
-
-        {
-            debug!("Creating indexes...");
-            let genshin_index = create_index_statements(GenshinGachaRecordEntity);
-            let star_rail_index = create_index_statements(StarRailGachaRecordEntity);
-            let wuthering_waves_index = create_index_statements(WutheringWavesGachaRecordEntity);
-            let account_index = create_index_statements(AccountEntity);
-
-            // Account: facet + uid constraint
-            let account_facet_index = Index::create()
-                .name(&format!(
-                    "idx-{}-{}-{}",
-                    AccountEntity.to_string(),
-                    AccountColumn::Facet.to_string(),
-                    AccountColumn::Uid.to_string()
-                ))
-                .table(AccountEntity)
-                .col(AccountColumn::Facet)
-                .col(AccountColumn::Uid)
-                .unique()
-                .if_not_exists()
-                .to_owned();
-
-            let mut statements = genshin_index;
-            statements.extend(star_rail_index);
-            statements.extend(wuthering_waves_index);
-            statements.extend(account_index);
-            statements.push(account_facet_index);
-            execute_statements(&self.database, &statements).await?;
-        }
-
-        {
-            debug!("Checking for Genshin Impact Item ID Updates...");
-            let records = GenshinGachaRecordEntity::find()
-                .filter(GenshinGachaRecordColumn::ItemId.eq(""))
-                .all(&self.database)
-                .await?;
-            debug!("The records: {:?}", records);
-
-            let txn = self.database.begin().await?;
-            for record in records {
-                let mut model = GenshinGachaRecordActiveModel::from(record.clone());
-                if let Some(entry) = dict::embedded::name(
-                    &AccountFacet::Genshin,
-                    "en-us",
-                    &model.clone().name.unwrap(),
-                ) {
-                    model.item_id = ActiveValue::Set(entry.item_id.to_string());
-                }
-                model.update(&txn).await?;
-            }
-            txn.commit().await?;
-        }
+        let version = migrate(&self.database, &self.database_file).await?;
+        debug!("Storage schema version: {version}");
 
         debug!("Storage initialized");
         Ok(())
@@ -287,14 +310,24 @@ impl Storage {
         uid: &str,
         gacha_url: Option<&str>,
     ) -> Result<AccountModel> {
-        self.update_account(
-            facet,
-            uid,
-            ActiveValue::NotSet,
-            ActiveValue::Set(gacha_url.map(|s| s.to_owned())),
-            ActiveValue::NotSet,
-        )
-        .await
+        let account = self
+            .update_account(
+                facet,
+                uid,
+                ActiveValue::NotSet,
+                ActiveValue::Set(gacha_url.map(|s| s.to_owned())),
+                ActiveValue::NotSet,
+            )
+            .await?;
+
+        match (facet, gacha_url) {
+            (AccountFacet::WutheringWaves, Some(gacha_url)) => {
+                let region = KuroRegion::from_gacha_url(gacha_url);
+                self.save_account_region(facet, uid, region).await?;
+                self.find_account(facet, uid).await
+            }
+            _ => Ok(account),
+        }
     }
 
     pub async fn update_account_properties(
@@ -313,6 +346,87 @@ impl Storage {
         .await
     }
 
+    /// UTC offset in hours of the server the account plays on, see `account_time_zone`
+    pub async fn find_account_time_zone(&self, facet: &AccountFacet, uid: &str) -> Result<i8> {
+        let account = self.try_find_account(facet, uid).await?;
+        Ok(account_time_zone(
+            facet,
+            uid,
+            account
+                .as_ref()
+                .and_then(|account| account.gacha_url.as_deref()),
+            account
+                .as_ref()
+                .and_then(|account| account.properties.as_ref()),
+        ))
+    }
+
+    /// Remember the server time zone reported by a pull. The stored records of the
+    /// account are stamped again when it differs from the one they were stamped in.
+    pub async fn save_account_time_zone(
+        &self,
+        facet: &AccountFacet,
+        uid: &str,
+        time_zone: i8,
+    ) -> Result<()> {
+        let Some(account) = self.try_find_account(facet, uid).await? else {
+            return Ok(());
+        };
+
+        let properties = account.properties.as_ref();
+        if properties.and_then(AccountProperties::time_zone) == Some(time_zone) {
+            return Ok(());
+        }
+
+        let stamped_time_zone =
+            account_time_zone(facet, uid, account.gacha_url.as_deref(), properties);
+        let mut properties = account
+            .properties
+            .map(|properties| properties.0)
+            .unwrap_or_default();
+        properties.insert(AccountProperties::TIME_ZONE.to_owned(), time_zone.into());
+        self.update_account_properties(facet, uid, Some(&AccountProperties(properties)))
+            .await?;
+
+        if stamped_time_zone != time_zone {
+            dispatch_gacha_records!(self, facet, restamp_, _gacha_records, (uid, time_zone))?;
+        }
+        Ok(())
+    }
+
+    /// Remember the server region of the gacha url, if it has changed
+    pub async fn save_account_region(
+        &self,
+        facet: &AccountFacet,
+        uid: &str,
+        region: KuroRegion,
+    ) -> Result<()> {
+        let Some(account) = self.try_find_account(facet, uid).await? else {
+            return Ok(());
+        };
+
+        if account
+            .properties
+            .as_ref()
+            .and_then(AccountProperties::region)
+            == Some(region)
+        {
+            return Ok(());
+        }
+
+        let mut properties = account
+            .properties
+            .map(|properties| properties.0)
+            .unwrap_or_default();
+        properties.insert(
+            AccountProperties::REGION.to_owned(),
+            serde_json::to_value(region)?,
+        );
+        self.update_account_properties(facet, uid, Some(&AccountProperties(properties)))
+            .await?;
+        Ok(())
+    }
+
     pub async fn try_delete_account(&self, facet: &AccountFacet, uid: &str) -> Result<bool> {
         debug!("Delete account...: facet={facet:?}, uid={uid:?}");
 
@@ -331,13 +445,108 @@ impl Storage {
         if !result {
             Err(Error::AccountNotFound)
         } else {
-            Ok(())
+            self.delete_gacha_checkpoints(facet, uid).await
         }
     }
+
+    pub async fn find_gacha_checkpoints(
+        &self,
+        facet: &AccountFacet,
+        uid: &str,
+    ) -> Result<Vec<GachaRecordCheckpoint>> {
+        debug!("Find gacha checkpoints...: facet={facet:?}, uid={uid:?}");
+
+        let result = GachaCheckpointEntity::find()
+            .filter(GachaCheckpointColumn::Facet.eq(facet.clone()))
+            .filter(GachaCheckpointColumn::Uid.eq(uid))
+            .all(&self.database)
+            .await?
+            .into_iter()
+            .map(GachaRecordCheckpoint::from)
+            .collect();
+
+        Ok(result)
+    }
+
+    pub async fn save_gacha_checkpoint(
+        &self,
+        facet: &AccountFacet,
+        uid: &str,
+        checkpoint: &GachaRecordCheckpoint,
+    ) -> Result<()> {
+        debug!("Save gacha checkpoint...: facet={facet:?}, uid={uid:?}, checkpoint={checkpoint:?}");
+
+        let model = GachaCheckpointActiveModel {
+            id: ActiveValue::NotSet,
+            facet: ActiveValue::Set(facet.clone()),
+            uid: ActiveValue::Set(uid.to_owned()),
+            gacha_type: ActiveValue::Set(checkpoint.gacha_type.clone()),
+            end_id: ActiveValue::Set(checkpoint.end_id.clone()),
+            floor_end_id: ActiveValue::Set(checkpoint.floor_end_id.clone()),
+            reached_end: ActiveValue::Set(checkpoint.reached_end),
+        };
+
+        GachaCheckpointEntity::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    GachaCheckpointColumn::Facet,
+                    GachaCheckpointColumn::Uid,
+                    GachaCheckpointColumn::GachaType,
+                ])
+                .update_columns([
+                    GachaCheckpointColumn::EndId,
+                    GachaCheckpointColumn::FloorEndId,
+                    GachaCheckpointColumn::ReachedEnd,
+                ])
+                .to_owned(),
+            )
+            .exec_without_returning(&self.database)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Wutheringwaves records of the given second, newest first
+    pub async fn find_wutheringwaves_gacha_records_at(
+        &self,
+        uid: &str,
+        gacha_types: &[&str],
+        time: &str,
+    ) -> Result<Vec<WutheringWavesGachaRecord>> {
+        debug!("Find wutheringwaves gacha records at: uid={uid}, gacha_types={gacha_types:?}, time={time}");
+        Ok(WutheringWavesGachaRecordEntity::find()
+            .filter(
+                Condition::all()
+                    .add(WutheringWavesGachaRecordColumn::Uid.eq(uid))
+                    .add(
+                        WutheringWavesGachaRecordColumn::GachaType
+                            .is_in(gacha_types.iter().copied()),
+                    )
+                    .add(WutheringWavesGachaRecordColumn::Time.eq(time)),
+            )
+            .order_by_desc(WutheringWavesGachaRecordColumn::Ordinal)
+            .all(&self.database)
+            .await?
+            .into_iter()
+            .map(WutheringWavesGachaRecord::from)
+            .collect())
+    }
+
+    pub async fn delete_gacha_checkpoints(&self, facet: &AccountFacet, uid: &str) -> Result<()> {
+        debug!("Delete gacha checkpoints...: facet={facet:?}, uid={uid:?}");
+
+        GachaCheckpointEntity::delete_many()
+            .filter(GachaCheckpointColumn::Facet.eq(facet.clone()))
+            .filter(GachaCheckpointColumn::Uid.eq(uid))
+            .exec(&self.database)
+            .await?;
+
+        Ok(())
+    }
 }
 
 macro_rules! impl_gacha_records_curd {
-    ($struct: ident, $name: tt, $record: ident, $active_model: ident, $entity: ident, $column: ident) => {
+    ($struct: ident, $name: tt, $facet: ident, $record: ident, $active_model: ident, $entity: ident, $column: ident, $latest: ident, [$($key: ident),+]) => {
         paste! {
           impl $struct {
             pub async fn [<find_ $name _gacha_records>](&self,
@@ -371,6 +580,25 @@ macro_rules! impl_gacha_records_curd {
               Ok(result)
             }
 
+            pub async fn [<find_ $name _gacha_records_latest>](&self,
+              uid: &str,
+              gacha_types: &[&str]
+            ) -> Result<Option<$record>> {
+              debug!("Find {} latest gacha record by uid: {uid} {:?}", stringify!($name), gacha_types);
+
+              let result = $entity::find()
+                .filter(Condition::all()
+                  .add($column::Uid.eq(uid))
+                  .add($column::GachaType.is_in(gacha_types.iter().copied()))
+                )
+                .order_by_desc($column::$latest)
+                .one(&self.database)
+                .await?
+                .map($record::from);
+
+              Ok(result)
+            }
+
             pub async fn [<save_ $name _gacha_records>](&self,
               records: &[$record]
             ) -> Result<u64> {
@@ -379,18 +607,52 @@ macro_rules! impl_gacha_records_curd {
                 return Ok(0);
               }
 
+              // The times are in the server time zone of the account
+              let mut time_zones = HashMap::new();
+              for record in records {
+                let model = $active_model::from(record.clone());
+                let uid: &String = model.uid.as_ref();
+                if !time_zones.contains_key(uid) {
+                  let time_zone = self.find_account_time_zone(&AccountFacet::$facet, uid).await?;
+                  time_zones.insert(uid.clone(), time_zone);
+                }
+              }
+
               let txn = self.database.begin().await?;
               let mut changes = 0;
               for record in records {
-                let model = $active_model::from(record.clone());
+                let mut model = $active_model::from(record.clone());
+                let time_zone = time_zones[AsRef::<String>::as_ref(&model.uid)];
+                model.timestamp = ActiveValue::set(parse_server_time(model.time.as_ref(), time_zone));
                 changes += $entity::insert(model)
-                  .on_conflict(OnConflict::column($column::Id).do_nothing().to_owned())
+                  .on_conflict(OnConflict::columns([$($column::$key),+]).do_nothing().to_owned())
                   .exec_without_returning(&txn)
                   .await?;
               }
               txn.commit().await?;
               Ok(changes)
             }
+
+            /// Stamp the records of the account again, in its new server time zone
+            pub async fn [<restamp_ $name _gacha_records>](&self,
+              uid: &str,
+              time_zone: i8
+            ) -> Result<()> {
+              debug!("Restamp {} gacha records by uid: {uid} {time_zone}", stringify!($name));
+
+              let txn = self.database.begin().await?;
+              stamp_gacha_records::<$entity, _>(
+                &txn,
+                $column::Uid,
+                $column::Time,
+                $column::Timestamp,
+                uid,
+                time_zone,
+                false
+              ).await?;
+              txn.commit().await?;
+              Ok(())
+            }
           }
         }
     };
@@ -399,32 +661,386 @@ macro_rules! impl_gacha_records_curd {
 impl_gacha_records_curd!(
     Storage,
     genshin,
+    Genshin,
     GenshinGachaRecord,
     GenshinGachaRecordActiveModel,
     GenshinGachaRecordEntity,
-    GenshinGachaRecordColumn
+    GenshinGachaRecordColumn,
+    Id,
+    [Id]
 );
 
 impl_gacha_records_curd!(
     Storage,
     starrail,
+    StarRail,
     StarRailGachaRecord,
     StarRailGachaRecordActiveModel,
     StarRailGachaRecordEntity,
-    StarRailGachaRecordColumn
+    StarRailGachaRecordColumn,
+    Id,
+    [Id]
 );
 
 impl_gacha_records_curd!(
     Storage,
     wutheringwaves,
+    WutheringWaves,
     WutheringWavesGachaRecord,
     WutheringWavesGachaRecordActiveModel,
     WutheringWavesGachaRecordEntity,
-    WutheringWavesGachaRecordColumn
+    WutheringWavesGachaRecordColumn,
+    Time,
+    [Uid, CardPoolType, Time, ResourceId, Ordinal]
+);
+
+impl_gacha_records_curd!(
+    Storage,
+    zenlesszonezero,
+    ZenlessZoneZero,
+    ZenlessZoneZeroGachaRecord,
+    ZenlessZoneZeroGachaRecordActiveModel,
+    ZenlessZoneZeroGachaRecordEntity,
+    ZenlessZoneZeroGachaRecordColumn,
+    Id,
+    [Id]
+);
+
+macro_rules! impl_gacha_records_query {
+    ($struct: ident, $name: tt, $record: ident, $entity: ident, $column: ident, $rank: ident, $rank_value: expr, $item_type: ident, [$($key: ident),+]) => {
+        paste! {
+          impl $struct {
+            pub async fn [<query_ $name _gacha_records>](&self,
+              query: &GachaRecordQuery
+            ) -> Result<GachaRecordPage<$record>> {
+              debug!("Query {} gacha records: {query:?}", stringify!($name));
+
+              let mut condition = Condition::all()
+                .add($column::Uid.eq(query.uid.as_str()));
+              if let Some(gacha_types) = &query.gacha_types {
+                condition = condition.add($column::GachaType.is_in(gacha_types.iter().cloned()));
+              }
+              if let Some(start_time) = query.start_time {
+                let start_time = OffsetDateTime::from_unix_timestamp(start_time).map_err(time::Error::from)?;
+                condition = condition.add($column::Timestamp.gte(start_time));
+              }
+              if let Some(end_time) = query.end_time {
+                let end_time = OffsetDateTime::from_unix_timestamp(end_time).map_err(time::Error::from)?;
+                condition = condition.add($column::Timestamp.lt(end_time));
+              }
+              if let Some(ranks) = &query.ranks {
+                condition = condition.add($column::$rank.is_in(ranks.iter().copied().map($rank_value)));
+              }
+              if let Some(item_types) = &query.item_types {
+                condition = condition.add($column::$item_type.is_in(item_types.iter().cloned()));
+              }
+              if let Some(names) = &query.names {
+                condition = condition.add($column::Name.is_in(names.iter().cloned()));
+              }
+              let order_keys = || vec![$(integer_order_key($column::$key)),+];
+              if let Some(cursor) = query.cursor_condition(Expr::col($column::Timestamp).into(), order_keys())? {
+                condition = condition.add(cursor);
+              }
+
+              let limit = query.limit();
+              let mut select = $entity::find()
+                .filter(condition)
+                .order_by($column::Timestamp, query.sea_order());
+              for order_key in order_keys() {
+                select = select.order_by(order_key, query.sea_order());
+              }
+              let mut models = select
+                .limit(limit + 1)
+                .all(&self.database)
+                .await?;
+
+              // One more than the limit tells whether another page follows
+              let next_cursor = if models.len() as u64 > limit {
+                models.truncate(limit as usize);
+                models
+                  .last()
+                  .map(|model| {
+                    // Stamped on save, only records of an unknown time lack one
+                    let timestamp = model.timestamp.ok_or_else(|| {
+                      Error::IllegalGachaRecordCursor(format!("{}: {}", stringify!($name), model.id))
+                    })?;
+                    encode_cursor(&timestamp, vec![$(model.get($column::$key)),+])
+                  })
+                  .transpose()?
+              } else {
+                None
+              };
+
+              Ok(GachaRecordPage {
+                records: models.into_iter().map($record::from).collect(),
+                next_cursor,
+              })
+            }
+          }
+        }
+    };
+}
+
+impl_gacha_records_query!(
+    Storage,
+    genshin,
+    GenshinGachaRecord,
+    GenshinGachaRecordEntity,
+    GenshinGachaRecordColumn,
+    RankType,
+    |rank: u32| rank.to_string(),
+    ItemType,
+    [Id]
+);
+
+impl_gacha_records_query!(
+    Storage,
+    starrail,
+    StarRailGachaRecord,
+    StarRailGachaRecordEntity,
+    StarRailGachaRecordColumn,
+    RankType,
+    |rank: u32| rank.to_string(),
+    ItemType,
+    [Id]
+);
+
+impl_gacha_records_query!(
+    Storage,
+    wutheringwaves,
+    WutheringWavesGachaRecord,
+    WutheringWavesGachaRecordEntity,
+    WutheringWavesGachaRecordColumn,
+    QualityLevel,
+    |rank: u32| rank as i32,
+    ResourceType,
+    [Ordinal, Id]
+);
+
+impl_gacha_records_query!(
+    Storage,
+    zenlesszonezero,
+    ZenlessZoneZeroGachaRecord,
+    ZenlessZoneZeroGachaRecordEntity,
+    ZenlessZoneZeroGachaRecordColumn,
+    RankType,
+    |rank: u32| rank.to_string(),
+    ItemType,
+    [Id]
+);
+
+macro_rules! impl_gacha_records_statistics {
+    ($struct: ident, $name: tt, $facet: ident, $record: ident, $entity: ident, $column: ident, $rank: ident, $item_id: ident, $item_type: ident) => {
+        paste! {
+          impl $struct {
+            fn [<$name _gacha_records_condition>](uid: &str, gacha_type: Option<&str>) -> Condition {
+              Condition::all()
+                .add($column::Uid.eq(uid))
+                .add_option(gacha_type.map(|gacha_type| $column::GachaType.eq(gacha_type)))
+            }
+
+            pub async fn [<count_ $name _gacha_records_by_rank>](&self,
+              uid: &str
+            ) -> Result<Vec<GachaRecordRankCount>> {
+              debug!("Count {} gacha records by rank: {uid}", stringify!($name));
+              Ok($entity::find()
+                .select_only()
+                .column($column::GachaType)
+                .column_as(Expr::col($column::$rank).cast_as(Alias::new("INTEGER")), "rank")
+                .column_as(Expr::col($column::Uid).count(), "count")
+                .filter(Self::[<$name _gacha_records_condition>](uid, None))
+                .group_by($column::GachaType)
+                .group_by($column::$rank)
+                .order_by_asc($column::GachaType)
+                .order_by_desc($column::$rank)
+                .into_model::<GachaRecordRankCount>()
+                .all(&self.database)
+                .await?)
+            }
+
+            pub async fn [<count_ $name _gacha_records_by_item>](&self,
+              uid: &str,
+              gacha_type: Option<&str>
+            ) -> Result<Vec<GachaRecordItemCount>> {
+              debug!("Count {} gacha records by item: {uid} {gacha_type:?}", stringify!($name));
+              Ok($entity::find()
+                .select_only()
+                .column_as(Expr::col($column::$item_id).cast_as(Alias::new("TEXT")), "item_id")
+                .column($column::Name)
+                .column_as($column::$item_type, "item_type")
+                .column_as(Expr::col($column::$rank).cast_as(Alias::new("INTEGER")), "rank")
+                .column_as(Expr::col($column::Uid).count(), "count")
+                .filter(Self::[<$name _gacha_records_condition>](uid, gacha_type))
+                .group_by($column::$item_id)
+                .group_by($column::Name)
+                .group_by($column::$item_type)
+                .group_by($column::$rank)
+                .order_by_desc(Expr::col(Alias::new("count")))
+                .into_model::<GachaRecordItemCount>()
+                .all(&self.database)
+                .await?)
+            }
+
+            pub async fn [<find_ $name _gacha_records_time_range>](&self,
+              uid: &str,
+              gacha_type: Option<&str>
+            ) -> Result<GachaRecordTimeRange> {
+              debug!("Find {} gacha records time range: {uid} {gacha_type:?}", stringify!($name));
+              let result = $entity::find()
+                .select_only()
+                .column_as(Expr::col($column::Time).min(), "first_time")
+                .column_as(Expr::col($column::Time).max(), "last_time")
+                .column_as(Expr::col($column::Uid).count(), "count")
+                .filter(Self::[<$name _gacha_records_condition>](uid, gacha_type))
+                .into_model::<GachaRecordTimeRange>()
+                .one(&self.database)
+                .await?;
+
+              Ok(result.unwrap_or(GachaRecordTimeRange {
+                first_time: None,
+                last_time: None,
+                count: 0,
+              }))
+            }
+
+            pub async fn [<count_ $name _gacha_records_by_period>](&self,
+              uid: &str,
+              gacha_type: Option<&str>,
+              period: GachaRecordPeriod
+            ) -> Result<Vec<GachaRecordPeriodCount>> {
+              debug!("Count {} gacha records by period: {uid} {gacha_type:?} {period:?}", stringify!($name));
+              // The timestamps are in UTC, the periods in the time of the server
+              let time_zone = self.find_account_time_zone(&AccountFacet::$facet, uid).await?;
+              let period = SimpleExpr::from(
+                Func::cust(Alias::new("strftime")).args([
+                  period.strftime_format().into(),
+                  Expr::col($column::Timestamp).into(),
+                  format!("{time_zone:+} hours").into(),
+                ])
+              );
+              Ok($entity::find()
+                .select_only()
+                .column_as(period.clone(), "period")
+                .column_as(Expr::col($column::Uid).count(), "count")
+                .filter(Self::[<$name _gacha_records_condition>](uid, gacha_type))
+                .filter($column::Timestamp.is_not_null())
+                .group_by(period)
+                .order_by_asc(Expr::col(Alias::new("period")))
+                .into_model::<GachaRecordPeriodCount>()
+                .all(&self.database)
+                .await?)
+            }
+
+            /// The latest records of the top rank, at most `limit` of each pity track.
+            ///   The tracks are the gacha type mappings of the provider, keyed by the
+            ///   gacha type pulled. A stored gacha type of no track is one of its own.
+            pub async fn [<find_ $name _gacha_records_latest_top_rank>](&self,
+              uid: &str,
+              pity_tracks: &[(&str, &[&str])],
+              limit: u64
+            ) -> Result<BTreeMap<String, Vec<$record>>> {
+              debug!("Find {} latest top rank gacha records: {uid} {limit}", stringify!($name));
+              let stored_gacha_types = $entity::find()
+                .select_only()
+                .column($column::GachaType)
+                .distinct()
+                .filter(Self::[<$name _gacha_records_condition>](uid, None))
+                .into_tuple::<String>()
+                .all(&self.database)
+                .await?;
+
+              let mut tracks: Vec<(String, Vec<String>)> = pity_tracks
+                .iter()
+                .filter(|(_, gacha_types)| {
+                  gacha_types.iter().any(|gacha_type| stored_gacha_types.iter().any(|stored| stored == gacha_type))
+                })
+                .map(|(gacha_type, gacha_types)| {
+                  (gacha_type.to_string(), gacha_types.iter().map(|gacha_type| gacha_type.to_string()).collect())
+                })
+                .collect();
+              for gacha_type in stored_gacha_types {
+                if !pity_tracks.iter().any(|(_, gacha_types)| gacha_types.contains(&gacha_type.as_str())) {
+                  tracks.push((gacha_type.clone(), vec![gacha_type]));
+                }
+              }
+
+              let mut result = BTreeMap::new();
+              for (gacha_type, gacha_types) in tracks {
+                let query = GachaRecordQuery {
+                  uid: uid.to_owned(),
+                  gacha_types: Some(gacha_types),
+                  ranks: Some(vec![TOP_RANK]),
+                  limit: Some(limit),
+                  ..Default::default()
+                };
+                let page = self.[<query_ $name _gacha_records>](&query).await?;
+                result.insert(gacha_type, page.records);
+              }
+              Ok(result)
+            }
+          }
+        }
+    };
+}
+
+/// 5 stars, the rank type and the quality level of Wuthering Waves alike
+const TOP_RANK: u32 = 5;
+
+impl_gacha_records_statistics!(
+    Storage,
+    genshin,
+    Genshin,
+    GenshinGachaRecord,
+    GenshinGachaRecordEntity,
+    GenshinGachaRecordColumn,
+    RankType,
+    ItemId,
+    ItemType
+);
+
+impl_gacha_records_statistics!(
+    Storage,
+    starrail,
+    StarRail,
+    StarRailGachaRecord,
+    StarRailGachaRecordEntity,
+    StarRailGachaRecordColumn,
+    RankType,
+    ItemId,
+    ItemType
+);
+
+impl_gacha_records_statistics!(
+    Storage,
+    wutheringwaves,
+    WutheringWaves,
+    WutheringWavesGachaRecord,
+    WutheringWavesGachaRecordEntity,
+    WutheringWavesGachaRecordColumn,
+    QualityLevel,
+    ResourceId,
+    ResourceType
+);
+
+impl_gacha_records_statistics!(
+    Storage,
+    zenlesszonezero,
+    ZenlessZoneZero,
+    ZenlessZoneZeroGachaRecord,
+    ZenlessZoneZeroGachaRecordEntity,
+    ZenlessZoneZeroGachaRecordColumn,
+    RankType,
+    ItemId,
+    ItemType
 );
 
 /// Tauri commands
 
+#[tauri::command]
+fn find_database_file(storage: tauri::State<'_, Storage>) -> PathBuf {
+    storage.database_file.clone()
+}
+
 #[tauri::command]
 async fn create_account(
     storage: tauri::State<'_, Storage>,
@@ -538,6 +1154,114 @@ macro_rules! impl_gacha_records_tauri_command {
 impl_gacha_records_tauri_command!(genshin, GenshinGachaRecord);
 impl_gacha_records_tauri_command!(starrail, StarRailGachaRecord);
 impl_gacha_records_tauri_command!(wutheringwaves, WutheringWavesGachaRecord);
+impl_gacha_records_tauri_command!(zenlesszonezero, ZenlessZoneZeroGachaRecord);
+
+#[tauri::command]
+async fn query_gacha_records(
+    storage: tauri::State<'_, Storage>,
+    facet: AccountFacet,
+    query: GachaRecordQuery,
+) -> Result<GachaRecordPage<serde_json::Value>> {
+    fn to_values<T: serde::Serialize>(
+        page: GachaRecordPage<T>,
+    ) -> Result<GachaRecordPage<serde_json::Value>> {
+        Ok(GachaRecordPage {
+            records: page
+                .records
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<std::result::Result<_, _>>()?,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    match facet {
+        AccountFacet::Genshin => to_values(storage.query_genshin_gacha_records(&query).await?),
+        AccountFacet::StarRail => to_values(storage.query_starrail_gacha_records(&query).await?),
+        AccountFacet::WutheringWaves => {
+            to_values(storage.query_wutheringwaves_gacha_records(&query).await?)
+        }
+        AccountFacet::ZenlessZoneZero => {
+            to_values(storage.query_zenlesszonezero_gacha_records(&query).await?)
+        }
+    }
+}
+
+#[tauri::command]
+async fn count_gacha_records_by_rank(
+    storage: tauri::State<'_, Storage>,
+    facet: AccountFacet,
+    uid: String,
+) -> Result<Vec<GachaRecordRankCount>> {
+    dispatch_gacha_records!(storage, facet, count_, _gacha_records_by_rank, (&uid))
+}
+
+#[tauri::command]
+async fn count_gacha_records_by_item(
+    storage: tauri::State<'_, Storage>,
+    facet: AccountFacet,
+    uid: String,
+    gacha_type: Option<String>,
+) -> Result<Vec<GachaRecordItemCount>> {
+    dispatch_gacha_records!(
+        storage,
+        facet,
+        count_,
+        _gacha_records_by_item,
+        (&uid, gacha_type.as_deref())
+    )
+}
+
+#[tauri::command]
+async fn find_gacha_records_time_range(
+    storage: tauri::State<'_, Storage>,
+    facet: AccountFacet,
+    uid: String,
+    gacha_type: Option<String>,
+) -> Result<GachaRecordTimeRange> {
+    dispatch_gacha_records!(
+        storage,
+        facet,
+        find_,
+        _gacha_records_time_range,
+        (&uid, gacha_type.as_deref())
+    )
+}
+
+#[tauri::command]
+async fn count_gacha_records_by_period(
+    storage: tauri::State<'_, Storage>,
+    facet: AccountFacet,
+    uid: String,
+    gacha_type: Option<String>,
+    period: GachaRecordPeriod,
+) -> Result<Vec<GachaRecordPeriodCount>> {
+    dispatch_gacha_records!(
+        storage,
+        facet,
+        count_,
+        _gacha_records_by_period,
+        (&uid, gacha_type.as_deref(), period)
+    )
+}
+
+#[tauri::command]
+async fn find_gacha_records_latest_top_rank(
+    storage: tauri::State<'_, Storage>,
+    facet: AccountFacet,
+    uid: String,
+    limit: u64,
+) -> Result<serde_json::Value> {
+    let pity_tracks = find_provider(&facet).gacha_type_mappings();
+    dispatch_gacha_records!(
+        storage,
+        facet,
+        find_,
+        _gacha_records_latest_top_rank,
+        (&uid, pity_tracks, limit),
+        |records| Ok(serde_json::to_value(records)?)
+    )
+}
 
 /// Tauri plugin
 
@@ -547,7 +1271,7 @@ pub struct StoragePluginBuilder {
 }
 
 impl StoragePluginBuilder {
-    const PLUGIN_NAME: &str = "storage";
+    const PLUGIN_NAME: &'static str = "storage";
 
     pub fn new() -> Self {
         Self::default()
@@ -567,18 +1291,18 @@ impl StoragePluginBuilder {
                     let storage = if let Some(database_file) = self.database_file {
                         Storage::new_with_database_file(database_file).await?
                     } else {
-                        Storage::new().await?
+                        Storage::new(app_handle).await?
                     };
                     storage.initialize().await?;
                     Ok(storage)
                 });
 
-                use tauri::Manager;
                 app_handle.manage(storage?);
 
                 Ok(())
             })
             .invoke_handler(tauri::generate_handler![
+                find_database_file,
                 create_account,
                 find_accounts,
                 find_account,
@@ -592,7 +1316,279 @@ impl StoragePluginBuilder {
                 save_starrail_gacha_records,
                 find_wutheringwaves_gacha_records,
                 save_wutheringwaves_gacha_records,
+                find_zenlesszonezero_gacha_records,
+                save_zenlesszonezero_gacha_records,
+                query_gacha_records,
+                count_gacha_records_by_rank,
+                count_gacha_records_by_item,
+                find_gacha_records_time_range,
+                count_gacha_records_by_period,
+                find_gacha_records_latest_top_rank,
             ])
             .build()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{GenshinGachaRecordEntity, Storage};
+    use crate::gacha::hoyoverse::genshin::GenshinGachaRecord;
+    use crate::gacha::kuro::kuro::KuroRegion;
+    use crate::storage::entity_account::{AccountFacet, AccountProperties};
+    use crate::storage::query::GachaRecordQuery;
+    use crate::storage::statistics::{GachaRecordPeriod, GachaRecordPeriodCount};
+    use sea_orm::EntityTrait;
+    use std::path::PathBuf;
+    use time::macros::datetime;
+    use time::OffsetDateTime;
+
+    /// A migrated storage in a directory of its own, removed when dropped
+    struct TestStorage {
+        storage: Storage,
+        directory: PathBuf,
+    }
+
+    impl TestStorage {
+        async fn new(name: &str) -> Self {
+            let directory =
+                std::env::temp_dir().join(format!("storage-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&directory);
+            let storage = Storage::new_with_database_file(directory.join("database.db"))
+                .await
+                .unwrap();
+            storage.initialize().await.unwrap();
+            Self { storage, directory }
+        }
+    }
+
+    impl Drop for TestStorage {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    fn genshin_record(id: &str, time: &str) -> GenshinGachaRecord {
+        GenshinGachaRecord {
+            id: id.to_owned(),
+            uid: String::from("600000001"),
+            gacha_type: String::from("301"),
+            item_id: String::from("10000002"),
+            count: String::from("1"),
+            time: time.to_owned(),
+            name: String::from("Kamisato Ayaka"),
+            lang: String::from("en-us"),
+            item_type: String::from("Character"),
+            rank_type: String::from("5"),
+        }
+    }
+
+    async fn genshin_timestamp(storage: &Storage, id: &str) -> Option<OffsetDateTime> {
+        GenshinGachaRecordEntity::find_by_id(id.to_owned())
+            .one(&storage.database)
+            .await
+            .unwrap()
+            .unwrap()
+            .timestamp
+    }
+
+    #[tokio::test]
+    async fn records_are_stamped_in_the_account_time_zone() {
+        let test = TestStorage::new("time-zone").await;
+        let storage = &test.storage;
+        let facet = AccountFacet::Genshin;
+        storage
+            .create_account(&facet, "600000001", "", None, None)
+            .await
+            .unwrap();
+
+        // Guessed from the uid until a pull reports the server time zone
+        storage
+            .save_genshin_gacha_records(&[genshin_record("1", "2024-05-25 10:00:00")])
+            .await
+            .unwrap();
+        assert_eq!(
+            genshin_timestamp(storage, "1").await,
+            Some(datetime!(2024-05-25 15:00:00 UTC))
+        );
+
+        storage
+            .save_account_time_zone(&facet, "600000001", 8)
+            .await
+            .unwrap();
+        assert_eq!(
+            genshin_timestamp(storage, "1").await,
+            Some(datetime!(2024-05-25 02:00:00 UTC))
+        );
+        assert_eq!(
+            storage
+                .find_account_time_zone(&facet, "600000001")
+                .await
+                .unwrap(),
+            8
+        );
+
+        storage
+            .save_genshin_gacha_records(&[genshin_record("2", "2024-05-26 10:00:00")])
+            .await
+            .unwrap();
+        assert_eq!(
+            genshin_timestamp(storage, "2").await,
+            Some(datetime!(2024-05-26 02:00:00 UTC))
+        );
+    }
+
+    #[tokio::test]
+    async fn wutheringwaves_region_is_saved_with_the_gacha_url() {
+        let test = TestStorage::new("region").await;
+        let storage = &test.storage;
+        let facet = AccountFacet::WutheringWaves;
+        storage
+            .create_account(&facet, "100000001", "", None, None)
+            .await
+            .unwrap();
+
+        let account = storage
+            .update_account_gacha_url(
+                &facet,
+                "100000001",
+                Some("https://aki-gm-resources.aki-game.com/aki/gacha/index.html#/record?svr_id=76402e5b20be2c39f095a152090afddc"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            account
+                .properties
+                .as_ref()
+                .and_then(AccountProperties::region),
+            Some(KuroRegion::China)
+        );
+
+        storage
+            .save_account_region(&facet, "100000001", KuroRegion::Global)
+            .await
+            .unwrap();
+        let account = storage.find_account(&facet, "100000001").await.unwrap();
+        assert_eq!(
+            account
+                .properties
+                .as_ref()
+                .and_then(AccountProperties::region),
+            Some(KuroRegion::Global)
+        );
+    }
+
+    #[tokio::test]
+    async fn query_pages_in_order_of_timestamp_and_integer_id() {
+        let test = TestStorage::new("query").await;
+        let storage = &test.storage;
+        storage
+            .save_genshin_gacha_records(&[
+                genshin_record("9", "2024-05-25 10:00:00"),
+                genshin_record("10", "2024-05-25 10:00:00"),
+                genshin_record("11", "2024-05-24 10:00:00"),
+                genshin_record("8", "2024-05-26 10:00:00"),
+            ])
+            .await
+            .unwrap();
+
+        let mut query = GachaRecordQuery {
+            uid: String::from("600000001"),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let mut ids = Vec::new();
+        loop {
+            let page = storage.query_genshin_gacha_records(&query).await.unwrap();
+            ids.push(
+                page.records
+                    .into_iter()
+                    .map(|record| record.id)
+                    .collect::<Vec<_>>(),
+            );
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(ids, [["8", "10"], ["9", "11"]]);
+    }
+
+    #[tokio::test]
+    async fn periods_are_in_the_server_time() {
+        let test = TestStorage::new("period").await;
+        let storage = &test.storage;
+        // Stamped in UTC-5, the first record is on the next day in UTC
+        storage
+            .save_genshin_gacha_records(&[
+                genshin_record("1", "2024-05-25 23:30:00"),
+                genshin_record("2", "2024-05-26 00:30:00"),
+                genshin_record("3", "2024-06-01 10:00:00"),
+            ])
+            .await
+            .unwrap();
+
+        let count = |period: &str, count| GachaRecordPeriodCount {
+            period: period.to_owned(),
+            count,
+        };
+        assert_eq!(
+            storage
+                .count_genshin_gacha_records_by_period("600000001", None, GachaRecordPeriod::Day)
+                .await
+                .unwrap(),
+            [
+                count("2024-05-25", 1),
+                count("2024-05-26", 1),
+                count("2024-06-01", 1)
+            ]
+        );
+        assert_eq!(
+            storage
+                .count_genshin_gacha_records_by_period("600000001", None, GachaRecordPeriod::Month)
+                .await
+                .unwrap(),
+            [count("2024-05", 2), count("2024-06", 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn latest_top_rank_is_grouped_by_pity_track() {
+        let test = TestStorage::new("top-rank").await;
+        let storage = &test.storage;
+        let record = |id: &str, gacha_type: &str, time: &str| GenshinGachaRecord {
+            gacha_type: gacha_type.to_owned(),
+            ..genshin_record(id, time)
+        };
+        storage
+            .save_genshin_gacha_records(&[
+                record("1", "301", "2024-05-25 10:00:00"),
+                record("2", "400", "2024-05-26 10:00:00"),
+                record("3", "200", "2024-05-27 10:00:00"),
+                // No pity track of its own
+                record("4", "999", "2024-05-28 10:00:00"),
+            ])
+            .await
+            .unwrap();
+
+        let pity_tracks: &[(&str, &[&str])] = &[("200", &["200"]), ("301", &["301", "400"])];
+        let result = storage
+            .find_genshin_gacha_records_latest_top_rank("600000001", pity_tracks, 10)
+            .await
+            .unwrap();
+        let ids = result
+            .iter()
+            .map(|(gacha_type, records)| {
+                let ids = records.iter().map(|record| record.id.as_str());
+                (gacha_type.as_str(), ids.collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            [
+                ("200", vec!["3"]),
+                ("301", vec!["2", "1"]),
+                ("999", vec!["4"])
+            ]
+        );
+    }
+}
@@ -0,0 +1,648 @@
+extern crate futures;
+extern crate sea_orm;
+extern crate time;
+extern crate tracing;
+
+use super::entity_account::{
+    AccountFacet, AccountProperties, Column as AccountColumn, Entity as AccountEntity,
+};
+use super::entity_gacha_checkpoint::{
+    Column as GachaCheckpointColumn, Entity as GachaCheckpointEntity,
+};
+use super::entity_genshin_gacha_record::{
+    Column as GenshinGachaRecordColumn, Entity as GenshinGachaRecordEntity,
+};
+use super::entity_starrail_gacha_record::{
+    Column as StarRailGachaRecordColumn, Entity as StarRailGachaRecordEntity,
+};
+use super::entity_wutheringwaves_gacha_record::{
+    Column as WutheringWavesGachaRecordColumn, Entity as WutheringWavesGachaRecordEntity,
+};
+use super::entity_zenlesszonezero_gacha_record::{
+    Column as ZenlessZoneZeroGachaRecordColumn, Entity as ZenlessZoneZeroGachaRecordEntity,
+};
+use super::utilities::{create_index_statements, create_table_statement, execute_statements};
+use crate::error::{Error, Result};
+use crate::gacha::dict;
+use crate::gacha::utilities::{account_time_zone, parse_server_time};
+use futures::future::BoxFuture;
+use sea_orm::sea_query::{Alias, ColumnDef, Expr, Index, Table};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbBackend, EntityTrait,
+    Iden, QueryFilter, QueryOrder, QuerySelect, Statement, TransactionTrait,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::{debug, info};
+
+/// Schema Migration
+///   Applied in order of version, each one in its own transaction
+///   together with the record of its version.
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    up: for<'a> fn(&'a DatabaseTransaction) -> BoxFuture<'a, Result<()>>,
+}
+
+/// Never edit or reorder an applied migration, append a new one instead.
+///   A new database creates its tables from the current entities in the first
+///   migration, so the later ones must tolerate a column or index that already exists.
+///   An older database lacks the later columns, a migration only selects its own.
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_tables",
+        up: create_tables,
+    },
+    Migration {
+        version: 2,
+        name: "genshin_item_id",
+        up: genshin_item_id,
+    },
+    Migration {
+        version: 3,
+        name: "wutheringwaves_natural_key",
+        up: wutheringwaves_natural_key,
+    },
+    Migration {
+        version: 4,
+        name: "record_timestamps",
+        up: record_timestamps,
+    },
+];
+
+const SCHEMA_VERSION_TABLE: &str = "schema_version";
+
+pub fn latest_schema_version() -> u32 {
+    MIGRATIONS
+        .last()
+        .map(|migration| migration.version)
+        .unwrap_or(0)
+}
+
+pub async fn find_schema_version<C: ConnectionTrait>(database: &C) -> Result<u32> {
+    let row = database
+        .query_one(Statement::from_string(
+            DbBackend::Sqlite,
+            format!("SELECT MAX(version) AS version FROM {SCHEMA_VERSION_TABLE}"),
+        ))
+        .await?;
+
+    Ok(match row {
+        Some(row) => row.try_get::<Option<i64>>("", "version")?.unwrap_or(0) as u32,
+        None => 0,
+    })
+}
+
+/// Apply the pending migrations, returns the schema version of the database.
+///   A database that already holds tables is backed up before each migration.
+pub async fn migrate(database: &DatabaseConnection, database_file: &Path) -> Result<u32> {
+    execute_statements(
+        database,
+        &[Table::create()
+            .table(Alias::new(SCHEMA_VERSION_TABLE))
+            .if_not_exists()
+            .col(
+                ColumnDef::new(Alias::new("version"))
+                    .integer()
+                    .not_null()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(Alias::new("name")).string().not_null())
+            .col(ColumnDef::new(Alias::new("applied_at")).string().not_null())
+            .to_owned()],
+    )
+    .await?;
+
+    let mut version = find_schema_version(database).await?;
+    let latest = latest_schema_version();
+    if version > latest {
+        return Err(Error::DatabaseSchemaTooNew {
+            current: version,
+            latest,
+        });
+    }
+
+    // Databases before the schema version table still hold the tables
+    let mut has_tables = version > 0 || has_tables(database).await?;
+
+    let pending = MIGRATIONS.iter().filter(|m| m.version > version);
+    for migration in pending.collect::<Vec<_>>() {
+        if has_tables {
+            backup_database(database, database_file, version).await?;
+        }
+
+        info!(
+            "Apply database migration: {} ({})",
+            migration.version, migration.name
+        );
+        let txn = database.begin().await?;
+        (migration.up)(&txn)
+            .await
+            .map_err(|error| Error::DatabaseMigration {
+                version: migration.version,
+                name: migration.name.to_owned(),
+                message: error.to_string(),
+            })?;
+
+        let applied_at = OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .map_err(time::Error::from)?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            &format!(
+                "INSERT INTO {SCHEMA_VERSION_TABLE} (version, name, applied_at) VALUES (?, ?, ?)"
+            ),
+            [
+                migration.version.into(),
+                migration.name.into(),
+                applied_at.into(),
+            ],
+        ))
+        .await?;
+        txn.commit().await?;
+
+        version = migration.version;
+        has_tables = true;
+    }
+
+    Ok(version)
+}
+
+async fn has_tables(database: &DatabaseConnection) -> Result<bool> {
+    let row = database
+        .query_one(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "SELECT COUNT(*) AS count FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != ?",
+            [SCHEMA_VERSION_TABLE.into()],
+        ))
+        .await?;
+
+    Ok(match row {
+        Some(row) => row.try_get::<i64>("", "count")? > 0,
+        None => false,
+    })
+}
+
+/// Copy the database next to it as `<database>.v<version>.bak`,
+/// the previous backup of the same version is replaced.
+async fn backup_database(
+    database: &DatabaseConnection,
+    database_file: &Path,
+    version: u32,
+) -> Result<()> {
+    if !database_file.is_file() {
+        return Ok(());
+    }
+
+    let mut backup_file = database_file.as_os_str().to_owned();
+    backup_file.push(format!(".v{version}.bak"));
+    let backup_file = PathBuf::from(backup_file);
+    if backup_file.exists() {
+        std::fs::remove_file(&backup_file)?;
+    }
+
+    debug!("Back up database to: {}", backup_file.display());
+    database
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "VACUUM INTO ?",
+            [backup_file.to_string_lossy().into_owned().into()],
+        ))
+        .await?;
+    Ok(())
+}
+
+/// Whether the table already has the column
+pub async fn has_column<C: ConnectionTrait>(
+    database: &C,
+    table: &str,
+    column: &str,
+) -> Result<bool> {
+    let rows = database
+        .query_all(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "SELECT name FROM pragma_table_info(?)",
+            [table.into()],
+        ))
+        .await?;
+
+    for row in rows {
+        if row.try_get::<String>("", "name")? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Migrations
+
+/// 1: The tables and indexes, a no-op for the databases before migrations
+fn create_tables(txn: &DatabaseTransaction) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        execute_statements(
+            txn,
+            &[
+                create_table_statement(GenshinGachaRecordEntity),
+                create_table_statement(StarRailGachaRecordEntity),
+                create_table_statement(WutheringWavesGachaRecordEntity),
+                create_table_statement(ZenlessZoneZeroGachaRecordEntity),
+                create_table_statement(AccountEntity),
+                create_table_statement(GachaCheckpointEntity),
+            ],
+        )
+        .await?;
+
+        // Account: facet + uid constraint
+        let account_facet_index = Index::create()
+            .name(&format!(
+                "idx-{}-{}-{}",
+                AccountEntity.to_string(),
+                AccountColumn::Facet.to_string(),
+                AccountColumn::Uid.to_string()
+            ))
+            .table(AccountEntity)
+            .col(AccountColumn::Facet)
+            .col(AccountColumn::Uid)
+            .unique()
+            .if_not_exists()
+            .to_owned();
+
+        // Checkpoint: facet + uid + gacha type constraint
+        let checkpoint_gacha_type_index = Index::create()
+            .name(&format!(
+                "idx-{}-{}-{}-{}",
+                GachaCheckpointEntity.to_string(),
+                GachaCheckpointColumn::Facet.to_string(),
+                GachaCheckpointColumn::Uid.to_string(),
+                GachaCheckpointColumn::GachaType.to_string()
+            ))
+            .table(GachaCheckpointEntity)
+            .col(GachaCheckpointColumn::Facet)
+            .col(GachaCheckpointColumn::Uid)
+            .col(GachaCheckpointColumn::GachaType)
+            .unique()
+            .if_not_exists()
+            .to_owned();
+
+        let mut statements = create_index_statements(GenshinGachaRecordEntity);
+        statements.extend(create_index_statements(StarRailGachaRecordEntity));
+        statements.extend(create_index_statements(WutheringWavesGachaRecordEntity));
+        statements.extend(create_index_statements(ZenlessZoneZeroGachaRecordEntity));
+        statements.extend(create_index_statements(AccountEntity));
+        statements.push(account_facet_index);
+        statements.extend(create_index_statements(GachaCheckpointEntity));
+        statements.push(checkpoint_gacha_type_index);
+        execute_statements(txn, &statements).await?;
+        Ok(())
+    })
+}
+
+/// 2: Genshin Impact records before the item id, filled from the dictionary by name
+fn genshin_item_id(txn: &DatabaseTransaction) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        let table = GenshinGachaRecordEntity.to_string();
+        let column = GenshinGachaRecordColumn::ItemId.to_string();
+        if !has_column(txn, &table, &column).await? {
+            execute_statements(
+                txn,
+                &[Table::alter()
+                    .table(GenshinGachaRecordEntity)
+                    .add_column(
+                        ColumnDef::new(GenshinGachaRecordColumn::ItemId)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned()],
+            )
+            .await?;
+        }
+
+        // Only the columns of this version, later migrations may add more
+        let records = GenshinGachaRecordEntity::find()
+            .select_only()
+            .column(GenshinGachaRecordColumn::Id)
+            .column(GenshinGachaRecordColumn::Name)
+            .filter(GenshinGachaRecordColumn::ItemId.eq(""))
+            .into_tuple::<(String, String)>()
+            .all(txn)
+            .await?;
+        debug!("Genshin Impact records without item id: {}", records.len());
+
+        for (id, name) in records {
+            if let Some(entry) = dict::embedded::name(&AccountFacet::Genshin, "en-us", &name) {
+                GenshinGachaRecordEntity::update_many()
+                    .col_expr(
+                        GenshinGachaRecordColumn::ItemId,
+                        Expr::value(entry.item_id.to_string()),
+                    )
+                    .filter(GenshinGachaRecordColumn::Id.eq(id))
+                    .exec(txn)
+                    .await?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// 3: Natural key of the Wuthering Waves records, which have no id from the server.
+///   Each save inserted the records of a second with consecutive ids, so that one
+///   run of ids is one copy of that second. The duplicates of the overlapping
+///   saves are removed, then the position within the second is the ordinal.
+fn wutheringwaves_natural_key(txn: &DatabaseTransaction) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        let table = WutheringWavesGachaRecordEntity.to_string();
+        let column = WutheringWavesGachaRecordColumn::Ordinal.to_string();
+        if !has_column(txn, &table, &column).await? {
+            execute_statements(
+                txn,
+                &[Table::alter()
+                    .table(WutheringWavesGachaRecordEntity)
+                    .add_column(
+                        ColumnDef::new(WutheringWavesGachaRecordColumn::Ordinal)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned()],
+            )
+            .await?;
+        }
+
+        let records = WutheringWavesGachaRecordEntity::find()
+            .select_only()
+            .column(WutheringWavesGachaRecordColumn::Id)
+            .column(WutheringWavesGachaRecordColumn::Uid)
+            .column(WutheringWavesGachaRecordColumn::CardPoolType)
+            .column(WutheringWavesGachaRecordColumn::Time)
+            .column(WutheringWavesGachaRecordColumn::ResourceId)
+            .order_by_asc(WutheringWavesGachaRecordColumn::Id)
+            .into_tuple::<(i32, String, String, String, i32)>()
+            .all(txn)
+            .await?;
+
+        // (uid, card pool type, time) -> (id, resource id) in order of id
+        let mut seconds = BTreeMap::<(String, String, String), Vec<(i32, i32)>>::new();
+        for (id, uid, card_pool_type, time, resource_id) in records {
+            seconds
+                .entry((uid, card_pool_type, time))
+                .or_default()
+                .push((id, resource_id));
+        }
+
+        let mut duplicates = Vec::new();
+        for records in seconds.into_values() {
+            let (kept, removed) = dedup_wutheringwaves_second(&records);
+            duplicates.extend(removed);
+
+            for (ordinal, id) in kept.into_iter().enumerate() {
+                WutheringWavesGachaRecordEntity::update_many()
+                    .col_expr(
+                        WutheringWavesGachaRecordColumn::Ordinal,
+                        Expr::value(ordinal as u32),
+                    )
+                    .filter(WutheringWavesGachaRecordColumn::Id.eq(id))
+                    .exec(txn)
+                    .await?;
+            }
+        }
+
+        debug!("Wuthering Waves duplicate records: {}", duplicates.len());
+        for ids in duplicates.chunks(500) {
+            WutheringWavesGachaRecordEntity::delete_many()
+                .filter(WutheringWavesGachaRecordColumn::Id.is_in(ids.iter().copied()))
+                .exec(txn)
+                .await?;
+        }
+
+        execute_statements(
+            txn,
+            &[Index::create()
+                .name(&format!(
+                    "idx-{}-{}-{}-{}-{}-{}",
+                    table,
+                    WutheringWavesGachaRecordColumn::Uid.to_string(),
+                    WutheringWavesGachaRecordColumn::CardPoolType.to_string(),
+                    WutheringWavesGachaRecordColumn::Time.to_string(),
+                    WutheringWavesGachaRecordColumn::ResourceId.to_string(),
+                    column
+                ))
+                .table(WutheringWavesGachaRecordEntity)
+                .col(WutheringWavesGachaRecordColumn::Uid)
+                .col(WutheringWavesGachaRecordColumn::CardPoolType)
+                .col(WutheringWavesGachaRecordColumn::Time)
+                .col(WutheringWavesGachaRecordColumn::ResourceId)
+                .col(WutheringWavesGachaRecordColumn::Ordinal)
+                .unique()
+                .if_not_exists()
+                .to_owned()],
+        )
+        .await?;
+        Ok(())
+    })
+}
+
+/// Split the `(id, resource id)` of a second, in order of id, into the ids kept
+/// oldest first and the duplicate ids. Each run of consecutive ids was saved newest
+/// first, a run only keeps the resources beyond what the earlier runs already hold.
+fn dedup_wutheringwaves_second(records: &[(i32, i32)]) -> (Vec<i32>, Vec<i32>) {
+    let mut kept = Vec::new();
+    let mut removed = Vec::new();
+    let mut kept_counts = HashMap::<i32, usize>::new();
+
+    let mut start = 0;
+    while start < records.len() {
+        let mut end = start + 1;
+        while end < records.len() && records[end].0 == records[end - 1].0 + 1 {
+            end += 1;
+        }
+
+        let mut run_counts = HashMap::<i32, usize>::new();
+        for &(id, resource_id) in records[start..end].iter().rev() {
+            let run_count = run_counts.entry(resource_id).or_default();
+            *run_count += 1;
+            let kept_count = kept_counts.entry(resource_id).or_default();
+            if *run_count > *kept_count {
+                *kept_count += 1;
+                kept.push(id);
+            } else {
+                removed.push(id);
+            }
+        }
+        start = end;
+    }
+
+    (kept, removed)
+}
+
+/// 4: The time of every record in UTC, from the server time zone of its account
+fn record_timestamps(txn: &DatabaseTransaction) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        backfill_timestamps(
+            txn,
+            AccountFacet::Genshin,
+            GenshinGachaRecordEntity,
+            GenshinGachaRecordColumn::Uid,
+            GenshinGachaRecordColumn::Time,
+            GenshinGachaRecordColumn::Timestamp,
+        )
+        .await?;
+        backfill_timestamps(
+            txn,
+            AccountFacet::StarRail,
+            StarRailGachaRecordEntity,
+            StarRailGachaRecordColumn::Uid,
+            StarRailGachaRecordColumn::Time,
+            StarRailGachaRecordColumn::Timestamp,
+        )
+        .await?;
+        backfill_timestamps(
+            txn,
+            AccountFacet::WutheringWaves,
+            WutheringWavesGachaRecordEntity,
+            WutheringWavesGachaRecordColumn::Uid,
+            WutheringWavesGachaRecordColumn::Time,
+            WutheringWavesGachaRecordColumn::Timestamp,
+        )
+        .await?;
+        backfill_timestamps(
+            txn,
+            AccountFacet::ZenlessZoneZero,
+            ZenlessZoneZeroGachaRecordEntity,
+            ZenlessZoneZeroGachaRecordColumn::Uid,
+            ZenlessZoneZeroGachaRecordColumn::Time,
+            ZenlessZoneZeroGachaRecordColumn::Timestamp,
+        )
+        .await
+    })
+}
+
+async fn backfill_timestamps<E: EntityTrait>(
+    txn: &DatabaseTransaction,
+    facet: AccountFacet,
+    entity: E,
+    uid: E::Column,
+    time: E::Column,
+    timestamp: E::Column,
+) -> Result<()> {
+    let table = entity.to_string();
+    if !has_column(txn, &table, &timestamp.to_string()).await? {
+        execute_statements(
+            txn,
+            &[Table::alter()
+                .table(entity)
+                .add_column(ColumnDef::new(timestamp).timestamp_with_time_zone().null())
+                .to_owned()],
+        )
+        .await?;
+    }
+
+    let uids = E::find()
+        .select_only()
+        .column(uid)
+        .distinct()
+        .filter(timestamp.is_null())
+        .into_tuple::<String>()
+        .all(txn)
+        .await?;
+    debug!("{table} accounts without timestamp: {}", uids.len());
+
+    for uid_value in uids {
+        let account = AccountEntity::find()
+            .select_only()
+            .column(AccountColumn::GachaUrl)
+            .column(AccountColumn::Properties)
+            .filter(AccountColumn::Facet.eq(facet.clone()))
+            .filter(AccountColumn::Uid.eq(&uid_value))
+            .into_tuple::<(Option<String>, Option<AccountProperties>)>()
+            .one(txn)
+            .await?;
+        let (gacha_url, properties) = account.unwrap_or_default();
+        let time_zone = account_time_zone(
+            &facet,
+            &uid_value,
+            gacha_url.as_deref(),
+            properties.as_ref(),
+        );
+        stamp_gacha_records::<E, _>(txn, uid, time, timestamp, &uid_value, time_zone, true).await?;
+    }
+
+    execute_statements(
+        txn,
+        &[Index::create()
+            .name(&format!(
+                "idx-{}-{}-{}",
+                table,
+                uid.to_string(),
+                timestamp.to_string()
+            ))
+            .table(entity)
+            .col(uid)
+            .col(timestamp)
+            .if_not_exists()
+            .to_owned()],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Stamp the records of an account with their time in UTC, from the time zone of
+/// the server. Only the ones without a timestamp, unless restamping all of them.
+pub(super) async fn stamp_gacha_records<E: EntityTrait, C: ConnectionTrait>(
+    conn: &C,
+    uid: E::Column,
+    time: E::Column,
+    timestamp: E::Column,
+    uid_value: &str,
+    time_zone: i8,
+    unstamped_only: bool,
+) -> Result<()> {
+    // The records of an account at the same time share the timestamp
+    let mut select = E::find()
+        .select_only()
+        .column(time)
+        .distinct()
+        .filter(uid.eq(uid_value));
+    if unstamped_only {
+        select = select.filter(timestamp.is_null());
+    }
+    let times = select.into_tuple::<String>().all(conn).await?;
+
+    for time_value in times {
+        let Some(value) = parse_server_time(&time_value, time_zone) else {
+            continue;
+        };
+        E::update_many()
+            .col_expr(timestamp, Expr::value(value))
+            .filter(uid.eq(uid_value))
+            .filter(time.eq(time_value))
+            .exec(conn)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dedup_wutheringwaves_second;
+
+    #[test]
+    fn wutheringwaves_duplicates_are_found_by_runs_of_ids() {
+        // A 10-pull of two resources saved twice, then a newer pull of the same second
+        let records = [
+            (1, 100),
+            (2, 200),
+            (3, 100),
+            (7, 100),
+            (8, 200),
+            (9, 100),
+            (12, 300),
+        ];
+        let (kept, removed) = dedup_wutheringwaves_second(&records);
+        assert_eq!(kept, [3, 2, 1, 12]);
+        assert_eq!(removed, [9, 8, 7]);
+    }
+}
@@ -1,9 +1,14 @@
 pub mod entity_account;
+pub mod entity_gacha_checkpoint;
 pub mod entity_genshin_gacha_record;
 pub mod entity_starrail_gacha_record;
 pub mod entity_wutheringwaves_gacha_record;
+pub mod entity_zenlesszonezero_gacha_record;
 
 mod impl_storage;
+pub mod migrations;
+pub mod query;
+pub mod statistics;
 mod utilities;
 
 pub use impl_storage::*;
@@ -0,0 +1,179 @@
+extern crate sea_orm;
+extern crate serde;
+extern crate serde_json;
+extern crate time;
+
+use crate::error::{Error, Result};
+use sea_orm::sea_query::{Alias, Expr, Func, IntoColumnRef, SimpleExpr};
+use sea_orm::{Order, Value};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Gacha Record Query
+///   Records of an account, filtered, in order of timestamp and paginated by cursor.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GachaRecordQuery {
+    pub uid: String,
+    pub gacha_types: Option<Vec<String>>,
+    /// Unix timestamp, inclusive
+    pub start_time: Option<i64>,
+    /// Unix timestamp, exclusive
+    pub end_time: Option<i64>,
+    /// Rank type, or quality level of Wuthering Waves
+    pub ranks: Option<Vec<u32>>,
+    /// Item type, or resource type of Wuthering Waves
+    pub item_types: Option<Vec<String>>,
+    pub names: Option<Vec<String>>,
+    pub order: GachaRecordOrder,
+    /// The `next_cursor` of the previous page, none for the first page
+    pub cursor: Option<String>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GachaRecordOrder {
+    Ascending,
+    /// The latest records first
+    #[default]
+    Descending,
+}
+
+/// Gacha Record Page
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordPage<T> {
+    pub records: Vec<T>,
+    /// None when this is the last page
+    pub next_cursor: Option<String>,
+}
+
+impl GachaRecordQuery {
+    pub const DEFAULT_LIMIT: u64 = 100;
+    pub const MAX_LIMIT: u64 = 1000;
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+            .unwrap_or(Self::DEFAULT_LIMIT)
+            .clamp(1, Self::MAX_LIMIT)
+    }
+
+    pub fn sea_order(&self) -> Order {
+        match self.order {
+            GachaRecordOrder::Ascending => Order::Asc,
+            GachaRecordOrder::Descending => Order::Desc,
+        }
+    }
+
+    /// The records past the cursor, which holds the order keys of the last record:
+    /// its timestamp, then the integer keys telling the records of a second apart
+    pub fn cursor_condition(
+        &self,
+        timestamp: SimpleExpr,
+        keys: Vec<SimpleExpr>,
+    ) -> Result<Option<SimpleExpr>> {
+        let Some(cursor) = &self.cursor else {
+            return Ok(None);
+        };
+
+        let illegal = || Error::IllegalGachaRecordCursor(cursor.clone());
+        let values = decode_cursor(cursor)?;
+        let Some((timestamp_value, values)) = values.split_first() else {
+            return Err(illegal());
+        };
+        if values.len() != keys.len() {
+            return Err(illegal());
+        }
+        let timestamp_value =
+            OffsetDateTime::from_unix_timestamp(*timestamp_value).map_err(|_| illegal())?;
+
+        let keys = Expr::tuple(std::iter::once(timestamp).chain(keys));
+        let values = std::iter::once(Value::from(timestamp_value))
+            .chain(values.iter().copied().map(Value::from))
+            .map(SimpleExpr::Value)
+            .collect();
+        let values = SimpleExpr::Tuple(values);
+        Ok(Some(match self.order {
+            GachaRecordOrder::Ascending => keys.gt(values),
+            GachaRecordOrder::Descending => keys.lt(values),
+        }))
+    }
+}
+
+/// Order key of a column holding integers, ids are stored as strings of digits
+pub fn integer_order_key(column: impl IntoColumnRef) -> SimpleExpr {
+    Func::cast_as(Expr::col(column), Alias::new("INTEGER")).into()
+}
+
+/// The order keys of a record as an opaque cursor
+pub fn encode_cursor(timestamp: &OffsetDateTime, keys: Vec<Value>) -> Result<String> {
+    let keys = keys
+        .into_iter()
+        .map(|value| match value {
+            Value::String(Some(value)) => value
+                .parse::<i64>()
+                .map_err(|_| Error::IllegalGachaRecordCursor(*value)),
+            Value::Int(Some(value)) => Ok(value.into()),
+            Value::BigInt(Some(value)) => Ok(value),
+            Value::Unsigned(Some(value)) => Ok(value.into()),
+            value => Err(Error::IllegalGachaRecordCursor(format!("{value:?}"))),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let values = std::iter::once(timestamp.unix_timestamp())
+        .chain(keys)
+        .collect::<Vec<_>>();
+    Ok(serde_json::to_string(&values)?)
+}
+
+fn decode_cursor(cursor: &str) -> Result<Vec<i64>> {
+    serde_json::from_str(cursor).map_err(|_| Error::IllegalGachaRecordCursor(cursor.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_cursor, integer_order_key, GachaRecordQuery};
+    use crate::error::Error;
+    use sea_orm::sea_query::{Alias, Expr, Query, SqliteQueryBuilder};
+    use time::macros::datetime;
+
+    #[test]
+    fn query_continues_after_the_cursor() {
+        let timestamp = datetime!(2024-05-25 02:00:00 UTC);
+        let cursor = encode_cursor(&timestamp, vec!["1716602400000000003".into()]).unwrap();
+        assert_eq!(cursor, "[1716602400,1716602400000000003]");
+
+        let keys = || vec![integer_order_key(Alias::new("id"))];
+        let query = GachaRecordQuery {
+            cursor: Some(cursor),
+            ..Default::default()
+        };
+        let condition = query
+            .cursor_condition(Expr::col(Alias::new("timestamp")).into(), keys())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            Query::select()
+                .column(Alias::new("id"))
+                .from(Alias::new("records"))
+                .and_where(condition)
+                .to_string(SqliteQueryBuilder),
+            r#"SELECT "id" FROM "records" WHERE ("timestamp", CAST("id" AS INTEGER)) < ('2024-05-25 02:00:00.000000 +00:00', 1716602400000000003)"#
+        );
+        assert!(query
+            .cursor_condition(Expr::col(Alias::new("timestamp")).into(), vec![])
+            .is_err());
+
+        let query = GachaRecordQuery {
+            cursor: Some("not a cursor".to_owned()),
+            limit: Some(0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            query.cursor_condition(Expr::col(Alias::new("timestamp")).into(), keys()),
+            Err(Error::IllegalGachaRecordCursor(_))
+        ));
+        assert_eq!(query.limit(), 1);
+    }
+}
@@ -0,0 +1,60 @@
+extern crate sea_orm;
+extern crate serde;
+
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+/// Records of a gacha type and rank.
+///   The rank is the rank type, or the quality level of Wuthering Waves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromQueryResult)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordRankCount {
+    pub gacha_type: String,
+    pub rank: i64,
+    pub count: i64,
+}
+
+/// Records of an item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromQueryResult)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordItemCount {
+    pub item_id: String,
+    pub name: String,
+    pub item_type: String,
+    pub rank: i64,
+    pub count: i64,
+}
+
+/// Times of the first and last records, in the time of the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromQueryResult)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordTimeRange {
+    pub first_time: Option<String>,
+    pub last_time: Option<String>,
+    pub count: i64,
+}
+
+/// Records of a day `yyyy-MM-dd` or month `yyyy-MM`, in the time of the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromQueryResult)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaRecordPeriodCount {
+    pub period: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GachaRecordPeriod {
+    Day,
+    Month,
+}
+
+impl GachaRecordPeriod {
+    /// `strftime` format of the period of a record timestamp
+    pub fn strftime_format(&self) -> &'static str {
+        match self {
+            Self::Day => "%Y-%m-%d",
+            Self::Month => "%Y-%m",
+        }
+    }
+}
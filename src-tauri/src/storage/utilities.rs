@@ -1,7 +1,7 @@
 extern crate sea_orm;
 extern crate sqlx_core;
 
-use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, RuntimeErr, Schema, StatementBuilder};
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, EntityTrait, RuntimeErr, Schema, StatementBuilder};
 use sea_orm::sea_query::{IndexCreateStatement, TableCreateStatement};
 use sqlx_core::error::Error as SqlxError;
 
@@ -28,22 +28,22 @@ where E: EntityTrait {
     .collect::<Vec<_>>()
 }
 
-pub async fn execute_statement<S>(
-  database: &DatabaseConnection,
+pub async fn execute_statement<C, S>(
+  database: &C,
   statement: &S
 ) -> Result<(), DbErr>
-where S: StatementBuilder {
+where C: ConnectionTrait, S: StatementBuilder {
   database
     .execute(DbBackend::Sqlite.build(statement))
     .await?;
   Ok(())
 }
 
-pub async fn execute_statements<S>(
-  database: &DatabaseConnection,
+pub async fn execute_statements<C, S>(
+  database: &C,
   statements: &[S]
 ) -> Result<(), DbErr>
-where S: StatementBuilder {
+where C: ConnectionTrait, S: StatementBuilder {
   for statement in statements {
     execute_statement(database, statement).await?;
   }
@@ -387,6 +387,7 @@ const FacetGameDataDirExamples: Record<AccountFacet, string> = {
   [AccountFacet.Genshin]: "D:/Genshin Impact/Genshin Impact Game/YuanShen_Data",
   [AccountFacet.StarRail]: "D:/StarRail/Game/StarRail_Data",
   [AccountFacet.WutheringWaves]: "D:/Wuthering Waves/Wuthering Waves Game/",
+  [AccountFacet.ZenlessZoneZero]: "D:/ZenlessZoneZero/ZenlessZoneZero_Data",
 };
 
 function numericOnly(evt: React.KeyboardEvent<HTMLElement>) {
@@ -33,6 +33,7 @@ export default function GachaItemView({
   switch (facet) {
     case AccountFacet.Genshin:
     case AccountFacet.StarRail:
+    case AccountFacet.ZenlessZoneZero:
       return (
         <MihoyoItemView facet={facet} item={item as MihoyoRecord} size={size} />
       );
@@ -150,4 +150,5 @@ const KnownErrorIdentifiers: Record<string, string> = {
     "The UIGF or SRGF data UID to be imported does not match the current account!",
   UIGF_OR_SRGF_INVALID_FIELD:
     "There are invalid fields in the UIGF or SRGF data to be imported!",
+  TASK_CANCELLED: "Cancelled. The records already saved are kept.",
 };
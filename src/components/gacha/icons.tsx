@@ -16,6 +16,11 @@ const extensions = {
     character: ".webp",
     weapon: ".png",
   },
+  [AccountFacet.ZenlessZoneZero]: {
+    gameName: "zenlesszonezero",
+    character: ".webp",
+    weapon: ".webp",
+  },
 } as const;
 
 // TODO: this needs to be by ID ONLY
@@ -9,6 +9,7 @@ export default function GachaOverviewGrid() {
   switch (facet) {
     case AccountFacet.Genshin:
     case AccountFacet.StarRail:
+    case AccountFacet.ZenlessZoneZero:
       return <GachaOverviewGridMihoyo />;
     case AccountFacet.WutheringWaves:
       return <GachaOverviewGridKuro />;
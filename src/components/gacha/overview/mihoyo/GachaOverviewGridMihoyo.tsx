@@ -17,7 +17,15 @@ import {
 export default function GachaOverviewGridMihoyo() {
   const { facet, gachaRecords } = useGachaLayoutContext();
   const {
-    namedValues: { character, weapon, permanent, beginner },
+    namedValues: {
+      character,
+      weapon,
+      permanent,
+      beginner,
+      chronicled,
+      collaborationCharacter,
+      collaborationWeapon,
+    },
     aggregatedValues,
   } = gachaRecords as MihoyoRecords;
 
@@ -42,6 +50,17 @@ export default function GachaOverviewGridMihoyo() {
         // {/* </Grid> */}
       )}
 
+      {[chronicled, collaborationCharacter, collaborationWeapon].map(
+        (value) =>
+          !value ||
+          value.total === 0 || (
+            <Box key={value.category}>
+              <GachaOverviewCard facet={facet} value={value} />
+              <Divider />
+            </Box>
+          )
+      )}
+
       {permanent.total === 0 || (
         // <Grid xs={6} item>
         <Box>
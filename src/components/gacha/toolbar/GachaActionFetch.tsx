@@ -1,4 +1,5 @@
 import React from "react";
+import _ from "lodash";
 import { useImmer } from "use-immer";
 import { resolveCurrency } from "@/interfaces/account";
 import {
@@ -33,6 +34,9 @@ export default function GachaActionFetch() {
 
   const [currentCategory, setCurrentCategory] = React.useState("");
   const [currentTotal, setCurrentTotal] = React.useState(0);
+  const [currentInserted, setCurrentInserted] = React.useState<number | null>(
+    null
+  );
 
   const handleFetch = React.useCallback(async () => {
     if (!selectedAccount.gachaUrl) {
@@ -94,22 +98,29 @@ export default function GachaActionFetch() {
   ]);
 
   React.useEffect(() => {
-    if (
-      currentFragment === "idle" ||
-      currentFragment === "sleeping" ||
-      currentFragment === "finished"
-    ) {
+    if (currentFragment === "idle") {
       resetFetchState();
     } else if ("ready" in currentFragment) {
-      const gachaType = currentFragment.ready;
+      const { gachaType } = currentFragment.ready;
       const category = gachaRecords.gachaTypeToCategoryMappings[gachaType];
-      const categoryTitle = gachaRecords.namedValues[category].categoryTitle;
-      setCurrentCategory(categoryTitle);
+      const categoryTitle = gachaRecords.namedValues[category]?.categoryTitle;
+      setCurrentCategory(categoryTitle ?? "");
     } else if ("pagination" in currentFragment) {
       // const pagination = currentFragment.pagination;
     } else if ("data" in currentFragment) {
-      const data = currentFragment.data;
-      setCurrentTotal(currentTotal + data.length);
+      setCurrentTotal(currentFragment.data.total);
+    } else if ("saved" in currentFragment) {
+      setCurrentInserted(currentFragment.saved.totalInserted);
+    } else if ("sleeping" in currentFragment || "retrying" in currentFragment) {
+      // Keep the current category while waiting
+    } else if ("checkpoint" in currentFragment) {
+      // Persisted by the backend, nothing to show
+    } else if (
+      "finished" in currentFragment ||
+      "cancelled" in currentFragment ||
+      "summary" in currentFragment
+    ) {
+      resetFetchState();
     } else {
       // Should never reach here
       resetFetchState();
@@ -118,6 +129,7 @@ export default function GachaActionFetch() {
 
   React.useEffect(() => {
     setCurrentTotal(0);
+    setCurrentInserted(null);
   }, [currentCategory]);
 
   const resetFetchState = () => {
@@ -128,7 +140,10 @@ export default function GachaActionFetch() {
     return (
       <>
         <p>{currentCategory}</p>
-        <p>Located {currentTotal} new records</p>
+        <p>
+          Located {currentTotal} records
+          {currentInserted !== null && `, ${currentInserted} new`}
+        </p>
       </>
     );
   };
@@ -139,16 +154,29 @@ export default function GachaActionFetch() {
   ) => {
     if (fragment === "idle") {
       return "idle...";
-    } else if (fragment === "sleeping") {
-      return "Waiting...";
-    } else if (fragment === "finished") {
+    } else if ("sleeping" in fragment) {
+      const seconds = Math.ceil(fragment.sleeping.delayMillis / 1000);
+      return `Waiting ${seconds}s...`;
+    } else if ("finished" in fragment) {
       return "Finish";
+    } else if ("cancelled" in fragment) {
+      return "Cancelled";
+    } else if ("summary" in fragment) {
+      const { gachaTypes } = fragment.summary;
+      const fetched = _.sumBy(gachaTypes, "fetched");
+      const inserted = _.sumBy(gachaTypes, (v) => v.inserted ?? 0);
+      return `Finish: ${fetched} records, ${inserted} new`;
     } else if ("ready" in fragment) {
       return loadingString();
     } else if ("pagination" in fragment) {
       return loadingString();
-    } else if ("data" in fragment) {
+    } else if ("data" in fragment || "saved" in fragment) {
+      return loadingString();
+    } else if ("checkpoint" in fragment) {
       return loadingString();
+    } else if ("retrying" in fragment) {
+      const seconds = Math.ceil(fragment.retrying.delayMillis / 1000);
+      return `Retrying in ${seconds}s...`;
     } else {
       // Should never reach here
       return `Unknown fragment: ${JSON.stringify(fragment)}`;
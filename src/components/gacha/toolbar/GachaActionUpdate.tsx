@@ -1,4 +1,5 @@
 import React from "react";
+import _ from "lodash";
 import { useImmer } from "use-immer";
 import { AccountFacet, resolveCurrency } from "@/interfaces/account";
 import {
@@ -27,16 +28,14 @@ import MenuList from "@mui/material/MenuList";
 import GachaMenuItemImport from "./GachaMenuItemImport";
 import GachaMenuItemExport from "./GachaMenuItemExport";
 import PluginGacha from "@/utilities/plugin-gacha";
-import {
-  KnownWutheringWavesGachaTypes,
-  NamedKuroRecords,
-} from "@/hooks/useKuroRecordsQuery";
+import { NamedKuroRecords } from "@/hooks/useKuroRecordsQuery";
 import {
   KnownGenshinGachaTypes,
   KnownStarRailGachaTypes,
+  KnownZenlessZoneZeroGachaTypes,
   NamedMihoyoRecords,
 } from "@/hooks/useMihoyoRecordsQuery";
-import { GachaCategory } from "@/hooks/constants";
+import { NamedGachaCategories } from "@/hooks/constants";
 
 export default function GachaActionUpdate() {
   const { facet, selectedAccount, gachaRecords, alert } =
@@ -54,6 +53,9 @@ export default function GachaActionUpdate() {
 
   const [currentCategory, setCurrentCategory] = React.useState("");
   const [currentTotal, setCurrentTotal] = React.useState(0);
+  const [currentInserted, setCurrentInserted] = React.useState<number | null>(
+    null
+  );
   const [menuOpen, setMenuOpen] = React.useState(false);
 
   const handleMenuToggle = () => {
@@ -74,9 +76,9 @@ export default function GachaActionUpdate() {
   const getTheStuff = (
     facet: AccountFacet,
     namedValues:
-      | Record<GachaCategory, NamedKuroRecords>
-      | Record<GachaCategory, NamedMihoyoRecords>
-  ): Record<string, string | null> => {
+      | NamedGachaCategories<NamedKuroRecords>
+      | NamedGachaCategories<NamedMihoyoRecords>
+  ): Record<string, string | null> | undefined => {
     let gachaTypes = null;
     switch (facet) {
       case AccountFacet.Genshin:
@@ -86,20 +88,14 @@ export default function GachaActionUpdate() {
         gachaTypes = KnownStarRailGachaTypes;
         break;
       case AccountFacet.WutheringWaves:
-        gachaTypes = KnownWutheringWavesGachaTypes;
+        // Every pool, the backend derives their last times from storage
+        return undefined;
+      case AccountFacet.ZenlessZoneZero:
+        gachaTypes = KnownZenlessZoneZeroGachaTypes;
     }
     return Object.entries(gachaTypes).reduce(
       (mappings, [gachaType, category]) => {
-        switch (facet) {
-          case AccountFacet.Genshin:
-          case AccountFacet.StarRail:
-            mappings[gachaType] = namedValues[category].lastEndId || null;
-            break;
-          case AccountFacet.WutheringWaves:
-            mappings[gachaType] = namedValues[category].lastTime || null;
-            break;
-        }
-
+        mappings[gachaType] = namedValues[category]?.lastEndId || null;
         return mappings;
       },
       {} as Record<string, string | null>
@@ -180,22 +176,29 @@ export default function GachaActionUpdate() {
   ]);
 
   React.useEffect(() => {
-    if (
-      currentFragment === "idle" ||
-      currentFragment === "sleeping" ||
-      currentFragment === "finished"
-    ) {
+    if (currentFragment === "idle") {
       resetFetchState();
     } else if ("ready" in currentFragment) {
-      const gachaType = currentFragment.ready;
+      const { gachaType } = currentFragment.ready;
       const category = gachaRecords.gachaTypeToCategoryMappings[gachaType];
-      const categoryTitle = gachaRecords.namedValues[category].categoryTitle;
-      setCurrentCategory(categoryTitle);
+      const categoryTitle = gachaRecords.namedValues[category]?.categoryTitle;
+      setCurrentCategory(categoryTitle ?? "");
     } else if ("pagination" in currentFragment) {
       // const pagination = currentFragment.pagination;
     } else if ("data" in currentFragment) {
-      const data = currentFragment.data;
-      setCurrentTotal(currentTotal + data.length);
+      setCurrentTotal(currentFragment.data.total);
+    } else if ("saved" in currentFragment) {
+      setCurrentInserted(currentFragment.saved.totalInserted);
+    } else if ("sleeping" in currentFragment || "retrying" in currentFragment) {
+      // Keep the current category while waiting
+    } else if ("checkpoint" in currentFragment) {
+      // Persisted by the backend, nothing to show
+    } else if (
+      "finished" in currentFragment ||
+      "cancelled" in currentFragment ||
+      "summary" in currentFragment
+    ) {
+      resetFetchState();
     } else {
       // Should never reach here
       resetFetchState();
@@ -204,6 +207,7 @@ export default function GachaActionUpdate() {
 
   React.useEffect(() => {
     setCurrentTotal(0);
+    setCurrentInserted(null);
   }, [currentCategory]);
 
   const resetFetchState = () => {
@@ -214,7 +218,10 @@ export default function GachaActionUpdate() {
     return (
       <>
         <p>{currentCategory}</p>
-        <p>Located {currentTotal} new records</p>
+        <p>
+          Located {currentTotal} records
+          {currentInserted !== null && `, ${currentInserted} new`}
+        </p>
       </>
     );
   };
@@ -225,16 +232,29 @@ export default function GachaActionUpdate() {
   ) => {
     if (fragment === "idle") {
       return "idle...";
-    } else if (fragment === "sleeping") {
-      return "Waiting...";
-    } else if (fragment === "finished") {
+    } else if ("sleeping" in fragment) {
+      const seconds = Math.ceil(fragment.sleeping.delayMillis / 1000);
+      return `Waiting ${seconds}s...`;
+    } else if ("finished" in fragment) {
       return "Finish";
+    } else if ("cancelled" in fragment) {
+      return "Cancelled";
+    } else if ("summary" in fragment) {
+      const { gachaTypes } = fragment.summary;
+      const fetched = _.sumBy(gachaTypes, "fetched");
+      const inserted = _.sumBy(gachaTypes, (v) => v.inserted ?? 0);
+      return `Finish: ${fetched} records, ${inserted} new`;
     } else if ("ready" in fragment) {
       return loadingString();
     } else if ("pagination" in fragment) {
       return loadingString();
-    } else if ("data" in fragment) {
+    } else if ("data" in fragment || "saved" in fragment) {
+      return loadingString();
+    } else if ("checkpoint" in fragment) {
       return loadingString();
+    } else if ("retrying" in fragment) {
+      const seconds = Math.ceil(fragment.retrying.delayMillis / 1000);
+      return `Retrying in ${seconds}s...`;
     } else {
       // Should never reach here
       return `Unknown fragment: ${JSON.stringify(fragment)}`;
@@ -280,6 +300,10 @@ export default function GachaActionUpdate() {
                 <MenuList id="split-button-menu" autoFocusItem>
                   <GachaMenuItemImport />
                   <GachaMenuItemExport />
+                  {(facet === AccountFacet.Genshin ||
+                    facet === AccountFacet.StarRail) && (
+                    <GachaMenuItemExport format="uigf4" />
+                  )}
                 </MenuList>
               </ClickAwayListener>
             </Paper>
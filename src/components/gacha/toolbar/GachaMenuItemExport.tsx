@@ -2,11 +2,16 @@ import React from "react";
 import { dialog } from "@tauri-apps/api";
 import { resolveCurrency } from "@/interfaces/account";
 import { useGachaLayoutContext } from "@/components/gacha/GachaLayoutContext";
-import PluginGacha from "@/utilities/plugin-gacha";
+import PluginGacha, { ExportFormat } from "@/utilities/plugin-gacha";
 import MenuItem from "@mui/material/MenuItem";
 import SaveAltIcon from "@mui/icons-material/SaveAlt";
 
-export default function GachaMenuItemExport() {
+export interface GachaMenuItemExportProps {
+  format?: ExportFormat;
+}
+
+export default function GachaMenuItemExport(props: GachaMenuItemExportProps) {
+  const { format } = props;
   const { facet, selectedAccount, alert } = useGachaLayoutContext();
   const { action } = resolveCurrency(facet);
   const [busy, setBusy] = React.useState(false);
@@ -23,7 +28,8 @@ export default function GachaMenuItemExport() {
         const exportFile = await PluginGacha.exportGachaRecords(
           selectedAccount.facet,
           selectedAccount.uid,
-          directory
+          directory,
+          format
         );
         alert(
           null,
@@ -35,16 +41,16 @@ export default function GachaMenuItemExport() {
     } finally {
       setBusy(false);
     }
-  }, [selectedAccount, alert, action, setBusy]);
+  }, [selectedAccount, format, alert, action, setBusy]);
 
   return (
     <MenuItem
-      key="GachaActionExport"
+      key={`GachaActionExport-${format ?? "classic"}`}
       onClick={handleExportGachaRecords}
       disabled={busy}
     >
       <SaveAltIcon />
-      &nbsp;Export
+      &nbsp;{format === "uigf4" ? "Export UIGF v4" : "Export"}
     </MenuItem>
   );
 }
@@ -31,7 +31,10 @@ export default function GachaMenuItemImport() {
               [AccountFacet.Genshin]:
                 "UIGF (Unified Standardized GenshinData Format)",
               [AccountFacet.StarRail]: "SRGF (Star Rail GachaLog Format)",
-              [AccountFacet.WutheringWaves]: "",
+              [AccountFacet.WutheringWaves]:
+                "WWPF (Wuthering Waves Proprietary Format)",
+              [AccountFacet.ZenlessZoneZero]:
+                "UIGF (Uniform Interchangeable GachaLog Format)",
             }[selectedAccount.facet],
           },
         ],
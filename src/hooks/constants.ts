@@ -5,25 +5,42 @@ export const GachaCategory = {
   Permanent: "permanent",
   Character: "character",
   Weapon: "weapon",
+  Chronicled: "chronicled", // Genshin Impact only
+  CollaborationCharacter: "collaborationCharacter", // Honkai: Star Rail only
+  CollaborationWeapon: "collaborationWeapon", // Honkai: Star Rail only
 } as const;
 
 export type GachaCategory = (typeof GachaCategory)[keyof typeof GachaCategory];
 
+// Categories every game has, the rest only exist in some games
+export type CommonGachaCategory = Exclude<
+  GachaCategory,
+  | typeof GachaCategory.Chronicled
+  | typeof GachaCategory.CollaborationCharacter
+  | typeof GachaCategory.CollaborationWeapon
+>;
+
+export type NamedGachaCategories<T> = Record<CommonGachaCategory, T> &
+  Partial<Record<GachaCategory, T>>;
+
 export const KnownCategoryTitles: Record<
   AccountFacet,
-  Record<GachaCategory, string>
+  NamedGachaCategories<string>
 > = {
   [AccountFacet.Genshin]: {
     [GachaCategory.Character]: "Character",
     [GachaCategory.Weapon]: "Weapon",
     [GachaCategory.Permanent]: "Standard",
     [GachaCategory.Beginner]: "Beginner",
+    [GachaCategory.Chronicled]: "Chronicled",
   },
   [AccountFacet.StarRail]: {
     [GachaCategory.Character]: "Character",
     [GachaCategory.Weapon]: "Light Cone",
     [GachaCategory.Permanent]: "Regular",
     [GachaCategory.Beginner]: "Starter",
+    [GachaCategory.CollaborationCharacter]: "Collaboration Character",
+    [GachaCategory.CollaborationWeapon]: "Collaboration Light Cone",
   },
   [AccountFacet.WutheringWaves]: {
     [GachaCategory.Character]: "Featured Resonator",
@@ -31,4 +48,10 @@ export const KnownCategoryTitles: Record<
     [GachaCategory.Permanent]: "Standard Resonator",
     [GachaCategory.Beginner]: "Beginner",
   },
+  [AccountFacet.ZenlessZoneZero]: {
+    [GachaCategory.Character]: "Exclusive Channel",
+    [GachaCategory.Weapon]: "W-Engine Channel",
+    [GachaCategory.Permanent]: "Stable Channel",
+    [GachaCategory.Beginner]: "Bangboo Channel",
+  },
 };
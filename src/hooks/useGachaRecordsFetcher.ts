@@ -1,15 +1,62 @@
 import React from "react";
 import { event } from "@tauri-apps/api";
 import { useImmer } from "use-immer";
-import { GenshinGachaRecord, StarRailGachaRecord } from "@/interfaces/gacha";
+import {
+  GenshinGachaRecord,
+  StarRailGachaRecord,
+  WutheringWavesGachaRecord,
+  ZenlessZoneZeroGachaRecord,
+} from "@/interfaces/gacha";
 import PluginGacha from "@/utilities/plugin-gacha";
 
+// See: src-tauri/src/gacha/gacha.rs
+//   Every fragment of a gacha type carries it, the counts are cumulative.
+export interface PullSummary {
+  gachaTypes: Array<{
+    gachaType: string;
+    fetched: number;
+    inserted: number | null; // null when not saving to storage
+    duplicates: number | null;
+  }>;
+}
+
 type Fragment =
-  | "sleeping"
-  | { ready: string }
-  | { pagination: number }
-  | { data: Array<GenshinGachaRecord | StarRailGachaRecord> }
-  | "finished";
+  | { sleeping: { gachaType: string; delayMillis: number } }
+  | { ready: { gachaType: string } }
+  | { pagination: { gachaType: string; page: number } }
+  | {
+      data: {
+        gachaType: string;
+        records: Array<
+          | GenshinGachaRecord
+          | StarRailGachaRecord
+          | ZenlessZoneZeroGachaRecord
+          | WutheringWavesGachaRecord
+        >;
+        total: number;
+      };
+    }
+  | {
+      saved: {
+        gachaType: string;
+        inserted: number;
+        duplicates: number;
+        totalInserted: number;
+        totalDuplicates: number;
+      };
+    }
+  | { retrying: { gachaType: string; attempt: number; delayMillis: number } }
+  | {
+      checkpoint: {
+        gachaType: string;
+        endId: string;
+        floorEndId: string | null;
+        reachedEnd: boolean;
+      };
+    }
+  | { finished: { gachaType: string; total: number } }
+  | { cancelled: { gachaType: string } }
+  | { summary: PullSummary };
 
 export default function useGachaRecordsFetcher() {
   const [{ fragments, current }, produceState] = useImmer<{
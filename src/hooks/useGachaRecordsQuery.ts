@@ -22,6 +22,7 @@ export function useGachaRecordsQuery(
   switch (facet) {
     case AccountFacet.Genshin:
     case AccountFacet.StarRail:
+    case AccountFacet.ZenlessZoneZero:
       return useMihoyoRecordsQuery(facet, uid);
     case AccountFacet.WutheringWaves:
       return useKuroRecordsQuery(facet, uid);
@@ -33,6 +34,7 @@ export function useRefetchGachaRecordsFn(facet: AccountFacet) {
   switch (facet) {
     case AccountFacet.Genshin:
     case AccountFacet.StarRail:
+    case AccountFacet.ZenlessZoneZero:
       return useRefetchMihoyoRecordsFn();
     case AccountFacet.WutheringWaves:
       return useRefetchKuroRecordsFn();
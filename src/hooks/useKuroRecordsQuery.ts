@@ -11,7 +11,11 @@ import { AccountFacet, Account, resolveCurrency } from "@/interfaces/account";
 import { WutheringWavesGachaRecord } from "@/interfaces/gacha";
 import PluginStorage from "@/utilities/plugin-storage";
 import * as _ from "lodash";
-import { GachaCategory, KnownCategoryTitles } from "./constants";
+import {
+  GachaCategory,
+  KnownCategoryTitles,
+  NamedGachaCategories,
+} from "./constants";
 
 export type KuroRecord = WutheringWavesGachaRecord;
 
@@ -40,7 +44,7 @@ export interface KuroRecords {
     NamedKuroRecords["category"]
   >;
   readonly values: Partial<Record<KuroRecord["gacha_type"], KuroRecord[]>>;
-  readonly namedValues: Record<NamedKuroRecords["category"], NamedKuroRecords>;
+  readonly namedValues: NamedGachaCategories<NamedKuroRecords>;
   readonly aggregatedValues: Omit<
     NamedKuroRecords,
     "category" | "categoryTitle" | "gachaType" | "lastEndId"
@@ -8,12 +8,23 @@ import {
   useQueryClient,
 } from "@tanstack/react-query";
 import { AccountFacet, Account, resolveCurrency } from "@/interfaces/account";
-import { GenshinGachaRecord, StarRailGachaRecord } from "@/interfaces/gacha";
+import {
+  GenshinGachaRecord,
+  StarRailGachaRecord,
+  ZenlessZoneZeroGachaRecord,
+} from "@/interfaces/gacha";
 import PluginStorage from "@/utilities/plugin-storage";
 import _ from "lodash";
-import { GachaCategory, KnownCategoryTitles } from "./constants";
+import {
+  GachaCategory,
+  KnownCategoryTitles,
+  NamedGachaCategories,
+} from "./constants";
 
-export type MihoyoRecord = GenshinGachaRecord | StarRailGachaRecord;
+export type MihoyoRecord =
+  | GenshinGachaRecord
+  | StarRailGachaRecord
+  | ZenlessZoneZeroGachaRecord;
 
 type MihoyoFilter = (record: MihoyoRecord) => boolean;
 
@@ -25,6 +36,18 @@ export const KnownStarRailGachaTypes: Record<
   1: GachaCategory.Permanent,
   11: GachaCategory.Character,
   12: GachaCategory.Weapon,
+  21: GachaCategory.CollaborationCharacter, // own pity track
+  22: GachaCategory.CollaborationWeapon, // own pity track
+};
+
+export const KnownZenlessZoneZeroGachaTypes: Record<
+  ZenlessZoneZeroGachaRecord["gacha_type"],
+  NamedMihoyoRecords["category"]
+> = {
+  5: GachaCategory.Beginner, // Bangboo
+  1: GachaCategory.Permanent,
+  2: GachaCategory.Character,
+  3: GachaCategory.Weapon,
 };
 
 export const KnownGenshinGachaTypes: Record<
@@ -35,6 +58,7 @@ export const KnownGenshinGachaTypes: Record<
   200: GachaCategory.Permanent,
   301: GachaCategory.Character, // include 400
   302: GachaCategory.Weapon,
+  500: GachaCategory.Chronicled, // own pity track
 };
 
 // Computed Gacha Records
@@ -47,10 +71,7 @@ export interface MihoyoRecords {
     NamedMihoyoRecords["category"]
   >;
   readonly values: Partial<Record<MihoyoRecord["gacha_type"], MihoyoRecord[]>>;
-  readonly namedValues: Record<
-    NamedMihoyoRecords["category"],
-    NamedMihoyoRecords
-  >;
+  readonly namedValues: NamedGachaCategories<NamedMihoyoRecords>;
   readonly aggregatedValues: Omit<
     NamedMihoyoRecords,
     "category" | "categoryTitle" | "gachaType" | "lastEndId"
@@ -220,6 +241,8 @@ function computeNamedMihoyoRecords(
   const categories =
     facet === AccountFacet.Genshin
       ? KnownGenshinGachaTypes
+      : facet === AccountFacet.ZenlessZoneZero
+      ? KnownZenlessZoneZeroGachaTypes
       : KnownStarRailGachaTypes;
   const { action: currencyAction } = resolveCurrency(facet);
 
@@ -273,34 +296,21 @@ function computeAggregatedMihoyoRecords(
   const total = data.length;
   const firstTime = data[0]?.time;
   const lastTime = data[total - 1]?.time;
-  const {
-    [GachaCategory.Beginner]: newbie,
-    [GachaCategory.Permanent]: permanent,
-    [GachaCategory.Character]: character,
-    [GachaCategory.Weapon]: weapon,
-  } = namedValues;
-
-  const blueSum =
-    newbie.metadata.blue.sum +
-    permanent.metadata.blue.sum +
-    character.metadata.blue.sum +
-    weapon.metadata.blue.sum;
+  // Every category of the game, including the ones only some games have
+  const named: NamedMihoyoRecords[] = Object.values(namedValues);
+
+  const blueSum = _.sumBy(named, (value) => value.metadata.blue.sum);
   const blueSumPercentage =
     blueSum > 0 ? Math.round((blueSum / total) * 10000) / 100 : 0;
   const blueValues = data.filter(isRankTypeOfBlue);
 
-  const purpleSum =
-    newbie.metadata.purple.sum +
-    permanent.metadata.purple.sum +
-    character.metadata.purple.sum +
-    weapon.metadata.purple.sum;
+  const purpleSum = _.sumBy(named, (value) => value.metadata.purple.sum);
   const purpleSumPercentage =
     purpleSum > 0 ? Math.round((purpleSum / total) * 10000) / 100 : 0;
-  const purpleValues = Array.from(newbie.metadata.purple.values)
-    .concat(Array.from(permanent.metadata.purple.values))
-    .concat(Array.from(character.metadata.purple.values))
-    .concat(Array.from(weapon.metadata.purple.values))
-    .sort(sortMihoyoRecordById);
+  const purpleValues = _.flatMap(
+    named,
+    (value) => value.metadata.purple.values
+  ).sort(sortMihoyoRecordById);
 
   const { purpleUsedPitySum } = purpleValues.reduce(
     (acc, record) => {
@@ -317,18 +327,13 @@ function computeAggregatedMihoyoRecords(
       ? Math.ceil(Math.round((purpleUsedPitySum / purpleSum) * 100) / 100)
       : 0;
 
-  const goldenSum =
-    newbie.metadata.golden.sum +
-    permanent.metadata.golden.sum +
-    character.metadata.golden.sum +
-    weapon.metadata.golden.sum;
+  const goldenSum = _.sumBy(named, (value) => value.metadata.golden.sum);
   const goldenSumPercentage =
     goldenSum > 0 ? Math.round((goldenSum / total) * 10000) / 100 : 0;
-  const goldenValues = Array.from(newbie.metadata.golden.values)
-    .concat(Array.from(permanent.metadata.golden.values))
-    .concat(Array.from(character.metadata.golden.values))
-    .concat(Array.from(weapon.metadata.golden.values))
-    .sort(sortMihoyoRecordById);
+  const goldenValues = _.flatMap(
+    named,
+    (value) => value.metadata.golden.values
+  ).sort(sortMihoyoRecordById);
 
   const { goldenSumRestricted, goldenUsedPitySum } = goldenValues.reduce(
     (acc, record) => {
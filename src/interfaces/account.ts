@@ -6,11 +6,15 @@ export enum AccountFacet {
   Genshin = "genshin",
   StarRail = "starrail",
   WutheringWaves = "wutheringwaves",
+  ZenlessZoneZero = "zenlesszonezero",
 }
 
 export interface KnownAccountProperties {
   displayName?: string | null;
   lastGachaUpdated?: string | null;
+  preferredLanguage?: string | null; // e.g. en-us, zh-cn
+  timeZone?: number | null; // UTC offset in hours of the server
+  region?: string | null; // Wuthering Waves: global, cn
   [key: string]: unknown;
 }
 
@@ -37,6 +41,8 @@ function defaultAccountName(facet: AccountFacet | undefined): string {
       return "Trailblazer";
     case AccountFacet.WutheringWaves:
       return "Rover";
+    case AccountFacet.ZenlessZoneZero:
+      return "Proxy";
   }
 
   return "NULL";
@@ -68,6 +74,11 @@ export function resolveCurrency(facet: AccountFacet): {
         currency: "Astrite",
         action: { singular: "Convene", plural: "Convenes" },
       };
+    case AccountFacet.ZenlessZoneZero:
+      return {
+        currency: "Polychrome",
+        action: { singular: "Signal Search", plural: "Signal Searches" },
+      };
     default:
       throw new Error(`Unknown account facet: ${facet}`);
   }
@@ -82,6 +93,8 @@ export function resolveFacetName(facet: AccountFacet): string {
       return "Honkai: Star Rail";
     case AccountFacet.WutheringWaves:
       return "Wuthering Waves";
+    case AccountFacet.ZenlessZoneZero:
+      return "Zenless Zone Zero";
     default:
       throw new Error(`Unknown account facet: ${facet}`);
   }
@@ -51,7 +51,7 @@ export interface StarRailGachaRecord {
   id: string;
   uid: string;
   gacha_id: string;
-  gacha_type: string; // 1 | 2 | 11 | 12
+  gacha_type: string; // 1 | 2 | 11 | 12 | 21 | 22
   item_id: string;
   count: string; // always 1
   time: string;
@@ -61,6 +61,21 @@ export interface StarRailGachaRecord {
   rank_type: string; // 3 | 4 | 5
 }
 
+// See: src-tauri/src/gacha/hoyoverse/zenlesszonezero.rs
+export interface ZenlessZoneZeroGachaRecord {
+  id: string;
+  uid: string;
+  gacha_id: string;
+  gacha_type: string; // 1 | 2 | 3 | 5
+  item_id: string;
+  count: string; // always 1
+  time: string;
+  name: string;
+  lang: string; // en-us
+  item_type: string; // Agents | W-Engines | Bangboo
+  rank_type: string; // 2 | 3 | 4
+}
+
 // See: src-tauri/src/gacha/impl_wutheringwaves.rs
 export interface WutheringWavesGachaRecord {
   id: number;
@@ -73,4 +88,5 @@ export interface WutheringWavesGachaRecord {
   name: string;
   count: number;
   time: string;
+  ordinal: number;
 }
@@ -1,5 +1,9 @@
 import { Account, AccountFacet } from "@/interfaces/account";
-import { GenshinGachaRecord, StarRailGachaRecord } from "@/interfaces/gacha";
+import {
+  GenshinGachaRecord,
+  StarRailGachaRecord,
+  ZenlessZoneZeroGachaRecord,
+} from "@/interfaces/gacha";
 import invoke from "@/utilities/invoke";
 
 export async function findGameDataDirectories(
@@ -16,19 +20,60 @@ export async function findGachaUrl(
   return invoke("plugin:gacha|find_gacha_url", { facet, uid, gameDataDir });
 }
 
+// See: src-tauri/src/gacha/diff.rs
+export interface GachaRecordPullDiff {
+  gachaTypes: Array<{
+    gachaType: string;
+    new: Array<unknown>; // fetched and not stored yet
+    stored: Array<unknown>; // fetched and already stored
+    missing: Array<unknown>; // stored and missing from the server window
+  }>;
+}
+
+export interface GachaRecordPullRepair {
+  gachaTypes: Array<{
+    gachaType: string;
+    new: number; // newer than every stored record
+    gaps: Array<{ newest: unknown; oldest: unknown; count: number }>;
+  }>;
+}
+
+// See: src-tauri/src/gacha/plugin.rs
+export type PullReport =
+  | { diff: GachaRecordPullDiff }
+  | { repair: GachaRecordPullRepair };
+
+// Cancelled pulls keep the records already saved, and report them rather than an error
+export interface PullOutcome {
+  status: Extract<AccountPullStatus, "completed" | "cancelled">;
+  inserted: number; // newly saved to storage
+  report: PullReport | null; // null for an incremental or cancelled pull
+}
+
 export async function pullAllGachaRecords(
   facet: AccountFacet,
   uid: Account["uid"],
   payload: {
     gachaUrl: string;
-    gachaTypeAndLastQueryMappings: Record<
-      GenshinGachaRecord["gacha_type"] | StarRailGachaRecord["gacha_type"],
-      GenshinGachaRecord["id"] | StarRailGachaRecord["id"] | null
+    // Omitted: every gacha type, with the last queries derived from storage
+    gachaTypeAndLastQueryMappings?: Record<
+      | GenshinGachaRecord["gacha_type"]
+      | StarRailGachaRecord["gacha_type"]
+      | ZenlessZoneZeroGachaRecord["gacha_type"],
+      | GenshinGachaRecord["id"]
+      | StarRailGachaRecord["id"]
+      | ZenlessZoneZeroGachaRecord["id"]
+      | null
     >;
     eventChannel: string;
     saveToStorage?: boolean;
+    // Walk the whole server window and diff it against storage, saving nothing
+    dryRun?: boolean;
+    // Walk the whole server window and save the records missing from storage
+    repair?: boolean;
+    taskId?: string;
   }
-): Promise<void> {
+): Promise<PullOutcome> {
   return invoke("plugin:gacha|pull_all_gacha_records", {
     facet,
     uid,
@@ -36,6 +81,37 @@ export async function pullAllGachaRecords(
   });
 }
 
+// See: src-tauri/src/gacha/plugin.rs
+// Busy: skipped, a pull of the account was already running
+// Cancelled: the pull or the whole batch was cancelled before it completed
+export type AccountPullStatus = "completed" | "failed" | "busy" | "cancelled";
+
+export interface AccountPullSummary {
+  facet: AccountFacet;
+  uid: Account["uid"];
+  status: AccountPullStatus;
+  inserted: number;
+  error: { identifier: string; message: string } | null;
+}
+
+export type PullAllAccountsFragment =
+  | { account: { facet: AccountFacet; uid: Account["uid"] } }
+  | {
+      progress: {
+        facet: AccountFacet;
+        uid: Account["uid"];
+        fragment: unknown;
+      };
+    }
+  | { summary: AccountPullSummary[] };
+
+// Cancel with the task id "pullAll"
+export async function pullAllAccounts(
+  eventChannel: string
+): Promise<AccountPullSummary[]> {
+  return invoke("plugin:gacha|pull_all_accounts", { eventChannel });
+}
+
 export async function importGachaRecords(
   facet: AccountFacet,
   uid: Account["uid"],
@@ -44,20 +120,80 @@ export async function importGachaRecords(
   return invoke("plugin:gacha|import_gacha_records", { facet, uid, file });
 }
 
+// See: src-tauri/src/gacha/provider.rs
+// Classic: UIGF v2.2, SRGF, WWPF for Wuthering Waves, or UIGF v4 for Zenless Zone Zero
+// Uigf4: UIGF v4, all games but Wuthering Waves
+export type ExportFormat = "classic" | "uigf4";
+
 export async function exportGachaRecords(
   facet: AccountFacet,
   uid: Account["uid"],
+  directory: string,
+  format?: ExportFormat
+): Promise<string> {
+  return invoke("plugin:gacha|export_gacha_records", {
+    facet,
+    uid,
+    directory,
+    format,
+  });
+}
+
+// See: src-tauri/src/gacha/archive.rs
+export interface ArchivedPull {
+  pullId: string;
+  facet: AccountFacet;
+  uid: Account["uid"];
+  startTime: string;
+}
+
+export async function findResponseArchives(): Promise<ArchivedPull[]> {
+  return invoke("plugin:gacha|find_response_archives");
+}
+
+export async function exportResponseArchive(
+  pullId: ArchivedPull["pullId"],
   directory: string
 ): Promise<string> {
-  return invoke("plugin:gacha|export_gacha_records", { facet, uid, directory });
+  return invoke("plugin:gacha|export_response_archive", { pullId, directory });
+}
+
+export type TaskKind = "pull" | "pullAll" | "import" | "export";
+export type TaskStatus = "running" | "completed" | "failed" | "cancelled";
+
+// See: src-tauri/src/gacha/task.rs
+export interface Task {
+  id: string;
+  kind: TaskKind;
+  // Null for a task over every account
+  facet: AccountFacet | null;
+  uid: Account["uid"] | null;
+  status: TaskStatus;
+  progress: number;
+  error: string | null;
+  createdAt: string;
+  finishedAt: string | null;
+}
+
+export async function findTasks(): Promise<Task[]> {
+  return invoke("plugin:gacha|find_tasks");
+}
+
+export async function cancelTask(taskId: Task["id"]): Promise<boolean> {
+  return invoke("plugin:gacha|cancel_task", { taskId });
 }
 
 const PluginGacha = Object.freeze({
   findGameDataDirectories,
   findGachaUrl,
   pullAllGachaRecords,
+  pullAllAccounts,
   importGachaRecords,
   exportGachaRecords,
+  findResponseArchives,
+  exportResponseArchive,
+  findTasks,
+  cancelTask,
 });
 
 export default PluginGacha;
@@ -0,0 +1,64 @@
+import { AccountFacet } from "@/interfaces/account";
+import invoke from "@/utilities/invoke";
+
+// See: src-tauri/src/gacha/policy.rs
+
+export interface FetchPolicy {
+  pageSize: number;
+  pageDelayMillis: number;
+  burstSize: number;
+  burstPauseMillis: number;
+  requestTimeoutSecs: number;
+  maxRetries: number;
+  retryBaseDelayMillis: number;
+  retryMaxDelayMillis: number;
+  apiBaseUrl: string | null;
+  language: string | null;
+}
+
+export interface FetchPolicySettings {
+  default: FetchPolicy;
+  overrides: Partial<Record<AccountFacet, Partial<FetchPolicy>>>;
+}
+
+// See: src-tauri/src/http.rs
+
+export interface HttpClientConfig {
+  proxy: string | null;
+  rootCertificates: string[];
+  connectTimeoutSecs: number;
+  requestTimeoutSecs: number;
+  userAgent: string | null;
+}
+
+// See: src-tauri/src/gacha/archive.rs
+
+export interface ResponseArchiveSettings {
+  enabled: boolean;
+  maxPulls: number;
+}
+
+// See: src-tauri/src/settings.rs
+
+export interface Settings {
+  fetchPolicy: FetchPolicySettings;
+  http: HttpClientConfig;
+  responseArchive: ResponseArchiveSettings;
+  /** Custom database file, takes effect on the next launch */
+  databaseFile: string | null;
+}
+
+export async function getSettings(): Promise<Settings> {
+  return invoke("plugin:settings|get_settings");
+}
+
+export async function updateSettings(settings: Settings): Promise<Settings> {
+  return invoke("plugin:settings|update_settings", { settings });
+}
+
+const PluginSettings = Object.freeze({
+  getSettings,
+  updateSettings,
+});
+
+export default PluginSettings;
@@ -3,6 +3,7 @@ import {
   GenshinGachaRecord,
   StarRailGachaRecord,
   WutheringWavesGachaRecord,
+  ZenlessZoneZeroGachaRecord,
 } from "@/interfaces/gacha";
 import invoke from "@/utilities/invoke";
 
@@ -13,10 +14,62 @@ export type FindGachaRecordsPayload = {
   uid: AccountUid;
   gachaType?:
     | GenshinGachaRecord["gacha_type"]
-    | StarRailGachaRecord["gacha_type"];
+    | StarRailGachaRecord["gacha_type"]
+    | ZenlessZoneZeroGachaRecord["gacha_type"];
   limit?: number;
 };
 
+export type GachaRecordQuery = {
+  uid: AccountUid;
+  gachaTypes?: Array<string>;
+  /** Unix timestamp in seconds, inclusive */
+  startTime?: number;
+  /** Unix timestamp in seconds, exclusive */
+  endTime?: number;
+  ranks?: Array<number>;
+  itemTypes?: Array<string>;
+  names?: Array<string>;
+  order?: "ascending" | "descending";
+  cursor?: string | null;
+  limit?: number;
+};
+
+export type GachaRecordPage<T> = {
+  records: Array<T>;
+  nextCursor: string | null;
+};
+
+export type GachaRecordRankCount = {
+  gachaType: string;
+  rank: number;
+  count: number;
+};
+
+export type GachaRecordItemCount = {
+  itemId: string;
+  name: string;
+  itemType: string;
+  rank: number;
+  count: number;
+};
+
+export type GachaRecordTimeRange = {
+  firstTime: string | null;
+  lastTime: string | null;
+  count: number;
+};
+
+export type GachaRecordPeriod = "day" | "month";
+
+export type GachaRecordPeriodCount = {
+  period: string;
+  count: number;
+};
+
+export async function findDatabaseFile(): Promise<string> {
+  return invoke("plugin:storage|find_database_file");
+}
+
 export async function createAccount(
   payload: CreateAccountPayload
 ): Promise<Account> {
@@ -91,11 +144,20 @@ export async function findGachaRecords(
   facet: AccountFacet,
   payload: FindGachaRecordsPayload
 ): Promise<Array<WutheringWavesGachaRecord>>;
+export async function findGachaRecords(
+  facet: AccountFacet,
+  payload: FindGachaRecordsPayload
+): Promise<Array<ZenlessZoneZeroGachaRecord>>;
 export async function findGachaRecords(
   facet: AccountFacet,
   payload: FindGachaRecordsPayload
 ): Promise<
-  Array<GenshinGachaRecord | StarRailGachaRecord | WutheringWavesGachaRecord>
+  Array<
+    | GenshinGachaRecord
+    | StarRailGachaRecord
+    | WutheringWavesGachaRecord
+    | ZenlessZoneZeroGachaRecord
+  >
 > {
   return invoke(`plugin:storage|find_${facet}_gacha_records`, payload);
 }
@@ -112,16 +174,121 @@ export async function saveGachaRecords(
   facet: AccountFacet.StarRail,
   records: Array<WutheringWavesGachaRecord>
 ): Promise<number>;
+export async function saveGachaRecords(
+  facet: AccountFacet.ZenlessZoneZero,
+  records: Array<ZenlessZoneZeroGachaRecord>
+): Promise<number>;
 export async function saveGachaRecords(
   facet: AccountFacet,
   records: Array<
-    GenshinGachaRecord | StarRailGachaRecord | WutheringWavesGachaRecord
+    | GenshinGachaRecord
+    | StarRailGachaRecord
+    | WutheringWavesGachaRecord
+    | ZenlessZoneZeroGachaRecord
   >
 ): Promise<number> {
   return invoke(`plugin:storage|save_${facet}_gacha_records`, { records });
 }
 
+export async function queryGachaRecords(
+  facet: AccountFacet.Genshin,
+  query: GachaRecordQuery
+): Promise<GachaRecordPage<GenshinGachaRecord>>;
+export async function queryGachaRecords(
+  facet: AccountFacet.StarRail,
+  query: GachaRecordQuery
+): Promise<GachaRecordPage<StarRailGachaRecord>>;
+export async function queryGachaRecords(
+  facet: AccountFacet.WutheringWaves,
+  query: GachaRecordQuery
+): Promise<GachaRecordPage<WutheringWavesGachaRecord>>;
+export async function queryGachaRecords(
+  facet: AccountFacet.ZenlessZoneZero,
+  query: GachaRecordQuery
+): Promise<GachaRecordPage<ZenlessZoneZeroGachaRecord>>;
+export async function queryGachaRecords(
+  facet: AccountFacet,
+  query: GachaRecordQuery
+): Promise<
+  GachaRecordPage<
+    | GenshinGachaRecord
+    | StarRailGachaRecord
+    | WutheringWavesGachaRecord
+    | ZenlessZoneZeroGachaRecord
+  >
+> {
+  return invoke("plugin:storage|query_gacha_records", { facet, query });
+}
+
+export async function countGachaRecordsByRank(
+  facet: AccountFacet,
+  uid: AccountUid
+): Promise<Array<GachaRecordRankCount>> {
+  return invoke("plugin:storage|count_gacha_records_by_rank", { facet, uid });
+}
+
+export async function countGachaRecordsByItem(
+  facet: AccountFacet,
+  uid: AccountUid,
+  gachaType?: string
+): Promise<Array<GachaRecordItemCount>> {
+  return invoke("plugin:storage|count_gacha_records_by_item", {
+    facet,
+    uid,
+    gachaType,
+  });
+}
+
+export async function findGachaRecordsTimeRange(
+  facet: AccountFacet,
+  uid: AccountUid,
+  gachaType?: string
+): Promise<GachaRecordTimeRange> {
+  return invoke("plugin:storage|find_gacha_records_time_range", {
+    facet,
+    uid,
+    gachaType,
+  });
+}
+
+export async function countGachaRecordsByPeriod(
+  facet: AccountFacet,
+  uid: AccountUid,
+  period: GachaRecordPeriod,
+  gachaType?: string
+): Promise<Array<GachaRecordPeriodCount>> {
+  return invoke("plugin:storage|count_gacha_records_by_period", {
+    facet,
+    uid,
+    gachaType,
+    period,
+  });
+}
+
+/**
+ * The latest 5 star records of each pity track, at most limit of each.
+ * Keyed by the gacha type pulled, e.g. Genshin 400 is under 301.
+ */
+export async function findGachaRecordsLatestTopRank<
+  T extends
+    | GenshinGachaRecord
+    | StarRailGachaRecord
+    | WutheringWavesGachaRecord
+    | ZenlessZoneZeroGachaRecord
+>(
+  facet: AccountFacet,
+  uid: AccountUid,
+  limit: number
+): Promise<Record<string, Array<T>>> {
+  return invoke("plugin:storage|find_gacha_records_latest_top_rank", {
+    facet,
+    uid,
+    limit,
+  });
+}
+
 const PluginStorage = Object.freeze({
+  findDatabaseFile,
   createAccount,
   findAccounts,
   findAccount,
@@ -131,6 +298,12 @@ const PluginStorage = Object.freeze({
   deleteAccount,
   findGachaRecords,
   saveGachaRecords,
+  queryGachaRecords,
+  countGachaRecordsByRank,
+  countGachaRecordsByItem,
+  findGachaRecordsTimeRange,
+  countGachaRecordsByPeriod,
+  findGachaRecordsLatestTopRank,
 });
 
 export default PluginStorage;